@@ -0,0 +1,358 @@
+//! Granting and revoking badges.
+//!
+//! [`Badges::grant_once`] and [`Badges::grant_bulk`] first read the badges a
+//! user already has and skip users who hold the badge, so a grant script can
+//! be run again without awarding anything twice. This holds for badges that
+//! allow multiple grants too: these functions award a badge at most once per
+//! user, while [`Badges::grant`] always awards it.
+//!
+//! A grant can link to the post it is for with a [`BadgeReason`], which
+//! Discourse shows on the user's badge.
+//!
+//! ```rust,no_run
+//! use discourse_api::{badge_grants::BadgeReason, group_sync::MemberRef};
+//!
+//! async fn example_badge_grants() -> anyhow::Result<()> {
+//!     let client = discourse_api::Client::new_from_env();
+//!     let users = ["alice", "bob@example.com"].map(MemberRef::from);
+//!     let reason = BadgeReason::Post {
+//!         topic_id: 42,
+//!         post_number: 3,
+//!     };
+//!     for result in client.badges().grant_bulk(7, users, Some(&reason)).await? {
+//!         println!("{:?}: {:?}", result.user, result.outcome);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use crate::{badges::Badges, group_sync::MemberRef, methods::parse_response};
+
+/// What a badge was granted for.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum BadgeReason {
+    /// A post, by topic and post number.
+    Post { topic_id: i64, post_number: i64 },
+    /// A link to a topic or post on the site.
+    Url(String),
+}
+
+impl BadgeReason {
+    /// The link sent as the grant's reason. Discourse only accepts links to
+    /// its own topics and posts.
+    pub fn url(&self, base_url: &str) -> String {
+        match self {
+            BadgeReason::Post {
+                topic_id,
+                post_number,
+            } => format!(
+                "{}/t/{}/{}",
+                base_url.trim_end_matches('/'),
+                topic_id,
+                post_number
+            ),
+            BadgeReason::Url(url) => url.clone(),
+        }
+    }
+}
+
+/// A badge granted to a user.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct UserBadge {
+    pub id: i64,
+    pub badge_id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub granted_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub granted_by_id: Option<i64>,
+    /// The post the badge was granted for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_number: Option<i64>,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(serde::Deserialize)]
+struct UserBadgesPage {
+    #[serde(default)]
+    user_badges: Vec<UserBadge>,
+}
+
+#[derive(serde::Deserialize)]
+struct GrantResponse {
+    user_badge: UserBadge,
+}
+
+#[derive(serde::Serialize)]
+struct GrantRequest<'a> {
+    username: &'a str,
+    badge_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+/// What happened to a user's badge.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum UserBadgeOutcome {
+    /// The badge was granted.
+    Granted { user_badge_id: i64 },
+    /// The user already had the badge.
+    AlreadyGranted { user_badge_id: i64 },
+    /// The badge was taken back, every grant of it.
+    Revoked { user_badge_ids: Vec<i64> },
+    /// The user did not have the badge.
+    NotHeld,
+    /// No user has this username.
+    UnknownUser,
+    /// No user has this email address.
+    UnknownEmail,
+    /// The user appears earlier in the input.
+    Duplicate { first_index: usize },
+    /// Discourse rejected the request.
+    Failed { error: String },
+}
+
+/// The outcome for one user of a bulk grant or revoke.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct UserBadgeResult {
+    /// The 0-based position of the user in the input.
+    pub index: usize,
+    pub user: MemberRef,
+    /// The username the user was found under.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(flatten)]
+    pub outcome: UserBadgeOutcome,
+}
+
+/// The outcome for an error about one user. The badge is known to exist, so
+/// a `404` is about the user.
+fn user_outcome(err: &crate::types::error::Error) -> UserBadgeOutcome {
+    match err.status() {
+        Some(reqwest::StatusCode::NOT_FOUND) => UserBadgeOutcome::UnknownUser,
+        _ => UserBadgeOutcome::Failed {
+            error: err.to_string(),
+        },
+    }
+}
+
+impl Badges {
+    /// Fail unless `badge_id` names an enabled badge.
+    async fn check_badge(&self, badge_id: i64) -> Result<(), crate::types::error::Error> {
+        let mut req = self.client.client.request(
+            http::Method::GET,
+            format!("{}/badges/{}.json", self.client.base_url, badge_id),
+        );
+        req = req.bearer_auth(&self.client.token);
+        match crate::methods::bytes_response(req.send().await?).await {
+            Ok(_) => Ok(()),
+            Err(err) if err.status() == Some(reqwest::StatusCode::NOT_FOUND) => {
+                Err(crate::types::error::Error::InvalidRequest(format!(
+                    "badge {} does not exist or is disabled",
+                    badge_id
+                )))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// The badges a user has, each grant separately.
+    pub async fn user_badges(
+        &self,
+        username: &str,
+    ) -> Result<Vec<UserBadge>, crate::types::error::Error> {
+        let mut req = self.client.client.request(
+            http::Method::GET,
+            format!("{}/user-badges/{}.json", self.client.base_url, username),
+        );
+        req = req.bearer_auth(&self.client.token);
+        req = req.query(&[("grouped", "false")]);
+        let page: UserBadgesPage = parse_response(req.send().await?).await?;
+        Ok(page.user_badges)
+    }
+
+    /// Grant a badge to a user, even if they already have it.
+    pub async fn grant(
+        &self,
+        badge_id: i64,
+        username: &str,
+        reason: Option<&BadgeReason>,
+    ) -> Result<UserBadge, crate::types::error::Error> {
+        let mut req = self.client.client.request(
+            http::Method::POST,
+            format!("{}/user_badges.json", self.client.base_url),
+        );
+        req = req.bearer_auth(&self.client.token);
+        req = req.json(&GrantRequest {
+            username,
+            badge_id,
+            reason: reason.map(|reason| reason.url(&self.client.base_url)),
+        });
+        let granted: GrantResponse = parse_response(req.send().await?).await?;
+        Ok(granted.user_badge)
+    }
+
+    /// Grant a badge to a user unless they already have it.
+    pub async fn grant_once(
+        &self,
+        badge_id: i64,
+        username: &str,
+        reason: Option<&BadgeReason>,
+    ) -> Result<UserBadgeOutcome, crate::types::error::Error> {
+        let held = self.user_badges(username).await?;
+        if let Some(user_badge) = held.iter().find(|held| held.badge_id == badge_id) {
+            return Ok(UserBadgeOutcome::AlreadyGranted {
+                user_badge_id: user_badge.id,
+            });
+        }
+        let user_badge = self.grant(badge_id, username, reason).await?;
+        Ok(UserBadgeOutcome::Granted {
+            user_badge_id: user_badge.id,
+        })
+    }
+
+    /// Take back one grant of a badge.
+    pub async fn revoke_user_badge(
+        &self,
+        user_badge_id: i64,
+    ) -> Result<(), crate::types::error::Error> {
+        let mut req = self.client.client.request(
+            http::Method::DELETE,
+            format!(
+                "{}/user_badges/{}.json",
+                self.client.base_url, user_badge_id
+            ),
+        );
+        req = req.bearer_auth(&self.client.token);
+        crate::methods::bytes_response(req.send().await?)
+            .await
+            .map(|_| ())
+    }
+
+    /// Take a badge back from a user, every grant of it.
+    pub async fn revoke(
+        &self,
+        badge_id: i64,
+        username: &str,
+    ) -> Result<UserBadgeOutcome, crate::types::error::Error> {
+        let mut user_badge_ids = vec![];
+        for user_badge in self.user_badges(username).await? {
+            if user_badge.badge_id == badge_id {
+                self.revoke_user_badge(user_badge.id).await?;
+                user_badge_ids.push(user_badge.id);
+            }
+        }
+        if user_badge_ids.is_empty() {
+            Ok(UserBadgeOutcome::NotHeld)
+        } else {
+            Ok(UserBadgeOutcome::Revoked { user_badge_ids })
+        }
+    }
+
+    /// Grant a badge to every user who does not have it yet.
+    ///
+    /// The badge is checked once before any user. After that, an error about
+    /// a user, from looking up their email address to an exhausted rate
+    /// limit, is reported as their outcome and the others are still granted
+    /// the badge; running it again skips the users granted so far.
+    pub async fn grant_bulk<I>(
+        &self,
+        badge_id: i64,
+        users: I,
+        reason: Option<&BadgeReason>,
+    ) -> Result<Vec<UserBadgeResult>, crate::types::error::Error>
+    where
+        I: IntoIterator<Item = MemberRef>,
+    {
+        self.check_badge(badge_id).await?;
+        Ok(self
+            .bulk(users, |username| async move {
+                self.grant_once(badge_id, &username, reason).await
+            })
+            .await)
+    }
+
+    /// Take a badge back from every listed user who has it. Errors are
+    /// reported per user as with [`Badges::grant_bulk`].
+    pub async fn revoke_bulk<I>(
+        &self,
+        badge_id: i64,
+        users: I,
+    ) -> Result<Vec<UserBadgeResult>, crate::types::error::Error>
+    where
+        I: IntoIterator<Item = MemberRef>,
+    {
+        Ok(self
+            .bulk(users, |username| async move {
+                self.revoke(badge_id, &username).await
+            })
+            .await)
+    }
+
+    async fn bulk<I, F, Fut>(&self, users: I, apply: F) -> Vec<UserBadgeResult>
+    where
+        I: IntoIterator<Item = MemberRef>,
+        F: Fn(String) -> Fut,
+        Fut: std::future::Future<Output = Result<UserBadgeOutcome, crate::types::error::Error>>,
+    {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        let mut results = vec![];
+        for (index, user) in users.into_iter().enumerate() {
+            let username = match &user {
+                MemberRef::Username(username) => Ok(Some(username.clone())),
+                MemberRef::Email(email) => {
+                    crate::methods::find_username_by_email(&self.client, email).await
+                }
+            };
+            let (username, outcome) = match username {
+                Err(err) => (
+                    None,
+                    UserBadgeOutcome::Failed {
+                        error: err.to_string(),
+                    },
+                ),
+                Ok(None) => (None, UserBadgeOutcome::UnknownEmail),
+                Ok(Some(username)) => {
+                    let outcome = match seen.get(&username.to_lowercase()) {
+                        Some(first_index) => UserBadgeOutcome::Duplicate {
+                            first_index: *first_index,
+                        },
+                        None => {
+                            seen.insert(username.to_lowercase(), index);
+                            apply(username.clone())
+                                .await
+                                .unwrap_or_else(|err| user_outcome(&err))
+                        }
+                    };
+                    (Some(username), outcome)
+                }
+            };
+            results.push(UserBadgeResult {
+                index,
+                user,
+                username,
+                outcome,
+            });
+        }
+        results
+    }
+}
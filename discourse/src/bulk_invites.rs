@@ -0,0 +1,435 @@
+//! Bulk invitations.
+//!
+//! [`Invites::create_bulk`] sends one invite per row, skipping addresses that
+//! repeat or already belong to a user, pacing requests to stay under the API
+//! rate limit, and reports what happened to each row.
+//!
+//! ```rust,no_run
+//! use discourse_api::bulk_invites::{results_csv, BulkInviteOptions};
+//!
+//! async fn example_bulk_invites() -> anyhow::Result<()> {
+//!     let client = discourse_api::Client::new_from_env();
+//!     let csv = std::fs::read_to_string("cohort.csv")?;
+//!     let results = client
+//!         .invites()
+//!         .create_bulk_from_csv(&csv, BulkInviteOptions::default())
+//!         .await?;
+//!     std::fs::write("cohort-results.csv", results_csv(&results))?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::{errors::ErrorExt, invites::Invites};
+
+/// A person to invite.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Debug,
+    Clone,
+    Default,
+    schemars :: JsonSchema,
+)]
+pub struct InviteRow {
+    pub email: String,
+    /// Names of the groups to add the user to when they accept.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+    /// The topic to take the user to when they accept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_message: Option<String>,
+}
+
+/// Why a CSV file of invites could not be read.
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum InviteCsvError {
+    /// The header has no `email` column.
+    #[error("missing `email` column")]
+    MissingEmailColumn,
+    /// A quoted field is not closed.
+    #[error("line {0}: unterminated quoted field")]
+    UnterminatedQuote(usize),
+    /// A `topic_id` is not a number.
+    #[error("line {line}: invalid topic_id `{value}`")]
+    InvalidTopicId { line: usize, value: String },
+}
+
+/// What happened to a row.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum InviteOutcome {
+    /// The invite was created.
+    Invited { invite_id: i64, link: String },
+    /// A user already has this email address.
+    ExistingUser { username: String },
+    /// The address appears on an earlier row.
+    Duplicate { first_row: usize },
+    /// The address is not an email address.
+    InvalidEmail,
+    /// Discourse rejected the invite.
+    Failed { error: String },
+    /// The run stopped at an earlier row.
+    NotAttempted,
+}
+
+impl InviteOutcome {
+    fn name(&self) -> &'static str {
+        match self {
+            InviteOutcome::Invited { .. } => "invited",
+            InviteOutcome::ExistingUser { .. } => "existing_user",
+            InviteOutcome::Duplicate { .. } => "duplicate",
+            InviteOutcome::InvalidEmail => "invalid_email",
+            InviteOutcome::Failed { .. } => "failed",
+            InviteOutcome::NotAttempted => "not_attempted",
+        }
+    }
+}
+
+/// The outcome of a row.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct InviteResult {
+    /// The 1-based position of the row in the input.
+    pub row: usize,
+    pub email: String,
+    #[serde(flatten)]
+    pub outcome: InviteOutcome,
+}
+
+/// How [`Invites::create_bulk`] sends invites.
+#[derive(Clone, Debug)]
+pub struct BulkInviteOptions {
+    /// Look up each address among users first and skip existing ones. Needs
+    /// an admin API key.
+    pub skip_existing_users: bool,
+    /// The least time between two requests. Discourse allows 60 admin API
+    /// requests a minute by default.
+    pub min_interval: Duration,
+    /// Create the invites without sending their emails.
+    pub skip_email: bool,
+    /// When the invites expire, e.g. `2026-12-31T00:00:00Z`.
+    pub expires_at: Option<String>,
+}
+
+impl Default for BulkInviteOptions {
+    fn default() -> Self {
+        BulkInviteOptions {
+            skip_existing_users: true,
+            min_interval: Duration::from_secs(1),
+            skip_email: false,
+            expires_at: None,
+        }
+    }
+}
+
+/// Split CSV text into records, keeping the line each record starts on.
+fn csv_records(csv: &str) -> Result<Vec<(usize, Vec<String>)>, InviteCsvError> {
+    let csv = csv.strip_prefix('\u{feff}').unwrap_or(csv);
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start_line = 1;
+    let mut chars = csv.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push((start_line, std::mem::take(&mut record)));
+                line += 1;
+                start_line = line;
+            }
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    if quoted {
+        return Err(InviteCsvError::UnterminatedQuote(start_line));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push((start_line, record));
+    }
+    Ok(records
+        .into_iter()
+        .filter(|(_, record)| record.iter().any(|f| !f.trim().is_empty()))
+        .collect())
+}
+
+/// Read invite rows from CSV with an `email` column and optional `groups`,
+/// `topic_id` and `custom_message` columns. Group names are separated by `;`
+/// or `,`.
+pub fn parse_invite_csv(csv: &str) -> Result<Vec<InviteRow>, InviteCsvError> {
+    let mut records = csv_records(csv)?.into_iter();
+    let header: Vec<String> = records
+        .next()
+        .map(|(_, header)| {
+            header
+                .iter()
+                .map(|name| name.trim().to_lowercase())
+                .collect()
+        })
+        .unwrap_or_default();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let email = column("email").ok_or(InviteCsvError::MissingEmailColumn)?;
+    let (groups, topic_id, custom_message) = (
+        column("groups"),
+        column("topic_id"),
+        column("custom_message"),
+    );
+
+    records
+        .map(|(line, record)| {
+            let field = |i: Option<usize>| {
+                i.and_then(|i| record.get(i))
+                    .map(|v| v.trim())
+                    .filter(|v| !v.is_empty())
+            };
+            Ok(InviteRow {
+                email: field(Some(email)).unwrap_or_default().to_string(),
+                groups: field(groups)
+                    .map(|g| {
+                        g.split([';', ','])
+                            .map(|g| g.trim().to_string())
+                            .filter(|g| !g.is_empty())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                topic_id: field(topic_id)
+                    .map(|v| {
+                        v.parse().map_err(|_| InviteCsvError::InvalidTopicId {
+                            line,
+                            value: v.to_string(),
+                        })
+                    })
+                    .transpose()?,
+                custom_message: field(custom_message).map(|v| v.to_string()),
+            })
+        })
+        .collect()
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Write results as CSV with `row,email,outcome,detail` columns.
+pub fn results_csv(results: &[InviteResult]) -> String {
+    let mut report = String::from("row,email,outcome,detail\n");
+    for result in results {
+        let detail = match &result.outcome {
+            InviteOutcome::Invited { link, .. } => link.clone(),
+            InviteOutcome::ExistingUser { username } => username.clone(),
+            InviteOutcome::Duplicate { first_row } => format!("same as row {}", first_row),
+            InviteOutcome::InvalidEmail => String::new(),
+            InviteOutcome::Failed { error } => error.clone(),
+            InviteOutcome::NotAttempted => String::new(),
+        };
+        let fields = [
+            result.row.to_string(),
+            result.email.clone(),
+            result.outcome.name().to_string(),
+            detail,
+        ];
+        report.push_str(&itertools::join(fields.iter().map(|f| csv_field(f)), ","));
+        report.push('\n');
+    }
+    report
+}
+
+fn is_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !email.contains(char::is_whitespace)
+                && !domain.contains('@')
+        }
+        None => false,
+    }
+}
+
+/// The message of a failed request, preferring the first of Discourse's
+/// `errors`.
+fn failure(err: &crate::types::error::Error) -> String {
+    match err {
+        crate::types::error::Error::Server { body, .. } => {
+            serde_json::from_str::<serde_json::Value>(body)
+                .ok()
+                .and_then(|v| {
+                    v.pointer("/errors/0")
+                        .and_then(|e| e.as_str())
+                        .map(|e| e.to_string())
+                })
+                .unwrap_or_else(|| err.to_string())
+        }
+        _ => err.to_string(),
+    }
+}
+
+/// Spaces requests at least `min_interval` apart.
+struct Pacer {
+    min_interval: Duration,
+    next: Option<Instant>,
+}
+
+impl Pacer {
+    async fn wait(&mut self) {
+        if let Some(next) = self.next {
+            let now = Instant::now();
+            if next > now {
+                tokio::time::sleep(next - now).await;
+            }
+        }
+        self.next = Some(Instant::now() + self.min_interval);
+    }
+}
+
+impl Invites {
+    /// Invite everyone in `rows` and report the outcome of each row.
+    ///
+    /// A rejected invite is reported and the next row is sent. The run stops
+    /// when a user lookup fails, the API key is refused or a request stays
+    /// rate limited after the client's rate limiter has waited it out; that
+    /// row is reported as failed and the rows after it as not attempted.
+    pub async fn create_bulk<I>(
+        &self,
+        rows: I,
+        options: BulkInviteOptions,
+    ) -> Result<Vec<InviteResult>, crate::types::error::Error>
+    where
+        I: IntoIterator<Item = InviteRow>,
+    {
+        let mut pacer = Pacer {
+            min_interval: options.min_interval,
+            next: None,
+        };
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        let mut results = vec![];
+
+        let mut rows = rows.into_iter().enumerate();
+        for (i, row) in rows.by_ref() {
+            let number = i + 1;
+            let email = row.email.trim().to_string();
+            let key = email.to_lowercase();
+            let mut result = |outcome| {
+                results.push(InviteResult {
+                    row: number,
+                    email: email.clone(),
+                    outcome,
+                })
+            };
+
+            if !is_email(&email) {
+                result(InviteOutcome::InvalidEmail);
+                continue;
+            }
+            if let Some(first_row) = seen.get(&key) {
+                result(InviteOutcome::Duplicate {
+                    first_row: *first_row,
+                });
+                continue;
+            }
+            seen.insert(key, number);
+
+            if options.skip_existing_users {
+                pacer.wait().await;
+                match crate::methods::find_username_by_email(&self.client, &email).await {
+                    Ok(Some(username)) => {
+                        result(InviteOutcome::ExistingUser { username });
+                        continue;
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        result(InviteOutcome::Failed {
+                            error: failure(&err),
+                        });
+                        break;
+                    }
+                }
+            }
+
+            let body = crate::types::CreateInviteRequestBody {
+                email: Some(email.clone()),
+                skip_email: options.skip_email,
+                custom_message: row.custom_message.clone(),
+                max_redemptions_allowed: None,
+                topic_id: row.topic_id,
+                group_ids: None,
+                group_names: (!row.groups.is_empty()).then(|| row.groups.join(",")),
+                expires_at: options.expires_at.clone(),
+            };
+            pacer.wait().await;
+            match self.create(&body).await {
+                Ok(invite) => result(InviteOutcome::Invited {
+                    invite_id: invite.id,
+                    link: invite.link,
+                }),
+                Err(err)
+                    if err.rate_limited().is_some()
+                        || matches!(
+                            err.status(),
+                            Some(
+                                reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN
+                            )
+                        ) =>
+                {
+                    result(InviteOutcome::Failed {
+                        error: failure(&err),
+                    });
+                    break;
+                }
+                Err(err) => result(InviteOutcome::Failed {
+                    error: failure(&err),
+                }),
+            }
+        }
+        results.extend(rows.map(|(i, row)| InviteResult {
+            row: i + 1,
+            email: row.email.trim().to_string(),
+            outcome: InviteOutcome::NotAttempted,
+        }));
+        Ok(results)
+    }
+
+    /// Invite everyone in a CSV file; see [`parse_invite_csv`] for the
+    /// columns.
+    pub async fn create_bulk_from_csv(
+        &self,
+        csv: &str,
+        options: BulkInviteOptions,
+    ) -> Result<Vec<InviteResult>, crate::types::error::Error> {
+        let rows = parse_invite_csv(csv)
+            .map_err(|err| crate::types::error::Error::InvalidRequest(err.to_string()))?;
+        self.create_bulk(rows, options).await
+    }
+}
@@ -0,0 +1,576 @@
+//! Category hierarchy and group permissions.
+//!
+//! Discourse returns categories as a flat list in which subcategories point at
+//! their parent with `parent_category_id`. A [`CategoryTree`] puts them back
+//! together, finds categories by slug path, the way they appear in URLs, and
+//! answers what a group may do in a category.
+//!
+//! Group permissions are only returned by a category's own page, so
+//! [`Categories::tree`] leaves them out and [`Categories::tree_with_permissions`]
+//! fetches them for every category.
+//!
+//! The generated [`crate::types::Permissions`] only has fields for the
+//! `everyone` and `staff` groups. [`CategoryPermissions`] holds any group, and
+//! [`Categories::create_category_with_permissions`] and
+//! [`Categories::update_category_with_permissions`] send it in place of the
+//! body's `permissions`.
+//!
+//! ```rust,no_run
+//! async fn example_category_tree() -> anyhow::Result<()> {
+//!     let client = discourse_api::Client::new_from_env();
+//!     let tree = client.categories().tree_with_permissions().await?;
+//!     if let Some(backend) = tree.by_slug_path("engineering/backend") {
+//!         println!(
+//!             "platform-team can create topics in {}: {}",
+//!             backend.name,
+//!             tree.can_create_topics(backend.id, "platform-team")
+//!         );
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::{categories::Categories, methods::parse_response};
+
+/// A group's access to a category, stored as a number by Discourse.
+///
+/// Codes this crate does not know are kept as [`PermissionType::Other`] and
+/// grant nothing in the permission queries of [`CategoryTree`].
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Debug,
+    Clone,
+    Copy,
+    schemars :: JsonSchema,
+)]
+#[serde(from = "i64", into = "i64")]
+pub enum PermissionType {
+    /// Create topics, reply and see.
+    Full,
+    /// Reply and see.
+    CreatePost,
+    /// See only.
+    Readonly,
+    /// A code this crate does not know.
+    Other(i64),
+}
+
+impl PermissionType {
+    pub fn can_create_topics(self) -> bool {
+        self == PermissionType::Full
+    }
+
+    pub fn can_reply(self) -> bool {
+        self <= PermissionType::CreatePost
+    }
+
+    pub fn can_see(self) -> bool {
+        self <= PermissionType::Readonly
+    }
+}
+
+impl From<i64> for PermissionType {
+    fn from(code: i64) -> Self {
+        match code {
+            1 => PermissionType::Full,
+            2 => PermissionType::CreatePost,
+            3 => PermissionType::Readonly,
+            code => PermissionType::Other(code),
+        }
+    }
+}
+
+impl From<PermissionType> for i64 {
+    fn from(permission: PermissionType) -> Self {
+        match permission {
+            PermissionType::Full => 1,
+            PermissionType::CreatePost => 2,
+            PermissionType::Readonly => 3,
+            PermissionType::Other(code) => code,
+        }
+    }
+}
+
+impl std::fmt::Display for PermissionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PermissionType::Full => write!(f, "full"),
+            PermissionType::CreatePost => write!(f, "create_post"),
+            PermissionType::Readonly => write!(f, "readonly"),
+            PermissionType::Other(code) => write!(f, "{}", code),
+        }
+    }
+}
+
+impl std::str::FromStr for PermissionType {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(PermissionType::Full),
+            "create_post" => Ok(PermissionType::CreatePost),
+            "readonly" => Ok(PermissionType::Readonly),
+            s => s
+                .parse::<i64>()
+                .map(PermissionType::from)
+                .map_err(|_| format!("unknown permission type {}", s)),
+        }
+    }
+}
+
+/// A group's permission in a category, as on the category's page.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct GroupPermission {
+    pub permission_type: PermissionType,
+    pub group_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<i64>,
+}
+
+impl From<&crate::types::GroupPermissions> for GroupPermission {
+    fn from(permission: &crate::types::GroupPermissions) -> Self {
+        GroupPermission {
+            permission_type: permission.permission_type.into(),
+            group_name: permission.group_name.clone(),
+            group_id: Some(permission.group_id),
+        }
+    }
+}
+
+/// Category permissions by group name, to create or update a category with.
+/// The `everyone` group stands for all users.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Debug,
+    Clone,
+    Default,
+    schemars :: JsonSchema,
+)]
+pub struct CategoryPermissions {
+    #[serde(flatten)]
+    pub groups: BTreeMap<String, PermissionType>,
+}
+
+impl CategoryPermissions {
+    /// The same permission for all users.
+    pub fn everyone(permission: PermissionType) -> Self {
+        CategoryPermissions::default().group("everyone", permission)
+    }
+
+    /// Add or replace a group's permission.
+    pub fn group(mut self, name: impl Into<String>, permission: PermissionType) -> Self {
+        self.groups.insert(name.into(), permission);
+        self
+    }
+}
+
+impl<S: Into<String>> FromIterator<(S, PermissionType)> for CategoryPermissions {
+    fn from_iter<I: IntoIterator<Item = (S, PermissionType)>>(iter: I) -> Self {
+        CategoryPermissions {
+            groups: iter
+                .into_iter()
+                .map(|(name, permission)| (name.into(), permission))
+                .collect(),
+        }
+    }
+}
+
+impl From<&crate::types::Permissions> for CategoryPermissions {
+    fn from(permissions: &crate::types::Permissions) -> Self {
+        [
+            ("everyone", permissions.everyone),
+            ("staff", permissions.staff),
+        ]
+        .into_iter()
+        .filter_map(|(name, code)| Some((name, PermissionType::from(code?))))
+        .collect()
+    }
+}
+
+/// A category in a [`CategoryTree`].
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct CategoryNode {
+    pub id: i64,
+    pub name: String,
+    pub slug: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_category_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<i64>,
+    #[serde(default)]
+    pub read_restricted: bool,
+    /// `None` until loaded, see [`Categories::load_permissions`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_permissions: Option<Vec<GroupPermission>>,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+impl From<&crate::types::GetSiteResponseCategories> for CategoryNode {
+    fn from(category: &crate::types::GetSiteResponseCategories) -> Self {
+        CategoryNode {
+            id: category.id,
+            name: category.name.clone(),
+            slug: category.slug.clone(),
+            parent_category_id: category.parent_category_id,
+            position: Some(category.position),
+            read_restricted: category.read_restricted,
+            group_permissions: None,
+            other: Default::default(),
+        }
+    }
+}
+
+impl From<&crate::types::Categories> for CategoryNode {
+    /// The parent is not part of a category list entry; [`CategoryTree::from_list`]
+    /// fills it in from the parents' `subcategory_ids`.
+    fn from(category: &crate::types::Categories) -> Self {
+        CategoryNode {
+            id: category.id,
+            name: category.name.clone(),
+            slug: category.slug.clone(),
+            parent_category_id: None,
+            position: Some(category.position),
+            read_restricted: category.read_restricted,
+            group_permissions: None,
+            other: Default::default(),
+        }
+    }
+}
+
+/// Categories arranged by parent.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CategoryTree {
+    categories: BTreeMap<i64, CategoryNode>,
+    children: BTreeMap<Option<i64>, Vec<i64>>,
+}
+
+impl CategoryTree {
+    /// Arrange categories by `parent_category_id`. A category whose parent is
+    /// missing is treated as top level. Siblings are ordered by position, then
+    /// name.
+    pub fn new<I>(categories: I) -> Self
+    where
+        I: IntoIterator<Item = CategoryNode>,
+    {
+        let categories: BTreeMap<i64, CategoryNode> = categories
+            .into_iter()
+            .map(|category| (category.id, category))
+            .collect();
+        let mut children: BTreeMap<Option<i64>, Vec<i64>> = BTreeMap::new();
+        for category in categories.values() {
+            let parent = category
+                .parent_category_id
+                .filter(|parent| categories.contains_key(parent));
+            children.entry(parent).or_default().push(category.id);
+        }
+        for siblings in children.values_mut() {
+            siblings.sort_by_key(|id| {
+                let category = &categories[id];
+                (category.position.unwrap_or(i64::MAX), category.name.clone())
+            });
+        }
+        CategoryTree {
+            categories,
+            children,
+        }
+    }
+
+    /// Build the tree from the categories of `site().get()`.
+    pub fn from_site(site: &crate::types::GetSiteResponse) -> Self {
+        Self::new(site.categories.iter().map(CategoryNode::from))
+    }
+
+    /// Build the tree from `categories().list()`. Subcategories are only listed
+    /// when it is called with `include_subcategories`.
+    pub fn from_list(list: &crate::types::ListCategoriesResponse) -> Self {
+        let mut nodes = vec![];
+        let mut parents = BTreeMap::new();
+        for category in &list.category_list.categories {
+            nodes.push(CategoryNode::from(category));
+            for id in category.subcategory_ids.iter().filter_map(|id| id.as_i64()) {
+                parents.insert(id, category.id);
+            }
+            for subcategory in category.subcategory_list.iter().flatten() {
+                if let Ok(node) = serde_json::from_value::<CategoryNode>(subcategory.clone()) {
+                    parents.insert(node.id, category.id);
+                    nodes.push(node);
+                }
+            }
+        }
+        for node in &mut nodes {
+            if let Some(parent) = parents.get(&node.id) {
+                node.parent_category_id = Some(*parent);
+            }
+        }
+        Self::new(nodes)
+    }
+
+    pub fn get(&self, id: i64) -> Option<&CategoryNode> {
+        self.categories.get(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.categories.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.categories.is_empty()
+    }
+
+    /// Every category, in the order of the tree: each parent before its
+    /// children.
+    pub fn iter(&self) -> impl Iterator<Item = &CategoryNode> + '_ {
+        self.walk().into_iter().map(|(_, category)| category)
+    }
+
+    /// Every category with its depth, top level categories being at depth 0.
+    pub fn walk(&self) -> Vec<(usize, &CategoryNode)> {
+        let mut out = vec![];
+        let mut stack: Vec<(usize, i64)> = self
+            .child_ids(None)
+            .iter()
+            .rev()
+            .map(|id| (0, *id))
+            .collect();
+        while let Some((depth, id)) = stack.pop() {
+            out.push((depth, &self.categories[&id]));
+            stack.extend(
+                self.child_ids(Some(id))
+                    .iter()
+                    .rev()
+                    .map(|id| (depth + 1, *id)),
+            );
+        }
+        out
+    }
+
+    fn child_ids(&self, parent: Option<i64>) -> &[i64] {
+        self.children.get(&parent).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn roots(&self) -> Vec<&CategoryNode> {
+        self.nodes(self.child_ids(None))
+    }
+
+    pub fn children(&self, id: i64) -> Vec<&CategoryNode> {
+        self.nodes(self.child_ids(Some(id)))
+    }
+
+    fn nodes(&self, ids: &[i64]) -> Vec<&CategoryNode> {
+        ids.iter().map(|id| &self.categories[id]).collect()
+    }
+
+    pub fn parent(&self, id: i64) -> Option<&CategoryNode> {
+        self.categories
+            .get(&id)?
+            .parent_category_id
+            .and_then(|parent| self.categories.get(&parent))
+    }
+
+    /// The parents of a category, nearest first.
+    pub fn ancestors(&self, id: i64) -> Vec<&CategoryNode> {
+        let mut out: Vec<&CategoryNode> = vec![];
+        let mut current = id;
+        while let Some(parent) = self.parent(current) {
+            // Guard against a cycle in bad data.
+            if parent.id == id || out.iter().any(|seen| seen.id == parent.id) {
+                break;
+            }
+            out.push(parent);
+            current = parent.id;
+        }
+        out
+    }
+
+    /// The slugs from the top level category down to this one, joined by `/`.
+    pub fn slug_path(&self, id: i64) -> Option<String> {
+        let category = self.categories.get(&id)?;
+        let mut slugs: Vec<&str> = self
+            .ancestors(id)
+            .iter()
+            .map(|parent| parent.slug.as_str())
+            .collect();
+        slugs.reverse();
+        slugs.push(&category.slug);
+        Some(slugs.join("/"))
+    }
+
+    /// Find a category by its slugs from the top level down, like
+    /// `engineering/backend`. Slugs match case-insensitively.
+    pub fn by_slug_path(&self, path: &str) -> Option<&CategoryNode> {
+        let mut parent = None;
+        let mut found = None;
+        for slug in path.split('/').filter(|slug| !slug.is_empty()) {
+            let category = self
+                .child_ids(parent)
+                .iter()
+                .map(|id| &self.categories[id])
+                .find(|category| category.slug.eq_ignore_ascii_case(slug))?;
+            parent = Some(category.id);
+            found = Some(category);
+        }
+        found
+    }
+
+    /// The permission a group has in a category, directly or through
+    /// `everyone`, or `None` when it has none or permissions are not loaded.
+    pub fn permission(&self, id: i64, group: &str) -> Option<PermissionType> {
+        self.categories
+            .get(&id)?
+            .group_permissions
+            .as_ref()?
+            .iter()
+            .filter(|permission| {
+                permission.group_name.eq_ignore_ascii_case(group)
+                    || permission.group_name == "everyone"
+            })
+            .map(|permission| permission.permission_type)
+            .min()
+    }
+
+    pub fn can_create_topics(&self, id: i64, group: &str) -> bool {
+        self.permission(id, group)
+            .is_some_and(PermissionType::can_create_topics)
+    }
+
+    pub fn can_reply(&self, id: i64, group: &str) -> bool {
+        self.permission(id, group)
+            .is_some_and(PermissionType::can_reply)
+    }
+
+    pub fn can_see(&self, id: i64, group: &str) -> bool {
+        self.permission(id, group)
+            .is_some_and(PermissionType::can_see)
+    }
+
+    /// The categories a group can create topics in.
+    pub fn postable_by(&self, group: &str) -> Vec<&CategoryNode> {
+        self.iter()
+            .filter(|category| self.can_create_topics(category.id, group))
+            .collect()
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct SiteCategories {
+    #[serde(default)]
+    categories: Vec<CategoryNode>,
+}
+
+impl Categories {
+    /// Fetch every category visible to the API user as a tree, without group
+    /// permissions.
+    ///
+    /// Reads `site.json` leniently rather than through `site().get()`, so that
+    /// fields this crate does not know about cannot fail the request.
+    pub async fn tree(&self) -> Result<CategoryTree, crate::types::error::Error> {
+        let mut req = self.client.client.request(
+            http::Method::GET,
+            format!("{}/site.json", self.client.base_url),
+        );
+        req = req.bearer_auth(&self.client.token);
+        let site: SiteCategories = parse_response(req.send().await?).await?;
+        Ok(CategoryTree::new(site.categories))
+    }
+
+    /// Fetch the tree and the group permissions of every category, one
+    /// request per category.
+    pub async fn tree_with_permissions(&self) -> Result<CategoryTree, crate::types::error::Error> {
+        let mut tree = self.tree().await?;
+        self.load_permissions(&mut tree).await?;
+        Ok(tree)
+    }
+
+    /// Fill in the group permissions of the categories that do not have them.
+    pub async fn load_permissions(
+        &self,
+        tree: &mut CategoryTree,
+    ) -> Result<(), crate::types::error::Error> {
+        for category in tree.categories.values_mut() {
+            if category.group_permissions.is_none() {
+                category.group_permissions = Some(self.group_permissions(category.id).await?);
+            }
+        }
+        Ok(())
+    }
+
+    /// The group permissions of one category. Needs an API key that can edit
+    /// it.
+    pub async fn group_permissions(
+        &self,
+        id: i64,
+    ) -> Result<Vec<GroupPermission>, crate::types::error::Error> {
+        let mut req = self.client.client.request(
+            http::Method::GET,
+            format!("{}/c/{}/show.json", self.client.base_url, id),
+        );
+        req = req.bearer_auth(&self.client.token);
+        let category: serde_json::Value = parse_response(req.send().await?).await?;
+        match category.pointer("/category/group_permissions") {
+            Some(permissions) => Ok(serde_json::from_value(permissions.clone())?),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Create a category with permissions for any groups. They replace the
+    /// `permissions` of `body`.
+    pub async fn create_category_with_permissions(
+        &self,
+        body: &crate::types::CreateCategoryRequestBody,
+        permissions: &CategoryPermissions,
+    ) -> Result<crate::types::CreateCategoryResponse, crate::types::error::Error> {
+        let mut req = self.client.client.request(
+            http::Method::POST,
+            format!("{}/categories.json", self.client.base_url),
+        );
+        req = req.bearer_auth(&self.client.token);
+        req = req.json(&with_permissions(body, permissions)?);
+        parse_response(req.send().await?).await
+    }
+
+    /// Update a category, replacing its permissions with those given for any
+    /// groups. They replace the `permissions` of `body`.
+    pub async fn update_category_with_permissions(
+        &self,
+        id: i64,
+        body: &crate::types::UpdateCategoryRequestBody,
+        permissions: &CategoryPermissions,
+    ) -> Result<crate::types::UpdateCategoryResponse, crate::types::error::Error> {
+        let mut req = self.client.client.request(
+            http::Method::PUT,
+            format!("{}/categories/{}.json", self.client.base_url, id),
+        );
+        req = req.bearer_auth(&self.client.token);
+        req = req.json(&with_permissions(body, permissions)?);
+        parse_response(req.send().await?).await
+    }
+}
+
+fn with_permissions<T: serde::Serialize>(
+    body: &T,
+    permissions: &CategoryPermissions,
+) -> Result<serde_json::Value, serde_json::Error> {
+    let mut body = serde_json::to_value(body)?;
+    if let Some(body) = body.as_object_mut() {
+        body.insert(
+            "permissions".to_string(),
+            serde_json::to_value(permissions)?,
+        );
+    }
+    Ok(body)
+}
@@ -0,0 +1,633 @@
+//! Chat channels and messages.
+//!
+//! Requests are made as the API key's user. With an admin key valid for all
+//! users, [`Chat::as_user`] sends them as another user instead, e.g. a bridge
+//! bot's account.
+//!
+//! ```rust,no_run
+//! use futures_util::TryStreamExt;
+//!
+//! use discourse_api::chat::{ChatEvent, SendChatMessage};
+//!
+//! async fn example_chat() -> anyhow::Result<()> {
+//!     let client = discourse_api::Client::new_from_env();
+//!     let chat = client.chat().as_user("bridge-bot");
+//!
+//!     let channels = chat.my_channels().await?;
+//!     let general = &channels.public_channels[0];
+//!     chat.send(general.id, &SendChatMessage::new("Bridge online")).await?;
+//!
+//!     let mut events = chat.event_stream(&[general.id]);
+//!     while let Some(event) = events.try_next().await? {
+//!         if let ChatEvent::Sent { message, .. } = event {
+//!             println!("{:?}: {}", message.user.and_then(|u| u.username), message.message);
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use futures::{Stream, StreamExt, TryStreamExt};
+
+use crate::{
+    methods::{bytes_response, parse_response},
+    webhooks::BasicUser,
+    Client,
+};
+
+/// The header selecting the user an admin API key acts as.
+pub(crate) const API_USERNAME_HEADER: &str = "Api-Username";
+
+#[cfg(feature = "retry")]
+type RequestBuilder = reqwest_middleware::RequestBuilder;
+#[cfg(not(feature = "retry"))]
+type RequestBuilder = reqwest::RequestBuilder;
+
+/// A chat channel, of a category or of direct messages.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ChatChannel {
+    pub id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// `Category` or `DirectMessage`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chatable_type: Option<String>,
+    /// The category ID, for category channels.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chatable_id: Option<i64>,
+    /// E.g. `open`, `read_only`, `closed` or `archived`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memberships_count: Option<i64>,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The channels the user is a member of.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct MyChatChannels {
+    #[serde(default)]
+    pub public_channels: Vec<ChatChannel>,
+    #[serde(default)]
+    pub direct_message_channels: Vec<ChatChannel>,
+    /// Fields not listed above, e.g. unread `tracking`.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+struct ChatChannelsResponse {
+    channels: Vec<ChatChannel>,
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+struct ChatChannelResponse {
+    channel: ChatChannel,
+}
+
+/// An emoji reaction to a chat message.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ChatReaction {
+    pub emoji: String,
+    pub count: i64,
+    /// Whether the requesting user reacted with it.
+    #[serde(default)]
+    pub reacted: bool,
+    /// Some of the users who reacted with it.
+    #[serde(default)]
+    pub users: Vec<BasicUser>,
+}
+
+/// A chat message.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ChatMessage {
+    pub id: i64,
+    /// The raw Markdown.
+    #[serde(default)]
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooked: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+    #[serde(default)]
+    pub edited: bool,
+    #[serde(
+        default,
+        alias = "chat_channel_id",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub channel_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<BasicUser>,
+    /// The message this one replies to, as a partial message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_reply_to: Option<serde_json::Value>,
+    #[serde(default)]
+    pub reactions: Vec<ChatReaction>,
+    #[serde(default)]
+    pub uploads: Vec<serde_json::Value>,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Which side of the target message to read.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Debug,
+    Clone,
+    Copy,
+    schemars :: JsonSchema,
+    parse_display :: FromStr,
+    parse_display :: Display,
+)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "tabled", derive(tabled::Tabled))]
+pub enum ChatDirection {
+    /// Older messages.
+    #[serde(rename = "past")]
+    #[display("past")]
+    Past,
+    /// Newer messages.
+    #[serde(rename = "future")]
+    #[display("future")]
+    Future,
+}
+
+/// A page of messages. Without a target message, the newest page is read.
+#[derive(Clone, Debug, Default)]
+pub struct ChatMessagesParams {
+    pub page_size: Option<i64>,
+    /// The message the page starts from, excluded when `direction` is set.
+    pub target_message_id: Option<i64>,
+    pub direction: Option<ChatDirection>,
+    /// Start from the user's last read message.
+    pub fetch_from_last_read: Option<bool>,
+}
+
+/// Whether more messages exist around a page.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ChatMessagesMeta {
+    #[serde(default)]
+    pub can_load_more_past: bool,
+    #[serde(default)]
+    pub can_load_more_future: bool,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A page of messages, oldest first.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ChatMessagesPage {
+    pub messages: Vec<ChatMessage>,
+    pub meta: ChatMessagesMeta,
+}
+
+impl ChatMessagesPage {
+    /// The parameters of the page of older messages, if there is one.
+    pub fn past(&self, page_size: Option<i64>) -> Option<ChatMessagesParams> {
+        let oldest = self.messages.first()?;
+        self.meta.can_load_more_past.then_some(ChatMessagesParams {
+            page_size,
+            target_message_id: Some(oldest.id),
+            direction: Some(ChatDirection::Past),
+            fetch_from_last_read: None,
+        })
+    }
+
+    /// The parameters of the page of newer messages, if there is one.
+    pub fn future(&self, page_size: Option<i64>) -> Option<ChatMessagesParams> {
+        let newest = self.messages.last()?;
+        self.meta
+            .can_load_more_future
+            .then_some(ChatMessagesParams {
+                page_size,
+                target_message_id: Some(newest.id),
+                direction: Some(ChatDirection::Future),
+                fetch_from_last_read: None,
+            })
+    }
+}
+
+/// A message to send.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct SendChatMessage {
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_reply_to_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub upload_ids: Vec<i64>,
+}
+
+impl SendChatMessage {
+    pub fn new<M: ToString>(message: M) -> Self {
+        SendChatMessage {
+            message: message.to_string(),
+            in_reply_to_id: None,
+            thread_id: None,
+            upload_ids: vec![],
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct SendChatMessageResponse {
+    message_id: i64,
+}
+
+/// Whether a reaction is added or removed.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Debug,
+    Clone,
+    Copy,
+    schemars :: JsonSchema,
+    parse_display :: FromStr,
+    parse_display :: Display,
+)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "tabled", derive(tabled::Tabled))]
+pub enum ReactAction {
+    #[serde(rename = "add")]
+    #[display("add")]
+    Add,
+    #[serde(rename = "remove")]
+    #[display("remove")]
+    Remove,
+}
+
+/// A change to a chat channel, as published on its `/chat/{channel_id}`
+/// MessageBus channel.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatEvent {
+    Sent {
+        channel_id: i64,
+        message: Box<ChatMessage>,
+    },
+    Edited {
+        channel_id: i64,
+        message: Box<ChatMessage>,
+    },
+    Deleted {
+        channel_id: i64,
+        message_id: i64,
+    },
+    Restored {
+        channel_id: i64,
+        message: Box<ChatMessage>,
+    },
+    Reaction {
+        channel_id: i64,
+        message_id: i64,
+        emoji: String,
+        action: ReactAction,
+        user: Option<BasicUser>,
+    },
+    /// Another kind of change, e.g. a thread or typing update, with its
+    /// `type` and data.
+    Other {
+        channel_id: i64,
+        type_: String,
+        data: serde_json::Value,
+    },
+}
+
+impl ChatEvent {
+    /// Read a MessageBus message of a `/chat/{channel_id}` channel.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_message(message: &crate::message_bus::MessageBusMessage) -> Option<Self> {
+        let channel_id = message.channel.strip_prefix("/chat/")?.parse().ok()?;
+        Some(ChatEvent::parse(channel_id, &message.data))
+    }
+
+    /// Read the data of a chat channel message.
+    pub fn parse(channel_id: i64, data: &serde_json::Value) -> Self {
+        let type_ = data
+            .get("type")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        let chat_message = || {
+            data.get("chat_message")
+                .and_then(|m| serde_json::from_value::<ChatMessage>(m.clone()).ok())
+                .map(Box::new)
+        };
+        let int = |key: &str| data.get(key).and_then(|v| v.as_i64());
+
+        let event = match type_ {
+            "sent" => chat_message().map(|message| ChatEvent::Sent {
+                channel_id,
+                message,
+            }),
+            "edit" => chat_message().map(|message| ChatEvent::Edited {
+                channel_id,
+                message,
+            }),
+            "restore" => chat_message().map(|message| ChatEvent::Restored {
+                channel_id,
+                message,
+            }),
+            "delete" => int("deleted_id").map(|message_id| ChatEvent::Deleted {
+                channel_id,
+                message_id,
+            }),
+            "reaction" => int("chat_message_id").and_then(|message_id| {
+                Some(ChatEvent::Reaction {
+                    channel_id,
+                    message_id,
+                    emoji: data.get("emoji")?.as_str()?.to_string(),
+                    action: serde_json::from_value(data.get("action")?.clone()).ok()?,
+                    user: data
+                        .get("user")
+                        .and_then(|u| serde_json::from_value(u.clone()).ok()),
+                })
+            }),
+            _ => None,
+        };
+        event.unwrap_or_else(|| ChatEvent::Other {
+            channel_id,
+            type_: type_.to_string(),
+            data: data.clone(),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Chat {
+    pub client: Client,
+    acting_username: Option<String>,
+}
+
+impl Chat {
+    #[doc(hidden)]
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            acting_username: None,
+        }
+    }
+
+    /// Make requests as another user. Needs an admin API key valid for all
+    /// users.
+    pub fn as_user<U: ToString>(mut self, username: U) -> Self {
+        self.acting_username = Some(username.to_string());
+        self
+    }
+
+    fn request(&self, method: http::Method, path: &str) -> RequestBuilder {
+        let mut req = self
+            .client
+            .client
+            .request(method, format!("{}/{}", self.client.base_url, path));
+        req = req.bearer_auth(&self.client.token);
+        if let Some(username) = &self.acting_username {
+            req = req.header(API_USERNAME_HEADER, username);
+        }
+        req
+    }
+
+    /// List the public channels, filtered by name when `filter` is set.
+    #[tracing::instrument]
+    pub async fn list_channels<'a>(
+        &'a self,
+        filter: Option<&'a str>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> Result<Vec<ChatChannel>, crate::types::error::Error> {
+        let mut req = self.request(http::Method::GET, "chat/api/channels.json");
+        let mut query_params = vec![];
+        if let Some(p) = filter {
+            query_params.push(("filter", p.to_string()));
+        }
+
+        if let Some(p) = limit {
+            query_params.push(("limit", format!("{}", p)));
+        }
+
+        if let Some(p) = offset {
+            query_params.push(("offset", format!("{}", p)));
+        }
+
+        req = req.query(&query_params);
+        let resp: ChatChannelsResponse = parse_response(req.send().await?).await?;
+        Ok(resp.channels)
+    }
+
+    /// List the channels and direct messages the user is a member of.
+    #[tracing::instrument]
+    pub async fn my_channels<'a>(&'a self) -> Result<MyChatChannels, crate::types::error::Error> {
+        let req = self.request(http::Method::GET, "chat/api/me/channels.json");
+        parse_response(req.send().await?).await
+    }
+
+    #[tracing::instrument]
+    pub async fn get_channel<'a>(
+        &'a self,
+        channel_id: i64,
+    ) -> Result<ChatChannel, crate::types::error::Error> {
+        let req = self.request(
+            http::Method::GET,
+            &format!("chat/api/channels/{}.json", channel_id),
+        );
+        let resp: ChatChannelResponse = parse_response(req.send().await?).await?;
+        Ok(resp.channel)
+    }
+
+    /// Read a page of a channel's messages. Follow [`ChatMessagesPage::past`]
+    /// and [`ChatMessagesPage::future`] for the neighbouring pages.
+    #[tracing::instrument]
+    pub async fn messages<'a>(
+        &'a self,
+        channel_id: i64,
+        params: ChatMessagesParams,
+    ) -> Result<ChatMessagesPage, crate::types::error::Error> {
+        let ChatMessagesParams {
+            page_size,
+            target_message_id,
+            direction,
+            fetch_from_last_read,
+        } = params;
+        let mut req = self.request(
+            http::Method::GET,
+            &format!("chat/api/channels/{}/messages.json", channel_id),
+        );
+        let mut query_params = vec![];
+        if let Some(p) = page_size {
+            query_params.push(("page_size", format!("{}", p)));
+        }
+
+        if let Some(p) = target_message_id {
+            query_params.push(("target_message_id", format!("{}", p)));
+        }
+
+        if let Some(p) = direction {
+            query_params.push(("direction", format!("{}", p)));
+        }
+
+        if let Some(p) = fetch_from_last_read {
+            query_params.push(("fetch_from_last_read", format!("{}", p)));
+        }
+
+        req = req.query(&query_params);
+        parse_response(req.send().await?).await
+    }
+
+    /// Stream a channel's history, newest message first.
+    pub fn history_stream<'a>(
+        &'a self,
+        channel_id: i64,
+        page_size: Option<i64>,
+    ) -> impl Stream<Item = Result<ChatMessage, crate::types::error::Error>> + Unpin + 'a {
+        let first = ChatMessagesParams {
+            page_size,
+            ..Default::default()
+        };
+        let pages = futures::stream::try_unfold(Some(first), move |params| async move {
+            let Some(params) = params else {
+                return Ok(None);
+            };
+            let page = self.messages(channel_id, params).await?;
+            let next = page.past(page_size);
+            Ok::<_, crate::types::error::Error>(Some((page.messages, next)))
+        });
+        Box::pin(
+            pages
+                .map_ok(|messages| futures::stream::iter(messages.into_iter().rev().map(Ok)))
+                .try_flatten(),
+        )
+    }
+
+    /// Send a message and return its ID.
+    #[tracing::instrument]
+    pub async fn send<'a>(
+        &'a self,
+        channel_id: i64,
+        message: &SendChatMessage,
+    ) -> Result<i64, crate::types::error::Error> {
+        let mut req = self.request(http::Method::POST, &format!("chat/{}.json", channel_id));
+        req = req.json(message);
+        let resp: SendChatMessageResponse = parse_response(req.send().await?).await?;
+        Ok(resp.message_id)
+    }
+
+    /// Replace the text of a message.
+    #[tracing::instrument]
+    pub async fn edit<'a>(
+        &'a self,
+        channel_id: i64,
+        message_id: i64,
+        message: &'a str,
+    ) -> Result<(), crate::types::error::Error> {
+        let mut req = self.request(
+            http::Method::PUT,
+            &format!(
+                "chat/api/channels/{}/messages/{}.json",
+                channel_id, message_id
+            ),
+        );
+        req = req.json(&serde_json::json!({ "message": message }));
+        bytes_response(req.send().await?).await.map(|_| ())
+    }
+
+    #[tracing::instrument]
+    pub async fn delete<'a>(
+        &'a self,
+        channel_id: i64,
+        message_id: i64,
+    ) -> Result<(), crate::types::error::Error> {
+        let req = self.request(
+            http::Method::DELETE,
+            &format!(
+                "chat/api/channels/{}/messages/{}.json",
+                channel_id, message_id
+            ),
+        );
+        bytes_response(req.send().await?).await.map(|_| ())
+    }
+
+    /// Add or remove a reaction, e.g. `heart` or `+1`.
+    #[tracing::instrument]
+    pub async fn react<'a>(
+        &'a self,
+        channel_id: i64,
+        message_id: i64,
+        emoji: &'a str,
+        action: ReactAction,
+    ) -> Result<(), crate::types::error::Error> {
+        let mut req = self.request(
+            http::Method::PUT,
+            &format!("chat/{}/react/{}.json", channel_id, message_id),
+        );
+        req = req.json(&serde_json::json!({
+            "emoji": emoji,
+            "react_action": action,
+        }));
+        bytes_response(req.send().await?).await.map(|_| ())
+    }
+
+    /// Follow channels over MessageBus, from now on, as the user requests
+    /// are made as. See [`crate::message_bus::MessageBus::into_stream`] for
+    /// error handling.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn event_stream(
+        &self,
+        channel_ids: &[i64],
+    ) -> impl Stream<Item = Result<ChatEvent, crate::types::error::Error>> + Unpin {
+        let mut bus = self.client.message_bus();
+        if let Some(username) = &self.acting_username {
+            bus = bus.as_user(username);
+        }
+        for channel_id in channel_ids {
+            bus.subscribe(format!("/chat/{}", channel_id), -1);
+        }
+        bus.into_stream().filter_map(|event| {
+            futures::future::ready(match event {
+                Ok(crate::message_bus::MessageBusEvent::Other(message)) => {
+                    ChatEvent::from_message(&message).map(Ok)
+                }
+                Ok(_) => None,
+                Err(err) => Some(Err(err)),
+            })
+        })
+    }
+}
@@ -0,0 +1,111 @@
+//! Errors the generated [`Error`] has no variant for.
+//!
+//! A request that stays rate limited fails with a [`RateLimitExceeded`]
+//! inside the generated error. [`ErrorExt::rate_limited`] finds it, whether
+//! it came from a generated or a hand-written method:
+//!
+//! ```rust,no_run
+//! use discourse_api::errors::ErrorExt;
+//!
+//! async fn example_rate_limited() -> anyhow::Result<()> {
+//!     let client = discourse_api::Client::new_from_env();
+//!     match client.site().get().await {
+//!         Err(err) if err.rate_limited().is_some() => {
+//!             println!("try again in {:?}", err.retry_after());
+//!         }
+//!         result => println!("{:?}", result?),
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::time::Duration;
+
+use crate::types::error::Error;
+
+/// A `429 Too Many Requests` response that was not retried.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimitExceeded {
+    /// How long the server asked to wait, from `extras.wait_seconds` or
+    /// `Retry-After`.
+    pub wait: Option<Duration>,
+    /// The text from the body.
+    pub body: String,
+}
+
+impl RateLimitExceeded {
+    /// Read the wait from a `429` response: `extras.wait_seconds` in the body,
+    /// or else the `Retry-After` header.
+    pub fn from_response(headers: &reqwest::header::HeaderMap, body: String) -> Self {
+        let wait_seconds = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|v| v.pointer("/extras/wait_seconds").and_then(|w| w.as_f64()));
+        let retry_after = headers
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<f64>().ok());
+        let wait = wait_seconds
+            .or(retry_after)
+            .filter(|w| w.is_finite())
+            .map(|w| Duration::from_secs_f64(w.max(0.0)));
+        RateLimitExceeded { wait, body }
+    }
+
+    /// Find a rate limit error returned by a middleware, also when the retry
+    /// middleware wrapped it.
+    #[cfg(feature = "retry")]
+    fn from_middleware_error(e: &reqwest_middleware::Error) -> Option<Self> {
+        let reqwest_middleware::Error::Middleware(e) = e else {
+            return None;
+        };
+        if let Some(e) = e.downcast_ref::<RateLimitExceeded>() {
+            return Some(e.clone());
+        }
+        match e.downcast_ref::<reqwest_retry::RetryError>()? {
+            reqwest_retry::RetryError::Error(e)
+            | reqwest_retry::RetryError::WithRetries { err: e, .. } => {
+                Self::from_middleware_error(e)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for RateLimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.wait {
+            Some(wait) => write!(f, "rate limited, retry in {:?}: {}", wait, self.body),
+            None => write!(f, "rate limited: {}", self.body),
+        }
+    }
+}
+
+impl std::error::Error for RateLimitExceeded {}
+
+/// Typed details of the generated [`Error`].
+pub trait ErrorExt {
+    /// The `429` the request failed with, if it stayed rate limited. The rate
+    /// limiter of a client returns it as a middleware error; without the
+    /// `retry` feature it is a `429` server error.
+    fn rate_limited(&self) -> Option<RateLimitExceeded>;
+
+    /// How long the server asked to wait, if it rate limited the request.
+    fn retry_after(&self) -> Option<Duration> {
+        self.rate_limited()?.wait
+    }
+}
+
+impl ErrorExt for Error {
+    fn rate_limited(&self) -> Option<RateLimitExceeded> {
+        match self {
+            #[cfg(feature = "retry")]
+            Error::CommunicationError(e) => RateLimitExceeded::from_middleware_error(e),
+            Error::Server { body, status } if *status == reqwest::StatusCode::TOO_MANY_REQUESTS => {
+                Some(RateLimitExceeded::from_response(
+                    &reqwest::header::HeaderMap::new(),
+                    body.clone(),
+                ))
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,453 @@
+//! Group membership sync.
+//!
+//! [`Groups::sync_members`] makes a group's members match a list of usernames
+//! and email addresses: it reads the current members page by page, adds the
+//! missing users and removes the others, in batches. A dry run only returns
+//! the plan, and removals beyond `max_removals` are held back so that an
+//! empty or truncated source list cannot empty a group. Group owners missing
+//! from the list are kept and reported in `owners_kept`, unless
+//! `remove_owners` is set.
+//!
+//! ```rust,no_run
+//! use discourse_api::group_sync::{GroupSyncOptions, MemberRef};
+//!
+//! async fn example_group_sync() -> anyhow::Result<()> {
+//!     let client = discourse_api::Client::new_from_env();
+//!     let desired = ["alice", "bob@example.com"].map(MemberRef::from);
+//!     let report = client
+//!         .groups()
+//!         .sync_members("platform-team", desired, GroupSyncOptions::default())
+//!         .await?;
+//!     println!("added {:?}, removed {:?}", report.added, report.removed);
+//!     if report.removals_held_back {
+//!         println!("too many removals, review: {:?}", report.plan.remove);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{groups::Groups, methods::parse_response};
+
+/// A user to have in a group, by username or email address.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Debug,
+    Clone,
+    schemars :: JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum MemberRef {
+    Username(String),
+    /// Looked up among users' primary and secondary addresses. Needs an admin
+    /// API key.
+    Email(String),
+}
+
+impl From<&str> for MemberRef {
+    /// An email address if it contains `@`, which usernames cannot, or else a
+    /// username.
+    fn from(s: &str) -> Self {
+        let s = s.trim();
+        if s.contains('@') {
+            MemberRef::Email(s.to_string())
+        } else {
+            MemberRef::Username(s.to_string())
+        }
+    }
+}
+
+impl From<String> for MemberRef {
+    fn from(s: String) -> Self {
+        MemberRef::from(s.as_str())
+    }
+}
+
+/// A current member of a group.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct GroupMember {
+    pub id: i64,
+    pub username: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Whether the user owns the group.
+    #[serde(default)]
+    pub owner: bool,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(serde::Deserialize)]
+struct MembersPage {
+    #[serde(default)]
+    members: Vec<GroupMember>,
+    #[serde(default)]
+    owners: Vec<GroupMember>,
+    #[serde(default)]
+    meta: Option<MembersMeta>,
+}
+
+#[derive(serde::Deserialize)]
+struct MembersMeta {
+    total: i64,
+}
+
+/// How [`Groups::sync_members`] applies changes.
+#[derive(Clone, Debug)]
+pub struct GroupSyncOptions {
+    /// Only compute the plan.
+    pub dry_run: bool,
+    /// The most removals applied in one sync. When more are planned, none
+    /// are applied.
+    pub max_removals: usize,
+    /// Usernames per add or remove request.
+    pub batch_size: usize,
+    /// Members read per page.
+    pub page_size: i64,
+    /// Also remove owners who are not desired members. Without it they are
+    /// kept and listed in [`GroupSyncPlan::owners_kept`].
+    pub remove_owners: bool,
+}
+
+impl Default for GroupSyncOptions {
+    fn default() -> Self {
+        GroupSyncOptions {
+            dry_run: false,
+            max_removals: 25,
+            batch_size: 50,
+            page_size: 100,
+            remove_owners: false,
+        }
+    }
+}
+
+/// The changes that make a group match the desired members.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Debug,
+    Clone,
+    Default,
+    schemars :: JsonSchema,
+)]
+pub struct GroupSyncPlan {
+    pub group: String,
+    /// Usernames to add.
+    pub add: Vec<String>,
+    /// Usernames to remove.
+    pub remove: Vec<String>,
+    /// How many desired members already are members.
+    pub unchanged: usize,
+    /// Email addresses no user has.
+    pub unknown_emails: Vec<String>,
+    /// Owners who are not desired members but are not removed.
+    #[serde(default)]
+    pub owners_kept: Vec<String>,
+}
+
+impl GroupSyncPlan {
+    /// Compare the current and desired usernames, case-insensitively.
+    pub fn new<C, D>(group: &str, current: C, desired: D) -> Self
+    where
+        C: IntoIterator<Item = String>,
+        D: IntoIterator<Item = String>,
+    {
+        let by_key = |names: Vec<String>| -> BTreeMap<String, String> {
+            names
+                .into_iter()
+                .map(|name| (name.to_lowercase(), name))
+                .collect()
+        };
+        let current = by_key(current.into_iter().collect());
+        let desired = by_key(desired.into_iter().collect());
+        GroupSyncPlan {
+            group: group.to_string(),
+            add: desired
+                .iter()
+                .filter(|(key, _)| !current.contains_key(*key))
+                .map(|(_, name)| name.clone())
+                .collect(),
+            remove: current
+                .iter()
+                .filter(|(key, _)| !desired.contains_key(*key))
+                .map(|(_, name)| name.clone())
+                .collect(),
+            unchanged: desired
+                .keys()
+                .filter(|key| current.contains_key(*key))
+                .count(),
+            unknown_emails: vec![],
+            owners_kept: vec![],
+        }
+    }
+
+    /// Take these owners out of the removals and list them in `owners_kept`.
+    pub fn keep_owners<O>(mut self, owners: O) -> Self
+    where
+        O: IntoIterator<Item = String>,
+    {
+        let owners: Vec<String> = owners
+            .into_iter()
+            .map(|owner| owner.to_lowercase())
+            .collect();
+        let (kept, remove) = std::mem::take(&mut self.remove)
+            .into_iter()
+            .partition(|name| owners.contains(&name.to_lowercase()));
+        self.owners_kept = kept;
+        self.remove = remove;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.add.is_empty() && self.remove.is_empty()
+    }
+}
+
+/// What a sync did.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Debug,
+    Clone,
+    Default,
+    schemars :: JsonSchema,
+)]
+pub struct GroupSyncReport {
+    pub plan: GroupSyncPlan,
+    pub dry_run: bool,
+    /// Usernames added.
+    pub added: Vec<String>,
+    /// Usernames removed.
+    pub removed: Vec<String>,
+    /// Whether the planned removals exceeded `max_removals` and none were
+    /// applied.
+    pub removals_held_back: bool,
+}
+
+impl Groups {
+    /// Read every member of a group, owners included and marked as such.
+    pub async fn list_all_members(
+        &self,
+        name: &str,
+        page_size: i64,
+    ) -> Result<Vec<GroupMember>, crate::types::error::Error> {
+        let mut members: BTreeMap<i64, GroupMember> = BTreeMap::new();
+        let mut offset = 0;
+        loop {
+            let mut req = self.client.client.request(
+                http::Method::GET,
+                format!("{}/groups/{}/members.json", self.client.base_url, name),
+            );
+            req = req.bearer_auth(&self.client.token);
+            req = req.query(&[
+                ("limit", page_size.max(1).to_string()),
+                ("offset", offset.to_string()),
+            ]);
+            let page: MembersPage = parse_response(req.send().await?).await?;
+            let count = page.members.len() as i64;
+            for owner in page.owners {
+                members.entry(owner.id).or_insert(owner).owner = true;
+            }
+            for member in page.members {
+                members.entry(member.id).or_insert(member);
+            }
+            offset += count;
+            let total = page.meta.map(|meta| meta.total).unwrap_or(0);
+            if count == 0 || offset >= total {
+                break;
+            }
+        }
+        Ok(members.into_values().collect())
+    }
+
+    /// Compute the changes that make a group's members match `desired`.
+    pub async fn plan_sync<I>(
+        &self,
+        name: &str,
+        desired: I,
+        options: &GroupSyncOptions,
+    ) -> Result<GroupSyncPlan, crate::types::error::Error>
+    where
+        I: IntoIterator<Item = MemberRef>,
+    {
+        self.plan_sync_cached(name, desired, options, &mut HashMap::new())
+            .await
+    }
+
+    async fn plan_sync_cached<I>(
+        &self,
+        name: &str,
+        desired: I,
+        options: &GroupSyncOptions,
+        emails: &mut HashMap<String, Option<String>>,
+    ) -> Result<GroupSyncPlan, crate::types::error::Error>
+    where
+        I: IntoIterator<Item = MemberRef>,
+    {
+        let mut usernames = vec![];
+        let mut unknown_emails = vec![];
+        for member in desired {
+            match member {
+                MemberRef::Username(username) => usernames.push(username),
+                MemberRef::Email(email) => {
+                    let key = email.to_lowercase();
+                    let username = match emails.get(&key) {
+                        Some(username) => username.clone(),
+                        None => {
+                            let username =
+                                crate::methods::find_username_by_email(&self.client, &email)
+                                    .await?;
+                            emails.insert(key, username.clone());
+                            username
+                        }
+                    };
+                    match username {
+                        Some(username) => usernames.push(username),
+                        None => unknown_emails.push(email),
+                    }
+                }
+            }
+        }
+
+        let current = self.list_all_members(name, options.page_size).await?;
+        let owners: Vec<String> = current
+            .iter()
+            .filter(|member| member.owner)
+            .map(|member| member.username.clone())
+            .collect();
+        let mut plan = GroupSyncPlan::new(
+            name,
+            current.into_iter().map(|member| member.username),
+            usernames,
+        );
+        if !options.remove_owners {
+            plan = plan.keep_owners(owners);
+        }
+        plan.unknown_emails = unknown_emails;
+        Ok(plan)
+    }
+
+    /// The ID of a group, which adding and removing members needs.
+    async fn group_id(&self, name: &str) -> Result<i64, crate::types::error::Error> {
+        let mut req = self.client.client.request(
+            http::Method::GET,
+            format!("{}/groups/{}.json", self.client.base_url, name),
+        );
+        req = req.bearer_auth(&self.client.token);
+        let group: serde_json::Value = parse_response(req.send().await?).await?;
+        group
+            .pointer("/group/id")
+            .and_then(|id| id.as_i64())
+            .ok_or_else(|| {
+                crate::types::error::Error::InvalidRequest(format!("group {} has no id", name))
+            })
+    }
+
+    /// Make a group's members match `desired`.
+    ///
+    /// Additions are applied before removals. A failed request stops the sync
+    /// with an error; running it again picks up from the group's new state.
+    pub async fn sync_members<I>(
+        &self,
+        name: &str,
+        desired: I,
+        options: GroupSyncOptions,
+    ) -> Result<GroupSyncReport, crate::types::error::Error>
+    where
+        I: IntoIterator<Item = MemberRef>,
+    {
+        self.sync_members_cached(name, desired, &options, &mut HashMap::new())
+            .await
+    }
+
+    async fn sync_members_cached<I>(
+        &self,
+        name: &str,
+        desired: I,
+        options: &GroupSyncOptions,
+        emails: &mut HashMap<String, Option<String>>,
+    ) -> Result<GroupSyncReport, crate::types::error::Error>
+    where
+        I: IntoIterator<Item = MemberRef>,
+    {
+        let plan = self
+            .plan_sync_cached(name, desired, options, emails)
+            .await?;
+        let mut report = GroupSyncReport {
+            removals_held_back: plan.remove.len() > options.max_removals,
+            dry_run: options.dry_run,
+            ..Default::default()
+        };
+        if report.removals_held_back {
+            log::warn!(
+                "not removing {} members from {}, more than the limit of {}",
+                plan.remove.len(),
+                name,
+                options.max_removals
+            );
+        }
+        if options.dry_run || plan.is_empty() {
+            report.plan = plan;
+            return Ok(report);
+        }
+
+        let id = self.group_id(name).await?;
+        for batch in plan.add.chunks(options.batch_size.max(1)) {
+            self.add_members(
+                id,
+                &crate::types::AddGroupMembersRequestBody {
+                    usernames: Some(batch.join(",")),
+                },
+            )
+            .await?;
+            report.added.extend_from_slice(batch);
+        }
+        if !report.removals_held_back {
+            for batch in plan.remove.chunks(options.batch_size.max(1)) {
+                self.remove_members(
+                    id,
+                    &crate::types::RemoveGroupMembersRequestBody {
+                        usernames: Some(batch.join(",")),
+                    },
+                )
+                .await?;
+                report.removed.extend_from_slice(batch);
+            }
+        }
+        report.plan = plan;
+        Ok(report)
+    }
+
+    /// Sync several groups, looking up each email address once.
+    pub async fn sync_all<G, I>(
+        &self,
+        groups: G,
+        options: GroupSyncOptions,
+    ) -> Result<Vec<GroupSyncReport>, crate::types::error::Error>
+    where
+        G: IntoIterator<Item = (String, I)>,
+        I: IntoIterator<Item = MemberRef>,
+    {
+        let mut emails = HashMap::new();
+        let mut reports = vec![];
+        for (name, desired) in groups {
+            reports.push(
+                self.sync_members_cached(&name, desired, &options, &mut emails)
+                    .await?,
+            );
+        }
+        Ok(reports)
+    }
+}
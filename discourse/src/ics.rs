@@ -0,0 +1,488 @@
+//! iCalendar feeds of calendar events.
+//!
+//! [`parse_ics`] reads the feed `export_events_ics` returns into
+//! [`CalendarEvent`]s, and [`to_ics`] writes events back out, e.g. events from
+//! `list_events` gathered across several categories.
+//!
+//! ```rust,no_run
+//! use discourse_api::discourse_calendar_events::ListEventsParams;
+//!
+//! async fn example_ics() -> anyhow::Result<()> {
+//!     let client = discourse_api::Client::new_from_env();
+//!     let calendar = client.discourse_calendar_events();
+//!
+//!     let feed = calendar
+//!         .merged_ics(&[4, 7, 12], ListEventsParams::default(), "Team calendar")
+//!         .await?;
+//!     std::fs::write("team.ics", feed)?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDate, NaiveDateTime, TimeZone};
+
+/// The longest line of a feed, in bytes, before it is folded.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// Why a feed could not be read.
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum IcsError {
+    /// A content line has no `:` separating its name from its value.
+    #[error("line {0}: expected `NAME:VALUE`")]
+    InvalidLine(usize),
+    /// A date or date-time property has an invalid value.
+    #[error("invalid {property} `{value}`")]
+    InvalidDate { property: String, value: String },
+    /// An event has no `UID` or no `DTSTART`.
+    #[error("event without {0}")]
+    MissingProperty(&'static str),
+    /// A `BEGIN:VEVENT` has no matching `END:VEVENT`.
+    #[error("unterminated event")]
+    Unterminated,
+}
+
+/// When an event starts or ends.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub enum EventTime {
+    /// A point in time, written with a `Z` suffix.
+    Utc(chrono::DateTime<chrono::Utc>),
+    /// A wall-clock time in the time zone named by `tzid`, or in the
+    /// attendee's time zone without one.
+    Local {
+        datetime: NaiveDateTime,
+        tzid: Option<String>,
+    },
+    /// A whole day.
+    Date(NaiveDate),
+}
+
+impl EventTime {
+    /// The time as UTC, when it names a point in time.
+    pub fn to_utc(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        match self {
+            EventTime::Utc(datetime) => Some(*datetime),
+            EventTime::Local { datetime, tzid } if tzid.as_deref().is_some_and(is_utc) => {
+                Some(chrono::Utc.from_utc_datetime(datetime))
+            }
+            _ => None,
+        }
+    }
+
+    fn parse(
+        property: &str,
+        params: &BTreeMap<String, String>,
+        value: &str,
+    ) -> Result<Self, IcsError> {
+        let invalid = || IcsError::InvalidDate {
+            property: property.to_string(),
+            value: value.to_string(),
+        };
+        if params.get("VALUE").map(String::as_str) == Some("DATE") || value.len() == 8 {
+            return NaiveDate::parse_from_str(value, "%Y%m%d")
+                .map(EventTime::Date)
+                .map_err(|_| invalid());
+        }
+        let (local, utc) = match value.strip_suffix('Z') {
+            Some(local) => (local, true),
+            None => (value, false),
+        };
+        let datetime =
+            NaiveDateTime::parse_from_str(local, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+        Ok(if utc {
+            EventTime::Utc(chrono::Utc.from_utc_datetime(&datetime))
+        } else {
+            EventTime::Local {
+                datetime,
+                tzid: params.get("TZID").cloned(),
+            }
+        })
+    }
+
+    fn write(&self, name: &str, out: &mut String) {
+        match self {
+            EventTime::Utc(datetime) => {
+                write_line(out, name, &datetime.format("%Y%m%dT%H%M%SZ").to_string())
+            }
+            EventTime::Local {
+                datetime,
+                tzid: Some(tzid),
+            } => write_line(
+                out,
+                &format!("{};TZID={}", name, tzid),
+                &datetime.format("%Y%m%dT%H%M%S").to_string(),
+            ),
+            EventTime::Local {
+                datetime,
+                tzid: None,
+            } => write_line(out, name, &datetime.format("%Y%m%dT%H%M%S").to_string()),
+            EventTime::Date(date) => write_line(
+                out,
+                &format!("{};VALUE=DATE", name),
+                &date.format("%Y%m%d").to_string(),
+            ),
+        }
+    }
+}
+
+fn is_utc(tzid: &str) -> bool {
+    matches!(tzid, "UTC" | "Etc/UTC" | "GMT" | "Etc/GMT")
+}
+
+/// A property line of an event, e.g. one `EXDATE` or `ATTENDEE`.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct IcsProperty {
+    /// The parameters by upper-case name, e.g. `TZID` or `CN`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, String>,
+    /// The value as written in the feed, still escaped.
+    pub value: String,
+}
+
+/// An event of a calendar feed.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct CalendarEvent {
+    pub uid: String,
+    pub start: EventTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<EventTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    /// The URL of the post of the event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// The recurrence rule, without the `RRULE:` prefix, e.g.
+    /// `FREQ=WEEKLY;BYDAY=MO`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rrule: Option<String>,
+    /// Other properties by name, e.g. `DTSTAMP`. A property that may repeat,
+    /// like `EXDATE` or `ATTENDEE`, has one entry per line in feed order.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub other: BTreeMap<String, Vec<IcsProperty>>,
+}
+
+#[cfg(feature = "requests")]
+impl CalendarEvent {
+    /// Convert an event of `list_events`, with post URLs relative to
+    /// `base_url`.
+    pub fn from_event(event: &crate::types::Events, base_url: &str) -> Option<Self> {
+        let start = event.starts_at?;
+        let post_url = if event.post.url.starts_with("http") {
+            event.post.url.clone()
+        } else {
+            format!(
+                "{}/{}",
+                base_url.trim_end_matches('/'),
+                event.post.url.trim_start_matches('/')
+            )
+        };
+        let rrule = event.rrule.as_deref().and_then(|rrule| {
+            // The rule may come with its `DTSTART` line.
+            rrule
+                .lines()
+                .map(|line| line.trim())
+                .find(|line| !line.starts_with("DTSTART"))
+                .map(|line| line.trim_start_matches("RRULE:").to_string())
+                .filter(|line| !line.is_empty())
+        });
+        Some(CalendarEvent {
+            uid: format!("discourse-post-event-{}", event.id),
+            start: EventTime::Utc(start),
+            end: event.ends_at.map(EventTime::Utc),
+            summary: Some(
+                event
+                    .name
+                    .clone()
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or_else(|| event.post.topic.title.clone()),
+            ),
+            description: event.description.clone().filter(|d| !d.is_empty()),
+            location: event.location.clone().filter(|l| !l.is_empty()),
+            url: Some(post_url),
+            rrule,
+            other: BTreeMap::new(),
+        })
+    }
+}
+
+/// Unescape a `TEXT` value.
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Escape a `TEXT` value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// The properties of an event by name, in feed order.
+type Properties = BTreeMap<String, Vec<IcsProperty>>;
+
+/// Split a content line into its name, parameters and value.
+fn split_line(line: &str) -> Option<(String, BTreeMap<String, String>, &str)> {
+    let mut in_quotes = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(i),
+        _ => None,
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let mut parts = head.split(';');
+    let name = parts.next()?.trim().to_uppercase();
+    let params = parts
+        .filter_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            Some((key.to_uppercase(), value.trim_matches('"').to_string()))
+        })
+        .collect();
+    Some((name, params, value))
+}
+
+/// Parse the events of an iCalendar feed. Events inside other components and
+/// other components are skipped.
+pub fn parse_ics(feed: &str) -> Result<Vec<CalendarEvent>, IcsError> {
+    // Unfold lines continued with a leading space or tab.
+    let mut lines: Vec<(usize, String)> = vec![];
+    for (number, line) in feed.lines().enumerate() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some((_, last))) => last.push_str(rest),
+            _ => lines.push((number + 1, line.to_string())),
+        }
+    }
+
+    let mut events = vec![];
+    let mut current: Option<Properties> = None;
+    let mut depth = 0;
+    for (number, line) in lines.iter().filter(|(_, line)| !line.trim().is_empty()) {
+        let (name, params, value) = split_line(line).ok_or(IcsError::InvalidLine(*number))?;
+        match (name.as_str(), value.trim().to_uppercase().as_str()) {
+            ("BEGIN", "VEVENT") if current.is_none() => {
+                current = Some(BTreeMap::new());
+                depth = 0;
+            }
+            ("BEGIN", _) if current.is_some() => depth += 1,
+            ("END", "VEVENT") if depth == 0 => {
+                let props = current.take().ok_or(IcsError::InvalidLine(*number))?;
+                events.push(event_from_properties(props)?);
+            }
+            ("END", _) if current.is_some() => depth -= 1,
+            _ => {
+                if let Some(props) = current.as_mut().filter(|_| depth == 0) {
+                    props.entry(name).or_default().push(IcsProperty {
+                        params,
+                        value: value.to_string(),
+                    });
+                }
+            }
+        }
+    }
+    if current.is_some() {
+        return Err(IcsError::Unterminated);
+    }
+    Ok(events)
+}
+
+fn event_from_properties(mut props: Properties) -> Result<CalendarEvent, IcsError> {
+    // The properties read into fields occur once per event; of repeats, the
+    // first is kept.
+    let mut take = |name: &str| {
+        props
+            .remove(name)
+            .and_then(|values| values.into_iter().next())
+    };
+    let mut text = |name: &str| take(name).map(|property| unescape(&property.value));
+    let uid = text("UID").ok_or(IcsError::MissingProperty("UID"))?;
+    let summary = text("SUMMARY");
+    let description = text("DESCRIPTION");
+    let location = text("LOCATION");
+    let url = text("URL");
+    let rrule = take("RRULE").map(|property| property.value);
+
+    let mut time = |name: &str| {
+        take(name)
+            .map(|property| EventTime::parse(name, &property.params, &property.value))
+            .transpose()
+    };
+    let start = time("DTSTART")?.ok_or(IcsError::MissingProperty("DTSTART"))?;
+    let end = time("DTEND")?;
+
+    Ok(CalendarEvent {
+        uid,
+        start,
+        end,
+        summary,
+        description,
+        location,
+        url,
+        rrule,
+        other: props,
+    })
+}
+
+/// Append a content line, folded to [`MAX_LINE_LENGTH`] bytes.
+fn write_line(out: &mut String, name: &str, value: &str) {
+    let line = format!("{}:{}", name, value);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_LENGTH {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+/// Append a property line with its parameters, quoting parameter values that
+/// hold separators.
+fn write_property(out: &mut String, name: &str, property: &IcsProperty) {
+    let mut head = name.to_string();
+    for (key, value) in &property.params {
+        if value.contains([':', ';', ',']) {
+            head.push_str(&format!(";{}=\"{}\"", key, value));
+        } else {
+            head.push_str(&format!(";{}={}", key, value));
+        }
+    }
+    write_line(out, &head, &property.value);
+}
+
+/// Write events as an iCalendar feed named `calendar_name`.
+pub fn to_ics(events: &[CalendarEvent], calendar_name: &str) -> String {
+    let mut out = String::new();
+    write_line(&mut out, "BEGIN", "VCALENDAR");
+    write_line(&mut out, "VERSION", "2.0");
+    write_line(
+        &mut out,
+        "PRODID",
+        &format!(
+            "-//{}//{}//EN",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        ),
+    );
+    write_line(&mut out, "X-WR-CALNAME", &escape(calendar_name));
+    for event in events {
+        write_line(&mut out, "BEGIN", "VEVENT");
+        write_line(&mut out, "UID", &escape(&event.uid));
+        #[cfg(not(target_arch = "wasm32"))]
+        if !event.other.contains_key("DTSTAMP") {
+            write_line(
+                &mut out,
+                "DTSTAMP",
+                &chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string(),
+            );
+        }
+        event.start.write("DTSTART", &mut out);
+        if let Some(end) = &event.end {
+            end.write("DTEND", &mut out);
+        }
+        if let Some(rrule) = &event.rrule {
+            write_line(&mut out, "RRULE", rrule);
+        }
+        for (name, value) in [
+            ("SUMMARY", &event.summary),
+            ("DESCRIPTION", &event.description),
+            ("LOCATION", &event.location),
+        ] {
+            if let Some(value) = value {
+                write_line(&mut out, name, &escape(value));
+            }
+        }
+        if let Some(url) = &event.url {
+            write_line(&mut out, "URL", url);
+        }
+        for (name, properties) in &event.other {
+            for property in properties {
+                write_property(&mut out, name, property);
+            }
+        }
+        write_line(&mut out, "END", "VEVENT");
+    }
+    write_line(&mut out, "END", "VCALENDAR");
+    out
+}
+
+#[cfg(feature = "requests")]
+impl crate::discourse_calendar_events::DiscourseCalendarEvents {
+    /// Get the iCalendar export of events as typed events.
+    pub async fn export_events<'a>(
+        &'a self,
+        params: crate::discourse_calendar_events::ExportEventsIcsParams,
+    ) -> Result<Vec<CalendarEvent>, crate::types::error::Error> {
+        let feed = self.export_events_ics(params).await?;
+        parse_ics(&feed).map_err(|err| crate::types::error::Error::InvalidRequest(err.to_string()))
+    }
+
+    /// List the events of several categories, each event once, ordered by
+    /// start. `params.category_id` is replaced by each of `category_ids`.
+    pub async fn list_events_in_categories<'a>(
+        &'a self,
+        category_ids: &[i64],
+        params: crate::discourse_calendar_events::ListEventsParams,
+    ) -> Result<Vec<CalendarEvent>, crate::types::error::Error> {
+        let mut events = BTreeMap::new();
+        for category_id in category_ids {
+            let resp = self
+                .list_events(crate::discourse_calendar_events::ListEventsParams {
+                    category_id: Some(*category_id),
+                    ..params.clone()
+                })
+                .await?;
+            for event in resp.events {
+                if let Some(calendar_event) =
+                    CalendarEvent::from_event(&event, &self.client.base_url)
+                {
+                    events.insert(event.id, calendar_event);
+                }
+            }
+        }
+        let mut events: Vec<CalendarEvent> = events.into_values().collect();
+        events.sort_by_key(|event| event.start.to_utc());
+        Ok(events)
+    }
+
+    /// Build one iCalendar feed from the events of several categories.
+    pub async fn merged_ics<'a>(
+        &'a self,
+        category_ids: &[i64],
+        params: crate::discourse_calendar_events::ListEventsParams,
+        calendar_name: &str,
+    ) -> Result<String, crate::types::error::Error> {
+        let events = self.list_events_in_categories(category_ids, params).await?;
+        Ok(to_ics(&events, calendar_name))
+    }
+}
@@ -0,0 +1,375 @@
+//! Chunked uploads to external storage.
+//!
+//! With direct S3 uploads enabled, large files are sent as S3 multipart
+//! uploads: `create_multipart` starts one, `batch_presign_multipart_parts`
+//! returns a presigned URL per part, each part is `PUT` to its URL and
+//! `complete_multipart` assembles them from the returned ETags.
+//! [`Uploads::upload_large`] runs these steps, uploading several parts at a
+//! time, retrying failed parts and aborting the upload if one fails for good.
+//!
+//! ```rust,no_run
+//! use discourse_api::large_uploads::LargeUploadOptions;
+//! use discourse_api::types::UploadType;
+//!
+//! async fn example_large_uploads() -> anyhow::Result<()> {
+//!     let client = discourse_api::Client::new_from_env();
+//!     let upload = client
+//!         .uploads()
+//!         .upload_large(
+//!             "recording.mp4",
+//!             UploadType::Composer,
+//!             LargeUploadOptions::default(),
+//!         )
+//!         .await?;
+//!     println!("{}", upload.short_url);
+//!     Ok(())
+//! }
+//! ```
+
+use std::{collections::BTreeMap, path::Path, time::Duration};
+
+use futures::{Stream, StreamExt, TryStreamExt};
+use tokio::io::AsyncReadExt;
+
+use crate::{methods::parse_response, uploads::Uploads};
+
+/// The smallest part size S3 accepts for all parts but the last.
+pub const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+/// The most parts an S3 multipart upload can have.
+pub const MAX_PARTS: u64 = 10_000;
+
+/// How [`Uploads::upload_large`] splits and sends a file.
+#[derive(Clone, Debug)]
+pub struct LargeUploadOptions {
+    /// The size of each part but the last. Sizes below [`MIN_PART_SIZE`],
+    /// which S3 rejects, are refused before anything is sent.
+    pub part_size: usize,
+    /// How many parts are uploaded at a time.
+    pub concurrency: usize,
+    /// How many part URLs are presigned per request.
+    pub presign_batch_size: usize,
+    /// How many times a failed part upload is sent again.
+    pub max_retries: u32,
+    /// The wait before the first retry, doubled for each following one.
+    pub retry_backoff: Duration,
+    /// The longest wait between two retries.
+    pub max_retry_backoff: Duration,
+    /// The SHA-1 of the file, checked by Discourse once the upload completes.
+    pub sha1_checksum: Option<String>,
+}
+
+impl Default for LargeUploadOptions {
+    fn default() -> Self {
+        LargeUploadOptions {
+            part_size: MIN_PART_SIZE,
+            concurrency: 4,
+            presign_batch_size: 10,
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(500),
+            max_retry_backoff: Duration::from_secs(30),
+            sha1_checksum: None,
+        }
+    }
+}
+
+impl LargeUploadOptions {
+    /// The wait before retry number `attempt`, counting from zero.
+    pub(crate) fn retry_delay(&self, attempt: u32) -> Duration {
+        self.retry_backoff
+            .saturating_mul(2u32.checked_pow(attempt).unwrap_or(u32::MAX))
+            .min(self.max_retry_backoff)
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct PresignedParts {
+    presigned_urls: BTreeMap<String, String>,
+}
+
+/// Regroup a stream of chunks into numbered parts of `part_size` bytes.
+fn into_parts<S>(
+    chunks: S,
+    part_size: usize,
+) -> impl Stream<Item = Result<(u64, bytes::Bytes), crate::types::error::Error>>
+where
+    S: Stream<Item = Result<bytes::Bytes, crate::types::error::Error>> + Unpin,
+{
+    futures::stream::try_unfold(
+        (chunks, bytes::BytesMut::new(), 1u64, false),
+        move |(mut chunks, mut buf, number, mut done)| async move {
+            while !done && buf.len() < part_size {
+                match chunks.try_next().await? {
+                    Some(chunk) => buf.extend_from_slice(&chunk),
+                    None => done = true,
+                }
+            }
+            if buf.is_empty() {
+                return Ok(None);
+            }
+            let part = buf.split_to(part_size.min(buf.len())).freeze();
+            Ok(Some(((number, part), (chunks, buf, number + 1, done))))
+        },
+    )
+}
+
+impl Uploads {
+    /// Upload a file to external storage in parts.
+    pub async fn upload_large<P: AsRef<Path>>(
+        &self,
+        path: P,
+        upload_type: crate::types::UploadType,
+        options: LargeUploadOptions,
+    ) -> Result<crate::types::CompleteMultipartResponse, crate::types::error::Error> {
+        let path = path.as_ref();
+        let io_error = |err: std::io::Error| {
+            crate::types::error::Error::InvalidRequest(format!(
+                "reading {}: {}",
+                path.display(),
+                err
+            ))
+        };
+        let file = tokio::fs::File::open(path).await.map_err(io_error)?;
+        let file_size = file.metadata().await.map_err(io_error)?.len();
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let read_size = options.part_size;
+        let chunks = futures::stream::try_unfold(file, move |mut file| async move {
+            let mut buf = vec![0; read_size];
+            let n = file.read(&mut buf).await.map_err(io_error)?;
+            buf.truncate(n);
+            Ok((n > 0).then(|| (bytes::Bytes::from(buf), file)))
+        });
+        self.upload_large_stream(
+            &file_name,
+            file_size,
+            Box::pin(chunks),
+            upload_type,
+            options,
+        )
+        .await
+    }
+
+    /// Upload `file_size` bytes read from a stream to external storage in
+    /// parts.
+    pub async fn upload_large_stream<S>(
+        &self,
+        file_name: &str,
+        file_size: u64,
+        chunks: S,
+        upload_type: crate::types::UploadType,
+        options: LargeUploadOptions,
+    ) -> Result<crate::types::CompleteMultipartResponse, crate::types::error::Error>
+    where
+        S: Stream<Item = Result<bytes::Bytes, crate::types::error::Error>> + Unpin,
+    {
+        if options.part_size < MIN_PART_SIZE {
+            return Err(crate::types::error::Error::InvalidRequest(format!(
+                "part size {} is below the minimum of {} bytes",
+                options.part_size, MIN_PART_SIZE
+            )));
+        }
+        if file_size.div_ceil(options.part_size as u64) > MAX_PARTS {
+            return Err(crate::types::error::Error::InvalidRequest(format!(
+                "{} bytes need more than {} parts of {} bytes",
+                file_size, MAX_PARTS, options.part_size
+            )));
+        }
+
+        let multipart = self
+            .create_multipart(&crate::types::CreateMultipartUploadRequestBody {
+                upload_type,
+                file_name: file_name.to_string(),
+                file_size: file_size as i64,
+                metadata: options.sha1_checksum.clone().map(|sha_1_checksum| {
+                    crate::types::Metadata {
+                        sha_1_checksum: Some(sha_1_checksum),
+                    }
+                }),
+            })
+            .await?;
+
+        match self
+            .upload_parts(&multipart.unique_identifier, file_size, chunks, &options)
+            .await
+        {
+            Ok(parts) => {
+                let result = self
+                    .complete_multipart(&crate::types::CompleteMultipartRequestBody {
+                        unique_identifier: multipart.unique_identifier.clone(),
+                        parts,
+                    })
+                    .await;
+                if result.is_err() {
+                    self.abort(&multipart.external_upload_identifier).await;
+                }
+                result
+            }
+            Err(err) => {
+                self.abort(&multipart.external_upload_identifier).await;
+                Err(err)
+            }
+        }
+    }
+
+    async fn abort(&self, external_upload_identifier: &str) {
+        let result = self
+            .abort_multipart(&crate::types::AbortMultipartRequestBody {
+                external_upload_identifier: external_upload_identifier.to_string(),
+            })
+            .await;
+        if let Err(err) = result {
+            log::warn!(
+                "aborting multipart upload {} failed: {}",
+                external_upload_identifier,
+                err
+            );
+        }
+    }
+
+    /// Upload every part and return the `part_number`/`etag` list
+    /// `complete_multipart` takes.
+    async fn upload_parts<S>(
+        &self,
+        unique_identifier: &str,
+        file_size: u64,
+        chunks: S,
+        options: &LargeUploadOptions,
+    ) -> Result<Vec<serde_json::Value>, crate::types::error::Error>
+    where
+        S: Stream<Item = Result<bytes::Bytes, crate::types::error::Error>> + Unpin,
+    {
+        // Presigned URLs need neither the API key nor the client's retry and
+        // rate limit middleware; `put_part` retries on its own. The timeouts
+        // are the API client's.
+        let storage = reqwest::Client::builder()
+            .user_agent(crate::APP_USER_AGENT)
+            .timeout(Duration::from_secs(600))
+            .connect_timeout(Duration::from_secs(60))
+            .build()?;
+        let storage = &storage;
+        let mut uploaded = 0u64;
+        let mut etags: Vec<(u64, String)> = into_parts(chunks, options.part_size)
+            .chunks(options.presign_batch_size.max(1))
+            .then(|batch| async move {
+                let batch = batch.into_iter().collect::<Result<Vec<_>, _>>()?;
+                let numbers: Vec<u64> = batch.iter().map(|(number, _)| *number).collect();
+                let mut urls = self.presign_parts(unique_identifier, &numbers).await?;
+                batch
+                    .into_iter()
+                    .map(|(number, part)| {
+                        let url = urls.remove(&number.to_string()).ok_or_else(|| {
+                            crate::types::error::Error::InvalidRequest(format!(
+                                "no presigned URL for part {}",
+                                number
+                            ))
+                        })?;
+                        Ok((number, url, part))
+                    })
+                    .collect::<Result<Vec<_>, crate::types::error::Error>>()
+            })
+            .map_ok(|parts| {
+                futures::stream::iter(parts.into_iter().map(Ok::<_, crate::types::error::Error>))
+            })
+            .try_flatten()
+            .map_ok(|(number, url, part)| {
+                uploaded += part.len() as u64;
+                async move {
+                    let etag = put_part(storage, &url, number, part, options).await?;
+                    Ok::<_, crate::types::error::Error>((number, etag))
+                }
+            })
+            .try_buffer_unordered(options.concurrency.max(1))
+            .try_collect()
+            .await?;
+
+        if uploaded != file_size {
+            return Err(crate::types::error::Error::InvalidRequest(format!(
+                "read {} bytes, expected {}",
+                uploaded, file_size
+            )));
+        }
+        etags.sort_by_key(|(number, _)| *number);
+        Ok(etags
+            .into_iter()
+            .map(
+                |(part_number, etag)| serde_json::json!({"part_number": part_number, "etag": etag}),
+            )
+            .collect())
+    }
+
+    async fn presign_parts(
+        &self,
+        unique_identifier: &str,
+        part_numbers: &[u64],
+    ) -> Result<BTreeMap<String, String>, crate::types::error::Error> {
+        // `BatchPresignMultipartPartsResponse` has no fields for the URLs.
+        let mut req = self.client.client.request(
+            http::Method::POST,
+            format!(
+                "{}/{}",
+                self.client.base_url, "uploads/batch-presign-multipart-parts.json"
+            ),
+        );
+        req = req.bearer_auth(&self.client.token);
+        req = req.json(&serde_json::json!({
+            "part_numbers": part_numbers,
+            "unique_identifier": unique_identifier,
+        }));
+        let resp: PresignedParts = parse_response(req.send().await?).await?;
+        Ok(resp.presigned_urls)
+    }
+}
+
+/// `PUT` a part to its presigned URL and return its ETag, retrying
+/// connection errors, `429` and `5xx` answers.
+async fn put_part(
+    storage: &reqwest::Client,
+    url: &str,
+    number: u64,
+    part: bytes::Bytes,
+    options: &LargeUploadOptions,
+) -> Result<String, crate::types::error::Error> {
+    let mut attempt = 0;
+    loop {
+        let result = storage
+            .request(http::Method::PUT, url)
+            .body(part.clone())
+            .send()
+            .await;
+        let retry = match &result {
+            Ok(resp) => {
+                resp.status().is_server_error()
+                    || resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            Err(_) => true,
+        };
+        if retry && attempt < options.max_retries {
+            tokio::time::sleep(options.retry_delay(attempt)).await;
+            attempt += 1;
+            continue;
+        }
+
+        let resp = result?;
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(crate::types::error::Error::Server {
+                body: resp.text().await.unwrap_or_default(),
+                status,
+            });
+        }
+        return resp
+            .headers()
+            .get(http::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(|etag| etag.to_string())
+            .ok_or_else(|| {
+                crate::types::error::Error::InvalidRequest(format!(
+                    "no ETag in the answer for part {}",
+                    number
+                ))
+            });
+    }
+}
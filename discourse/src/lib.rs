@@ -122,37 +122,73 @@
 #[cfg(feature = "requests")]
 pub mod backups;
 #[cfg(feature = "requests")]
+pub mod badge_grants;
+#[cfg(feature = "requests")]
 pub mod badges;
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "requests")]
+pub mod bulk_invites;
 #[cfg(feature = "requests")]
 pub mod categories;
 #[cfg(feature = "requests")]
+pub mod category_tree;
+#[cfg(feature = "requests")]
+pub mod chat;
+#[cfg(feature = "requests")]
 pub mod discourse_calendar_events;
 #[cfg(feature = "requests")]
+pub mod errors;
+#[cfg(feature = "requests")]
+pub mod group_sync;
+#[cfg(feature = "requests")]
 pub mod groups;
+pub mod ics;
 #[cfg(feature = "requests")]
 pub mod invites;
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "requests")]
+pub mod large_uploads;
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "requests")]
+pub mod message_bus;
 mod methods;
 #[cfg(feature = "requests")]
 pub mod notifications;
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "requests")]
+pub mod pm_archive;
 #[cfg(feature = "requests")]
 pub mod posts;
 #[cfg(feature = "requests")]
 pub mod private_messages;
 #[cfg(feature = "requests")]
+#[cfg(feature = "retry")]
+#[cfg(not(target_arch = "wasm32"))]
+pub mod rate_limit;
+#[cfg(feature = "requests")]
+pub mod reviewables;
+#[cfg(feature = "requests")]
 pub mod search;
 #[cfg(feature = "requests")]
 pub mod site;
+pub mod sso;
+#[cfg(feature = "requests")]
+pub mod streams;
 #[cfg(feature = "requests")]
 pub mod tags;
 #[cfg(test)]
 mod tests;
 #[cfg(feature = "requests")]
+pub mod threads;
+#[cfg(feature = "requests")]
 pub mod topics;
 pub mod types;
 #[cfg(feature = "requests")]
 pub mod uploads;
+pub mod user_api_keys;
 #[cfg(feature = "requests")]
 pub mod users;
+pub mod webhooks;
 
 #[cfg(feature = "requests")]
 use std::env;
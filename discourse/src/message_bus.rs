@@ -0,0 +1,371 @@
+//! Live updates through MessageBus.
+//!
+//! Discourse publishes notifications, new topics and post changes on
+//! MessageBus channels. A subscriber long-polls
+//! `/message-bus/{client_id}/poll` with the last message ID it saw on each
+//! channel, and the server answers as soon as a channel has newer messages.
+//!
+//! ```rust,no_run
+//! use futures_util::TryStreamExt;
+//!
+//! use discourse_api::message_bus::MessageBusEvent;
+//!
+//! async fn example_message_bus() -> anyhow::Result<()> {
+//!     let client = discourse_api::Client::new_from_env();
+//!     let mut bus = client.message_bus();
+//!     bus.subscribe("/notification-alert/42", -1);
+//!     bus.subscribe("/latest", -1);
+//!
+//!     let mut events = bus.into_stream();
+//!     while let Some(event) = events.try_next().await? {
+//!         match event {
+//!             MessageBusEvent::NotificationAlert { alert, .. } if alert.is_mention() => {
+//!                 println!("mentioned by {:?}: {:?}", alert.username, alert.post_url);
+//!             }
+//!             MessageBusEvent::TopicList { topic_id, .. } => println!("topic {}", topic_id),
+//!             _ => {}
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    time::Duration,
+};
+
+use futures::Stream;
+
+use crate::{errors::ErrorExt, methods::parse_response, Client};
+
+/// The channel the server uses to report current channel positions.
+const STATUS_CHANNEL: &str = "/__status";
+
+/// The `notification_type` of mentions.
+pub const NOTIFICATION_TYPE_MENTIONED: i64 = 1;
+
+/// A message as delivered by MessageBus.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct MessageBusMessage {
+    pub global_id: i64,
+    pub message_id: i64,
+    pub channel: String,
+    pub data: serde_json::Value,
+}
+
+/// The data of a `/notification-alert/{user_id}` message, sent when the user
+/// gets a notification.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct NotificationAlert {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notification_type: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic_title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_number: Option<i64>,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+impl NotificationAlert {
+    /// Whether the user was mentioned.
+    pub fn is_mention(&self) -> bool {
+        self.notification_type == Some(NOTIFICATION_TYPE_MENTIONED)
+    }
+}
+
+/// The data of a `/notification/{user_id}` message, sent when the user's
+/// unread counts change.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct NotificationState {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unread_notifications: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unread_high_priority_notifications: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub all_unread_notifications_count: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_notification: Option<serde_json::Value>,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The data of a `/topic/{topic_id}` message, sent when a post of the topic
+/// is created, edited or deleted.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct PostChange {
+    /// The post ID.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_number: Option<i64>,
+    /// E.g. `created`, `revised`, `deleted` or `acted`.
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_editor_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A MessageBus message, by channel.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageBusEvent {
+    NotificationAlert {
+        user_id: i64,
+        alert: NotificationAlert,
+    },
+    Notification {
+        user_id: i64,
+        state: NotificationState,
+    },
+    /// A message of `/new` or `/latest`.
+    TopicList {
+        channel: String,
+        topic_id: i64,
+        /// E.g. `new_topic` or `latest`.
+        message_type: Option<String>,
+        payload: serde_json::Value,
+    },
+    Post {
+        topic_id: i64,
+        change: PostChange,
+    },
+    /// A message of another channel, or one that did not match its channel's
+    /// type.
+    Other(MessageBusMessage),
+}
+
+impl From<MessageBusMessage> for MessageBusEvent {
+    fn from(message: MessageBusMessage) -> Self {
+        let id_after =
+            |prefix: &str| -> Option<i64> { message.channel.strip_prefix(prefix)?.parse().ok() };
+        fn data<T: serde::de::DeserializeOwned>(data: &serde_json::Value) -> Option<T> {
+            serde_json::from_value(data.clone()).ok()
+        }
+
+        let event = if let Some(user_id) = id_after("/notification-alert/") {
+            data(&message.data).map(|alert| MessageBusEvent::NotificationAlert { user_id, alert })
+        } else if let Some(user_id) = id_after("/notification/") {
+            data(&message.data).map(|state| MessageBusEvent::Notification { user_id, state })
+        } else if let Some(topic_id) = id_after("/topic/") {
+            data(&message.data).map(|change| MessageBusEvent::Post { topic_id, change })
+        } else if message.channel == "/new" || message.channel == "/latest" {
+            message
+                .data
+                .get("topic_id")
+                .and_then(|v| v.as_i64())
+                .map(|topic_id| MessageBusEvent::TopicList {
+                    channel: message.channel.clone(),
+                    topic_id,
+                    message_type: message
+                        .data
+                        .get("message_type")
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_string()),
+                    payload: message.data.get("payload").cloned().unwrap_or_default(),
+                })
+        } else {
+            None
+        };
+        event.unwrap_or(MessageBusEvent::Other(message))
+    }
+}
+
+/// A MessageBus subscriber: a client ID and the position of each subscribed
+/// channel.
+#[derive(Clone, Debug)]
+pub struct MessageBus {
+    pub client: Client,
+    acting_username: Option<String>,
+    client_id: String,
+    positions: BTreeMap<String, i64>,
+    seq: u64,
+    /// The wait before polling again after a failed poll, doubled for each
+    /// following failure up to `max_backoff`.
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl MessageBus {
+    /// Create a subscriber with a new client ID.
+    pub fn new(client: Client) -> Self {
+        MessageBus {
+            client,
+            acting_username: None,
+            client_id: uuid::Uuid::new_v4().simple().to_string(),
+            positions: BTreeMap::new(),
+            seq: 0,
+            backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+
+    /// Poll as another user, to receive the messages that user can see.
+    /// Needs an admin API key valid for all users.
+    pub fn as_user<U: ToString>(mut self, username: U) -> Self {
+        self.acting_username = Some(username.to_string());
+        self
+    }
+
+    /// The client ID polls are sent with.
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// Subscribe to a channel from the message after `last_id`. With `-1`,
+    /// only messages published from now on are received.
+    pub fn subscribe<C: ToString>(&mut self, channel: C, last_id: i64) {
+        self.positions.insert(channel.to_string(), last_id);
+    }
+
+    /// Stop receiving messages of a channel.
+    pub fn unsubscribe(&mut self, channel: &str) {
+        self.positions.remove(channel);
+    }
+
+    /// The last message ID seen on each subscribed channel.
+    pub fn positions(&self) -> &BTreeMap<String, i64> {
+        &self.positions
+    }
+
+    /// Send one long poll and return the messages it received, updating the
+    /// channel positions.
+    pub async fn poll(&mut self) -> Result<Vec<MessageBusMessage>, crate::types::error::Error> {
+        let mut req = self.client.client.request(
+            http::Method::POST,
+            format!(
+                "{}/message-bus/{}/poll",
+                self.client.base_url, self.client_id
+            ),
+        );
+        req = req.bearer_auth(&self.client.token);
+        if let Some(username) = &self.acting_username {
+            req = req.header(crate::chat::API_USERNAME_HEADER, username);
+        }
+        // Answer with a JSON array instead of a chunked stream.
+        req = req.header("Dont-Chunk", "true");
+        self.seq += 1;
+        let mut form: Vec<(String, String)> = self
+            .positions
+            .iter()
+            .map(|(channel, id)| (channel.clone(), id.to_string()))
+            .collect();
+        form.push(("__seq".to_string(), self.seq.to_string()));
+        req = req.form(&form);
+
+        let messages: Vec<MessageBusMessage> = parse_response(req.send().await?).await?;
+        Ok(self.receive(messages))
+    }
+
+    /// Update the channel positions from received messages and return the
+    /// ones for subscribers.
+    pub(crate) fn receive(&mut self, messages: Vec<MessageBusMessage>) -> Vec<MessageBusMessage> {
+        messages
+            .into_iter()
+            .filter(|message| {
+                if message.channel == STATUS_CHANNEL {
+                    if let Some(status) = message.data.as_object() {
+                        for (channel, id) in status {
+                            if let (Some(position), Some(id)) =
+                                (self.positions.get_mut(channel), id.as_i64())
+                            {
+                                *position = id;
+                            }
+                        }
+                    }
+                    return false;
+                }
+                match self.positions.get_mut(&message.channel) {
+                    Some(position) => {
+                        *position = (*position).max(message.message_id);
+                        true
+                    }
+                    None => false,
+                }
+            })
+            .collect()
+    }
+
+    /// Poll forever, yielding each message as an event.
+    ///
+    /// Failed polls are retried with an increasing wait, or after the wait a
+    /// rate limit asks for. The stream only ends
+    /// with an error when the server rejects the request, e.g. for a bad API
+    /// key.
+    pub fn into_stream(
+        self,
+    ) -> impl Stream<Item = Result<MessageBusEvent, crate::types::error::Error>> + Unpin {
+        Box::pin(futures::stream::unfold(
+            (self, VecDeque::new(), 0u32, false),
+            |(mut bus, mut pending, mut failures, done)| async move {
+                if done {
+                    return None;
+                }
+                loop {
+                    if let Some(message) = pending.pop_front() {
+                        let event = MessageBusEvent::from(message);
+                        return Some((Ok(event), (bus, pending, failures, false)));
+                    }
+                    match bus.poll().await {
+                        Ok(messages) => {
+                            failures = 0;
+                            pending.extend(messages);
+                        }
+                        Err(err)
+                            if err.status().is_some_and(|status| {
+                                status.is_client_error()
+                                    && status != reqwest::StatusCode::TOO_MANY_REQUESTS
+                            }) =>
+                        {
+                            return Some((Err(err), (bus, pending, failures, true)));
+                        }
+                        Err(err) => {
+                            let wait = err.retry_after().unwrap_or_else(|| {
+                                bus.backoff
+                                    .saturating_mul(2u32.saturating_pow(failures))
+                                    .min(bus.max_backoff)
+                            });
+                            log::warn!("MessageBus poll failed, retrying in {:?}: {}", wait, err);
+                            failures += 1;
+                            tokio::time::sleep(wait).await;
+                        }
+                    }
+                }
+            },
+        ))
+    }
+}
+
+impl Client {
+    /// Return a MessageBus subscriber with a new client ID.
+    pub fn message_bus(&self) -> MessageBus {
+        MessageBus::new(self.clone())
+    }
+}
@@ -1 +1,126 @@
+//! Helpers shared by the hand-written modules.
 
+/// Deserialize a successful response, or turn a failed one into a server error.
+#[cfg(feature = "requests")]
+pub(crate) async fn parse_response<T>(
+    resp: reqwest::Response,
+) -> Result<T, crate::types::error::Error>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let status = resp.status();
+    let text = resp.text().await.unwrap_or_default();
+    if status.is_success() {
+        serde_json::from_str(&text).map_err(|err| {
+            crate::types::error::Error::from_serde_error(
+                format_serde_error::SerdeError::new(text.to_string(), err),
+                status,
+            )
+        })
+    } else {
+        Err(crate::types::error::Error::Server {
+            body: text.to_string(),
+            status,
+        })
+    }
+}
+
+/// Read the body of a successful response, or turn a failed one into a server
+/// error.
+#[cfg(feature = "requests")]
+pub(crate) async fn bytes_response(
+    resp: reqwest::Response,
+) -> Result<bytes::Bytes, crate::types::error::Error> {
+    let status = resp.status();
+    if status.is_success() {
+        Ok(resp.bytes().await?)
+    } else {
+        let text = resp.text().await.unwrap_or_default();
+        Err(crate::types::error::Error::Server {
+            body: text.to_string(),
+            status,
+        })
+    }
+}
+
+/// Find the user with an email address, among primary and secondary
+/// addresses. Needs an admin API key.
+#[cfg(feature = "requests")]
+pub(crate) async fn find_username_by_email(
+    client: &crate::Client,
+    email: &str,
+) -> Result<Option<String>, crate::types::error::Error> {
+    let users = crate::users::Users::new(client.clone())
+        .admin_list(crate::users::AdminListParams {
+            email: Some(email.to_string()),
+            show_emails: Some(true),
+            ..Default::default()
+        })
+        .await?;
+    Ok(users
+        .into_iter()
+        .find(|user| {
+            user.email
+                .as_deref()
+                .is_some_and(|e| e.eq_ignore_ascii_case(email))
+                || user
+                    .secondary_emails
+                    .iter()
+                    .flatten()
+                    .any(|e| e.as_str().is_some_and(|e| e.eq_ignore_ascii_case(email)))
+        })
+        .map(|user| user.username))
+}
+
+#[cfg(feature = "requests")]
+impl crate::Client {
+    /// Return a reference to an interface that provides access to Chat operations.
+    pub fn chat(&self) -> crate::chat::Chat {
+        crate::chat::Chat::new(self.clone())
+    }
+
+    /// Return a reference to an interface that provides access to Reviewables operations.
+    pub fn reviewables(&self) -> crate::reviewables::Reviewables {
+        crate::reviewables::Reviewables::new(self.clone())
+    }
+}
+
+#[cfg(feature = "requests")]
+#[cfg(feature = "retry")]
+#[cfg(not(target_arch = "wasm32"))]
+impl crate::Client {
+    /// Append a middleware to the stacks of the client, after the tracing and
+    /// retries installed by the generated constructors.
+    pub(crate) fn with_middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: reqwest_middleware::Middleware + Clone,
+    {
+        self.client = reqwest_middleware::ClientBuilder::from_client(self.client)
+            .with(middleware.clone())
+            .build();
+        self.client_http1_only =
+            reqwest_middleware::ClientBuilder::from_client(self.client_http1_only)
+                .with(middleware)
+                .build();
+        self
+    }
+
+    /// Wait out `429` responses as the server asks, and space requests to a
+    /// per-minute budget, with a rate limiter.
+    ///
+    /// The limiter runs inside the retries, so they never see a `429`. The
+    /// clones of the returned client share it, and the same limiter can be set
+    /// on several clients that use the same API key. Without one, `429`s are
+    /// retried by the generated exponential backoff.
+    ///
+    /// ```rust,no_run
+    /// use discourse_api::rate_limit::{RateLimitConfig, RateLimiter};
+    ///
+    /// // With the budget from `DISCOURSE_MAX_ADMIN_API_REQS_PER_MINUTE`.
+    /// let client = discourse_api::Client::new_from_env()
+    ///     .with_rate_limiter(RateLimiter::new(RateLimitConfig::from_env()));
+    /// ```
+    pub fn with_rate_limiter(self, limiter: crate::rate_limit::RateLimiter) -> Self {
+        self.with_middleware(limiter)
+    }
+}
@@ -0,0 +1,597 @@
+//! Private message archives.
+//!
+//! [`PrivateMessages::export_archive`] walks every private message topic in a
+//! user's inbox and sent folder, fetches each complete thread and the uploads
+//! its posts link to, and writes a self-contained directory:
+//!
+//! ```text
+//! index.json
+//! threads/<topic_id>-<slug>/thread.json
+//! threads/<topic_id>-<slug>/thread.html
+//! threads/<topic_id>-<slug>/thread.md
+//! threads/<topic_id>-<slug>/attachments/...
+//! messages.mbox
+//! ```
+//!
+//! Uploads are fetched with a plain `GET` of the URLs posts link to: the
+//! `uploads` API only creates uploads and has no endpoint returning one's
+//! content, and an upload may be served from a storage service or CDN
+//! rather than the forum.
+//!
+//! ```rust,no_run
+//! use discourse_api::pm_archive::ArchiveOptions;
+//!
+//! async fn example_pm_archive() -> anyhow::Result<()> {
+//!     let client = discourse_api::Client::new_from_env();
+//!     let index = client
+//!         .private_messages()
+//!         .export_archive(
+//!             "alice",
+//!             "archives/alice",
+//!             ArchiveOptions {
+//!                 mbox: true,
+//!                 ..Default::default()
+//!             },
+//!         )
+//!         .await?;
+//!     println!("{} threads", index.threads.len());
+//!     Ok(())
+//! }
+//! ```
+
+use std::{collections::BTreeMap, path::Path};
+
+use base64::Engine;
+use futures::{StreamExt, TryStreamExt};
+
+use crate::{
+    methods::bytes_response, private_messages::PrivateMessages, streams::topic_list_pages,
+};
+
+/// What [`PrivateMessages::export_archive`] writes.
+#[derive(Clone, Debug)]
+pub struct ArchiveOptions {
+    /// Write `thread.html` for each thread.
+    pub html: bool,
+    /// Write `thread.md` for each thread, fetching the Markdown source of
+    /// every post.
+    pub markdown: bool,
+    /// Write all posts to `messages.mbox`.
+    pub mbox: bool,
+    /// Download the uploads posts link to.
+    pub attachments: bool,
+    /// How many posts or uploads are fetched at a time.
+    pub concurrency: usize,
+}
+
+impl Default for ArchiveOptions {
+    fn default() -> Self {
+        ArchiveOptions {
+            html: true,
+            markdown: true,
+            mbox: false,
+            attachments: true,
+            concurrency: 4,
+        }
+    }
+}
+
+/// A private message topic of a user's inbox or sent folder.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct PmTopic {
+    pub id: i64,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub posts_count: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_posted_at: Option<String>,
+}
+
+/// A post of an archived thread.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ArchivedPost {
+    pub id: i64,
+    pub post_number: i64,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to_post_number: Option<i64>,
+    /// The rendered HTML.
+    #[serde(default)]
+    pub cooked: String,
+    /// The Markdown source, when fetched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
+}
+
+impl From<crate::types::Posts> for ArchivedPost {
+    fn from(post: crate::types::Posts) -> Self {
+        ArchivedPost {
+            id: post.id.unwrap_or_default(),
+            post_number: post.post_number.unwrap_or_default(),
+            username: post.username.unwrap_or_default(),
+            name: post.name.filter(|name| !name.is_empty()),
+            created_at: post.created_at,
+            updated_at: post.updated_at,
+            reply_to_post_number: post.reply_to_post_number,
+            cooked: post.cooked.unwrap_or_default(),
+            raw: None,
+        }
+    }
+}
+
+/// An upload linked from a thread.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ArchivedAttachment {
+    /// The URL as it appears in the posts.
+    pub url: String,
+    /// The file, relative to the thread directory.
+    pub path: String,
+    pub size: u64,
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub data: bytes::Bytes,
+}
+
+/// A complete private message thread.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ArchivedThread {
+    pub topic: PmTopic,
+    pub posts: Vec<ArchivedPost>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<ArchivedAttachment>,
+    /// Uploads that could not be downloaded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_attachments: Vec<String>,
+}
+
+impl ArchivedThread {
+    /// The directory of the thread in an archive.
+    pub fn directory(&self) -> String {
+        match self.topic.slug.as_deref().filter(|slug| !slug.is_empty()) {
+            Some(slug) => format!("{}-{}", self.topic.id, sanitize_file_name(slug)),
+            None => self.topic.id.to_string(),
+        }
+    }
+}
+
+/// An entry of `index.json`.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ArchiveIndexEntry {
+    pub topic_id: i64,
+    pub title: String,
+    /// The thread directory, relative to the archive.
+    pub directory: String,
+    pub posts: usize,
+    pub attachments: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_attachments: Vec<String>,
+}
+
+/// The contents of `index.json`.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ArchiveIndex {
+    pub username: String,
+    pub threads: Vec<ArchiveIndexEntry>,
+}
+
+/// Keep the characters of a file name that are safe on every platform.
+fn sanitize_file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    name.trim_start_matches('.').to_string()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The upload URLs `href` and `src` attributes of rendered posts point to.
+pub fn upload_links(cooked: &str) -> Vec<String> {
+    let mut links = vec![];
+    for attribute in ["href=\"", "src=\""] {
+        for (start, _) in cooked.match_indices(attribute) {
+            let rest = &cooked[start + attribute.len()..];
+            let Some(end) = rest.find('"') else {
+                continue;
+            };
+            let link = rest[..end].replace("&amp;", "&");
+            if (link.contains("/uploads/") || link.contains("/secure-uploads/"))
+                && !links.contains(&link)
+            {
+                links.push(link);
+            }
+        }
+    }
+    links
+}
+
+/// Render a thread as a standalone HTML page, with upload links pointing to
+/// the downloaded files.
+pub fn render_html(thread: &ArchivedThread) -> String {
+    let title = escape_html(&thread.topic.title);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>body{{font-family:sans-serif;max-width:50em;margin:auto}}\
+         article{{border-top:1px solid #ddd;padding:1em 0}}img{{max-width:100%}}</style>\n\
+         </head>\n<body>\n<h1>{}</h1>\n",
+        title, title
+    );
+    for post in &thread.posts {
+        let mut cooked = post.cooked.clone();
+        for attachment in &thread.attachments {
+            cooked = cooked.replace(
+                &format!("\"{}\"", escape_html(&attachment.url)),
+                &format!("\"{}\"", attachment.path),
+            );
+        }
+        out.push_str(&format!(
+            "<article id=\"post-{}\">\n<header><strong>{}</strong> <time datetime=\"{}\">{}</time> \
+             <a href=\"#post-{}\">#{}</a></header>\n{}\n</article>\n",
+            post.post_number,
+            escape_html(&post.username),
+            escape_html(post.created_at.as_deref().unwrap_or_default()),
+            escape_html(post.created_at.as_deref().unwrap_or_default()),
+            post.post_number,
+            post.post_number,
+            cooked
+        ));
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Render a thread as Markdown, from the posts' source when fetched and
+/// their HTML otherwise.
+pub fn render_markdown(thread: &ArchivedThread) -> String {
+    let mut out = format!("# {}\n", thread.topic.title);
+    for post in &thread.posts {
+        out.push_str(&format!(
+            "\n## #{} {} ({})\n\n{}\n",
+            post.post_number,
+            post.username,
+            post.created_at.as_deref().unwrap_or_default(),
+            post.raw.as_deref().unwrap_or(&post.cooked).trim_end()
+        ));
+    }
+    if !thread.attachments.is_empty() {
+        out.push_str("\n## Attachments\n\n");
+        for attachment in &thread.attachments {
+            out.push_str(&format!("- [{}]({})\n", attachment.url, attachment.path));
+        }
+    }
+    out
+}
+
+/// Encode a header value as RFC 2047 when it is not plain ASCII.
+fn encode_header(value: &str) -> String {
+    if value.is_ascii() {
+        value.to_string()
+    } else {
+        format!(
+            "=?utf-8?B?{}?=",
+            base64::engine::general_purpose::STANDARD.encode(value)
+        )
+    }
+}
+
+/// Render the posts of a thread as mbox messages, with addresses at `host`.
+pub fn render_mbox(thread: &ArchivedThread, host: &str) -> String {
+    let mut out = String::new();
+    for post in &thread.posts {
+        let date = post
+            .created_at
+            .as_deref()
+            .and_then(|d| chrono::DateTime::parse_from_rfc3339(d).ok())
+            .unwrap_or_default();
+        let address = format!("{}@{}", post.username, host);
+        let message_id = |n: i64| format!("<{}.{}@{}>", thread.topic.id, n, host);
+
+        out.push_str(&format!(
+            "From {} {}\n",
+            address,
+            date.format("%a %b %e %H:%M:%S %Y")
+        ));
+        out.push_str(&format!(
+            "From: {} <{}>\n",
+            encode_header(post.name.as_deref().unwrap_or(&post.username)),
+            address
+        ));
+        out.push_str(&format!("Date: {}\n", date.to_rfc2822()));
+        let subject = if post.post_number > 1 {
+            format!("Re: {}", thread.topic.title)
+        } else {
+            thread.topic.title.clone()
+        };
+        out.push_str(&format!("Subject: {}\n", encode_header(&subject)));
+        out.push_str(&format!("Message-ID: {}\n", message_id(post.post_number)));
+        if post.post_number > 1 {
+            let parent = message_id(post.reply_to_post_number.unwrap_or(1));
+            out.push_str(&format!(
+                "In-Reply-To: {}\nReferences: {}\n",
+                parent, parent
+            ));
+        }
+        let (content_type, body) = match &post.raw {
+            Some(raw) => ("text/plain", raw.as_str()),
+            None => ("text/html", post.cooked.as_str()),
+        };
+        out.push_str(&format!(
+            "MIME-Version: 1.0\nContent-Type: {}; charset=utf-8\nContent-Transfer-Encoding: 8bit\n\n",
+            content_type
+        ));
+        for line in body.lines() {
+            // Quote lines that would start a new message.
+            if line.trim_start_matches('>').starts_with("From ") {
+                out.push('>');
+            }
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn io_error(path: &Path, err: std::io::Error) -> crate::types::error::Error {
+    crate::types::error::Error::InvalidRequest(format!("writing {}: {}", path.display(), err))
+}
+
+async fn write_file(path: &Path, contents: &[u8]) -> Result<(), crate::types::error::Error> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|err| io_error(parent, err))?;
+    }
+    tokio::fs::write(path, contents)
+        .await
+        .map_err(|err| io_error(path, err))
+}
+
+impl PrivateMessages {
+    /// List every private message topic in a user's inbox and sent folder.
+    pub async fn list_all<'a>(
+        &'a self,
+        username: &'a str,
+    ) -> Result<Vec<PmTopic>, crate::types::error::Error> {
+        let mut topics = BTreeMap::new();
+        for folder in ["private-messages", "private-messages-sent"] {
+            let path = format!("topics/{}/{}.json", folder, username);
+            let pages: Vec<serde_json::Value> = topic_list_pages(&self.client, &path, vec![])
+                .try_collect()
+                .await?;
+            for page in pages {
+                let folder_topics = page
+                    .pointer("/topic_list/topics")
+                    .cloned()
+                    .unwrap_or_default();
+                if folder_topics.is_null() {
+                    continue;
+                }
+                for topic in serde_json::from_value::<Vec<PmTopic>>(folder_topics)? {
+                    topics.entry(topic.id).or_insert(topic);
+                }
+            }
+        }
+        Ok(topics.into_values().collect())
+    }
+
+    /// Fetch a complete thread, with the Markdown source of its posts and its
+    /// uploads as set in `options`.
+    pub async fn archive_thread(
+        &self,
+        topic: &PmTopic,
+        options: &ArchiveOptions,
+    ) -> Result<ArchivedThread, crate::types::error::Error> {
+        let topics = crate::topics::Topics::new(self.client.clone());
+        let topic_id = topic.id.to_string();
+        let mut posts: Vec<ArchivedPost> = topics
+            .get_thread(&topic_id)
+            .await?
+            .into_iter()
+            .map(ArchivedPost::from)
+            .collect();
+
+        if options.markdown {
+            let raws: Vec<Option<String>> = futures::stream::iter(&posts)
+                .map(|post| self.get_raw(topic.id, post.post_number))
+                .buffered(options.concurrency.max(1))
+                .try_collect()
+                .await?;
+            for (post, raw) in posts.iter_mut().zip(raws) {
+                post.raw = raw;
+            }
+        }
+
+        let mut thread = ArchivedThread {
+            topic: topic.clone(),
+            posts,
+            attachments: vec![],
+            failed_attachments: vec![],
+        };
+        if options.attachments {
+            let mut links = vec![];
+            for post in &thread.posts {
+                for link in upload_links(&post.cooked) {
+                    if !links.contains(&link) {
+                        links.push(link);
+                    }
+                }
+            }
+            let downloads: Vec<(String, Result<bytes::Bytes, crate::types::error::Error>)> =
+                futures::stream::iter(links)
+                    .map(|link| async move {
+                        let data = self.download(&link).await;
+                        (link, data)
+                    })
+                    .buffered(options.concurrency.max(1))
+                    .collect()
+                    .await;
+            for (i, (url, data)) in downloads.into_iter().enumerate() {
+                match data {
+                    Ok(data) => {
+                        let name = url
+                            .split(['?', '#'])
+                            .next()
+                            .and_then(|path| path.rsplit('/').next())
+                            .map(sanitize_file_name)
+                            .unwrap_or_default();
+                        thread.attachments.push(ArchivedAttachment {
+                            path: format!("attachments/{:03}-{}", i + 1, name),
+                            size: data.len() as u64,
+                            url,
+                            data,
+                        });
+                    }
+                    Err(err) => {
+                        log::warn!("downloading {} failed: {}", url, err);
+                        thread.failed_attachments.push(url);
+                    }
+                }
+            }
+        }
+        Ok(thread)
+    }
+
+    /// Get the Markdown source of a post, or `None` when it is not available.
+    async fn get_raw(
+        &self,
+        topic_id: i64,
+        post_number: i64,
+    ) -> Result<Option<String>, crate::types::error::Error> {
+        let mut req = self.client.client.request(
+            http::Method::GET,
+            format!("{}/raw/{}/{}", self.client.base_url, topic_id, post_number),
+        );
+        req = req.bearer_auth(&self.client.token);
+        match bytes_response(req.send().await?).await {
+            Ok(raw) => Ok(Some(String::from_utf8_lossy(&raw).into_owned())),
+            Err(err) if err.status() == Some(reqwest::StatusCode::NOT_FOUND) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Download an upload. Credentials are only sent to the forum itself, not
+    /// to the storage service or CDN an upload may be served from.
+    async fn download(&self, link: &str) -> Result<bytes::Bytes, crate::types::error::Error> {
+        let url = if let Some(rest) = link.strip_prefix("//") {
+            format!("https://{}", rest)
+        } else if link.starts_with('/') {
+            format!("{}{}", self.client.base_url, link)
+        } else {
+            link.to_string()
+        };
+        let same_origin = match (
+            url::Url::parse(&url),
+            url::Url::parse(&self.client.base_url),
+        ) {
+            (Ok(url), Ok(base)) => url.origin() == base.origin(),
+            _ => false,
+        };
+        let mut req = self.client.client.request(http::Method::GET, &url);
+        if same_origin {
+            req = req.bearer_auth(&self.client.token);
+        }
+        bytes_response(req.send().await?).await
+    }
+
+    /// Write the archive of every private message thread of a user to `dir`.
+    pub async fn export_archive<P: AsRef<Path>>(
+        &self,
+        username: &str,
+        dir: P,
+        options: ArchiveOptions,
+    ) -> Result<ArchiveIndex, crate::types::error::Error> {
+        let dir = dir.as_ref();
+        let host = url::Url::parse(&self.client.base_url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_string()))
+            .unwrap_or_else(|| "discourse.invalid".to_string());
+
+        let mut index = ArchiveIndex {
+            username: username.to_string(),
+            threads: vec![],
+        };
+        let mut mbox = String::new();
+        for topic in self.list_all(username).await? {
+            let thread = self.archive_thread(&topic, &options).await?;
+            let directory = format!("threads/{}", thread.directory());
+            let thread_dir = dir.join(&directory);
+
+            write_file(
+                &thread_dir.join("thread.json"),
+                &serde_json::to_vec_pretty(&thread)?,
+            )
+            .await?;
+            if options.html {
+                write_file(
+                    &thread_dir.join("thread.html"),
+                    render_html(&thread).as_bytes(),
+                )
+                .await?;
+            }
+            if options.markdown {
+                write_file(
+                    &thread_dir.join("thread.md"),
+                    render_markdown(&thread).as_bytes(),
+                )
+                .await?;
+            }
+            for attachment in &thread.attachments {
+                write_file(&thread_dir.join(&attachment.path), &attachment.data).await?;
+            }
+            if options.mbox {
+                mbox.push_str(&render_mbox(&thread, &host));
+            }
+
+            index.threads.push(ArchiveIndexEntry {
+                topic_id: thread.topic.id,
+                title: thread.topic.title.clone(),
+                directory,
+                posts: thread.posts.len(),
+                attachments: thread.attachments.len(),
+                failed_attachments: thread.failed_attachments.clone(),
+            });
+        }
+
+        if options.mbox {
+            write_file(&dir.join("messages.mbox"), mbox.as_bytes()).await?;
+        }
+        write_file(&dir.join("index.json"), &serde_json::to_vec_pretty(&index)?).await?;
+        Ok(index)
+    }
+}
@@ -0,0 +1,229 @@
+//! Rate limit handling.
+//!
+//! Discourse answers requests over its limits with `429 Too Many Requests`, a
+//! `Retry-After` header and a body like
+//! `{"errors": [...], "extras": {"wait_seconds": 12}}`. A [`RateLimiter`]
+//! installed with the client's `with_rate_limiter` waits exactly that long
+//! before sending the request again, and gives up with a
+//! [`RateLimitExceeded`](crate::errors::RateLimitExceeded) once `max_retries`
+//! is reached or the wait is longer than `max_wait`; see
+//! [`ErrorExt`](crate::errors::ErrorExt).
+//!
+//! Admin API keys are limited to 60 requests a minute by default, changed with
+//! `DISCOURSE_MAX_ADMIN_API_REQS_PER_MINUTE` on the server. Setting the same
+//! budget on the limiter spaces requests out instead of running into the
+//! limit. The limiter is shared by every clone of a client, so the budget
+//! holds for all of them together.
+//!
+//! ```rust,no_run
+//! use discourse_api::rate_limit::{RateLimitConfig, RateLimiter};
+//!
+//! let limiter = RateLimiter::new(RateLimitConfig {
+//!     max_per_minute: Some(60),
+//!     ..Default::default()
+//! });
+//! let client = discourse_api::Client::new_from_env().with_rate_limiter(limiter.clone());
+//!
+//! // Later, for the client and all its clones.
+//! limiter.set_config(RateLimitConfig::default());
+//! ```
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::errors::RateLimitExceeded;
+
+/// The environment variable read by [`RateLimitConfig::from_env`], named
+/// after the server setting.
+pub const MAX_REQS_PER_MINUTE_ENV: &str = "DISCOURSE_MAX_ADMIN_API_REQS_PER_MINUTE";
+
+/// Configuration for a [`RateLimiter`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimitConfig {
+    /// Requests allowed per minute, or `None` to only react to `429`s.
+    pub max_per_minute: Option<u32>,
+    /// How many times a request answered with `429` is sent again after waiting.
+    pub max_retries: u32,
+    /// Wait used when a `429` carries neither `wait_seconds` nor `Retry-After`.
+    pub default_wait: Duration,
+    /// Longest wait before sending again; a longer one is returned as an error
+    /// right away.
+    pub max_wait: Duration,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            max_per_minute: None,
+            max_retries: 3,
+            default_wait: Duration::from_secs(10),
+            max_wait: Duration::from_secs(120),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// The default configuration, with the per-minute budget from
+    /// `DISCOURSE_MAX_ADMIN_API_REQS_PER_MINUTE` if it is set.
+    pub fn from_env() -> Self {
+        RateLimitConfig {
+            max_per_minute: std::env::var(MAX_REQS_PER_MINUTE_ENV)
+                .ok()
+                .and_then(|v| v.trim().parse().ok()),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    config: RateLimitConfig,
+    tokens: f64,
+    updated: Instant,
+    blocked_until: Option<Instant>,
+}
+
+impl State {
+    /// Take a token, or return how long to wait before trying again.
+    fn try_reserve(&mut self, now: Instant) -> Option<Duration> {
+        if let Some(until) = self.blocked_until {
+            if until > now {
+                return Some(until - now);
+            }
+            self.blocked_until = None;
+        }
+
+        // Without a budget, only `429` waits apply.
+        let max = self.config.max_per_minute?;
+        let capacity = f64::from(max.max(1));
+        let refill_per_sec = capacity / 60.0;
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * refill_per_sec).min(capacity);
+        self.updated = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64(
+                (1.0 - self.tokens) / refill_per_sec,
+            ))
+        }
+    }
+
+    fn block_for(&mut self, wait: Duration, now: Instant) {
+        let until = now + wait;
+        if self.blocked_until.is_none_or(|current| current < until) {
+            self.blocked_until = Some(until);
+        }
+    }
+}
+
+/// Spaces requests to a per-minute budget and waits out `429` responses.
+///
+/// Cloning a `RateLimiter` is cheap and the clones share their state.
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    state: Arc<Mutex<State>>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimitConfig::default())
+    }
+}
+
+impl RateLimiter {
+    /// Create a new rate limiter.
+    pub fn new(config: RateLimitConfig) -> Self {
+        let state = State {
+            tokens: f64::from(config.max_per_minute.unwrap_or(0)),
+            config,
+            updated: Instant::now(),
+            blocked_until: None,
+        };
+        RateLimiter {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// The current configuration.
+    pub fn config(&self) -> RateLimitConfig {
+        self.lock().config.clone()
+    }
+
+    /// Replace the configuration, for every client sharing this limiter.
+    pub fn set_config(&self, config: RateLimitConfig) {
+        let mut state = self.lock();
+        if config.max_per_minute != state.config.max_per_minute {
+            state.tokens = f64::from(config.max_per_minute.unwrap_or(0));
+            state.updated = Instant::now();
+        }
+        state.config = config;
+    }
+
+    /// Wait until a request fits the budget and any `429` wait is over, and
+    /// count it.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = self.lock().try_reserve(Instant::now());
+            match wait {
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => return,
+            }
+        }
+    }
+
+    /// Record a `429` response and return how long to wait before sending
+    /// again, or `None` when the wait is longer than `max_wait`.
+    pub fn throttled(&self, rate_limited: &RateLimitExceeded) -> Option<Duration> {
+        let mut state = self.lock();
+        let wait = rate_limited.wait.unwrap_or(state.config.default_wait);
+        state.block_for(wait, Instant::now());
+        (wait <= state.config.max_wait).then_some(wait)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        // The state is always left consistent, so a poisoned lock is still usable.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait::async_trait]
+impl reqwest_middleware::Middleware for RateLimiter {
+    async fn handle(
+        &self,
+        req: reqwest::Request,
+        extensions: &mut http::Extensions,
+        next: reqwest_middleware::Next<'_>,
+    ) -> reqwest_middleware::Result<reqwest::Response> {
+        let mut req = req;
+        let mut attempt = 0;
+        loop {
+            self.acquire().await;
+
+            let retry = if attempt < self.lock().config.max_retries {
+                req.try_clone()
+            } else {
+                None
+            };
+            let resp = next.clone().run(req, extensions).await?;
+            if resp.status() != reqwest::StatusCode::TOO_MANY_REQUESTS {
+                return Ok(resp);
+            }
+
+            let headers = resp.headers().clone();
+            let body = resp.text().await.unwrap_or_default();
+            let rate_limited = RateLimitExceeded::from_response(&headers, body);
+            match (self.throttled(&rate_limited), retry) {
+                (Some(wait), Some(retry)) => {
+                    tracing::debug!(?wait, attempt, "discourse rate limit hit, waiting");
+                    attempt += 1;
+                    req = retry;
+                }
+                _ => return Err(reqwest_middleware::Error::middleware(rate_limited)),
+            }
+        }
+    }
+}
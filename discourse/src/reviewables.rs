@@ -0,0 +1,835 @@
+//! The review queue.
+//!
+//! Flagged posts, posts held for approval and users waiting for approval are
+//! "reviewables". Each one lists the actions a moderator can take on it, and
+//! performing an action needs the reviewable's current version so that two
+//! moderators don't act on the same item at once.
+//!
+//! ```rust,no_run
+//! use discourse_api::reviewables::{
+//!     ListReviewablesParams, ReviewAction, ReviewableStatus, ReviewableType,
+//! };
+//!
+//! async fn example_reviewables() -> anyhow::Result<()> {
+//!     let client = discourse_api::Client::new_from_env();
+//!     let queue = client
+//!         .reviewables()
+//!         .list_all(ListReviewablesParams {
+//!             type_: Some(ReviewableType::QueuedPost),
+//!             status: Some(ReviewableStatus::Pending),
+//!             ..Default::default()
+//!         })
+//!         .await?;
+//!     for reviewable in &queue.reviewables {
+//!         let Some(post) = reviewable.queued_post() else {
+//!             continue;
+//!         };
+//!         if post.raw.contains("cheap watches") {
+//!             client
+//!                 .reviewables()
+//!                 .take_action(reviewable.id, ReviewAction::Reject)
+//!                 .await?;
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{methods::parse_response, webhooks::BasicUser, Client};
+
+/// The kind of a reviewable.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Debug,
+    Clone,
+    Copy,
+    schemars :: JsonSchema,
+    parse_display :: FromStr,
+    parse_display :: Display,
+)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "tabled", derive(tabled::Tabled))]
+pub enum ReviewableType {
+    #[serde(rename = "ReviewableFlaggedPost")]
+    #[display("ReviewableFlaggedPost")]
+    FlaggedPost,
+    #[serde(rename = "ReviewableQueuedPost")]
+    #[display("ReviewableQueuedPost")]
+    QueuedPost,
+    #[serde(rename = "ReviewableUser")]
+    #[display("ReviewableUser")]
+    User,
+    #[serde(rename = "ReviewablePost")]
+    #[display("ReviewablePost")]
+    Post,
+}
+
+/// The status of a reviewable, and the status filter of the queue.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Debug,
+    Clone,
+    Copy,
+    schemars :: JsonSchema,
+    parse_display :: FromStr,
+    parse_display :: Display,
+)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "tabled", derive(tabled::Tabled))]
+pub enum ReviewableStatus {
+    #[serde(rename = "pending")]
+    #[display("pending")]
+    Pending,
+    #[serde(rename = "approved")]
+    #[display("approved")]
+    Approved,
+    #[serde(rename = "rejected")]
+    #[display("rejected")]
+    Rejected,
+    #[serde(rename = "ignored")]
+    #[display("ignored")]
+    Ignored,
+    #[serde(rename = "deleted")]
+    #[display("deleted")]
+    Deleted,
+    /// Any status but pending. Only a filter.
+    #[serde(rename = "reviewed")]
+    #[display("reviewed")]
+    Reviewed,
+    /// Any status. Only a filter.
+    #[serde(rename = "all")]
+    #[display("all")]
+    All,
+}
+
+impl ReviewableStatus {
+    /// The status with the number Discourse stores it as.
+    pub fn from_code(code: i64) -> Option<Self> {
+        Some(match code {
+            0 => ReviewableStatus::Pending,
+            1 => ReviewableStatus::Approved,
+            2 => ReviewableStatus::Rejected,
+            3 => ReviewableStatus::Ignored,
+            4 => ReviewableStatus::Deleted,
+            _ => return None,
+        })
+    }
+}
+
+/// The minimum score filter of the queue.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Debug,
+    Clone,
+    Copy,
+    schemars :: JsonSchema,
+    parse_display :: FromStr,
+    parse_display :: Display,
+)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "tabled", derive(tabled::Tabled))]
+pub enum ReviewablePriority {
+    #[serde(rename = "any")]
+    #[display("any")]
+    Any,
+    #[serde(rename = "low")]
+    #[display("low")]
+    Low,
+    #[serde(rename = "medium")]
+    #[display("medium")]
+    Medium,
+    #[serde(rename = "high")]
+    #[display("high")]
+    High,
+}
+
+/// The order of the queue.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Debug,
+    Clone,
+    Copy,
+    schemars :: JsonSchema,
+    parse_display :: FromStr,
+    parse_display :: Display,
+)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "tabled", derive(tabled::Tabled))]
+pub enum ReviewableSortOrder {
+    #[serde(rename = "score")]
+    #[display("score")]
+    Score,
+    #[serde(rename = "score_asc")]
+    #[display("score_asc")]
+    ScoreAsc,
+    #[serde(rename = "created_at")]
+    #[display("created_at")]
+    CreatedAt,
+    #[serde(rename = "created_at_asc")]
+    #[display("created_at_asc")]
+    CreatedAtAsc,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ListReviewablesParams {
+    pub type_: Option<ReviewableType>,
+    /// Defaults to pending.
+    pub status: Option<ReviewableStatus>,
+    pub category_id: Option<i64>,
+    pub topic_id: Option<i64>,
+    pub priority: Option<ReviewablePriority>,
+    /// Only reviewables created by this user.
+    pub username: Option<String>,
+    /// Only reviewables reviewed by this user.
+    pub reviewed_by: Option<String>,
+    pub sort_order: Option<ReviewableSortOrder>,
+    /// How many reviewables to skip.
+    pub offset: Option<i64>,
+}
+
+/// An item of the review queue.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct Reviewable {
+    pub id: i64,
+    /// E.g. `ReviewableFlaggedPost`; see [`Reviewable::kind`].
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// See [`Reviewable::status`].
+    #[serde(rename = "status")]
+    pub status_code: i64,
+    /// Sent back with an action to detect concurrent reviews.
+    pub version: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category_id: Option<i64>,
+    /// The user who flagged or submitted it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by_id: Option<i64>,
+    /// The author of the post, or the user to approve.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_created_by_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_url: Option<String>,
+    /// The post's raw content, for flagged posts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooked: Option<String>,
+    /// Type-specific data, e.g. the `raw` and `title` of a queued post or the
+    /// `username` and `email` of a user. Its shape depends on the type and the
+    /// API spec has no model for it; see [`Reviewable::queued_post`] and
+    /// [`Reviewable::user_payload`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<serde_json::Value>,
+    #[serde(default)]
+    pub bundled_action_ids: Vec<String>,
+    #[serde(default)]
+    pub reviewable_score_ids: Vec<i64>,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Reviewable {
+    /// The kind, if this crate knows it.
+    pub fn kind(&self) -> Option<ReviewableType> {
+        self.type_.parse().ok()
+    }
+
+    pub fn status(&self) -> Option<ReviewableStatus> {
+        ReviewableStatus::from_code(self.status_code)
+    }
+
+    /// A string field of the payload.
+    pub fn payload_str(&self, key: &str) -> Option<&str> {
+        self.payload.as_ref()?.get(key)?.as_str()
+    }
+
+    /// The post held for approval, for queued posts.
+    pub fn queued_post(&self) -> Option<QueuedPostPayload> {
+        if self.kind() != Some(ReviewableType::QueuedPost) {
+            return None;
+        }
+        serde_json::from_value(self.payload.clone()?).ok()
+    }
+
+    /// The user waiting for approval, for reviewable users.
+    pub fn user_payload(&self) -> Option<UserPayload> {
+        if self.kind() != Some(ReviewableType::User) {
+            return None;
+        }
+        serde_json::from_value(self.payload.clone()?).ok()
+    }
+
+    /// The ID of the post under review, for flagged and reviewable posts.
+    pub fn post_id(&self) -> Option<i64> {
+        if self.target_type.as_deref() == Some("Post") {
+            self.target_id
+        } else {
+            None
+        }
+    }
+}
+
+/// The payload of a queued post.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct QueuedPostPayload {
+    pub raw: String,
+    /// The title, when the post would start a new topic.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The category ID, when the post would start a new topic.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archetype: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to_post_number: Option<i64>,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The payload of a user waiting for approval.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct UserPayload {
+    pub username: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub website: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bio: Option<String>,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// An action that can be taken on a reviewable.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ReviewableAction {
+    /// E.g. `agree_and_hide`, `approve_post` or `approve_user`.
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirm_message: Option<String>,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A group of actions, shown as one button or dropdown.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ReviewableBundledAction {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub action_ids: Vec<String>,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A reason a reviewable is in the queue, e.g. one flag.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ReviewableScore {
+    pub id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// The user who flagged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<i64>,
+    /// E.g. `spam` or `inappropriate`, by post action type.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score_type: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The records reviewables refer to by ID, sent along with them.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Debug,
+    Clone,
+    Default,
+    schemars :: JsonSchema,
+)]
+pub struct ReviewableSideloads {
+    #[serde(default)]
+    pub users: Vec<BasicUser>,
+    #[serde(default)]
+    pub topics: Vec<crate::types::BasicTopic>,
+    #[serde(default)]
+    pub bundled_actions: Vec<ReviewableBundledAction>,
+    #[serde(default)]
+    pub actions: Vec<ReviewableAction>,
+    #[serde(default)]
+    pub reviewable_scores: Vec<ReviewableScore>,
+}
+
+impl ReviewableSideloads {
+    pub fn user(&self, id: i64) -> Option<&BasicUser> {
+        self.users.iter().find(|user| user.id == Some(id))
+    }
+
+    /// The user who flagged or submitted a reviewable.
+    pub fn created_by(&self, reviewable: &Reviewable) -> Option<&BasicUser> {
+        self.user(reviewable.created_by_id?)
+    }
+
+    /// The author of a post under review, or the user waiting for approval.
+    pub fn target_created_by(&self, reviewable: &Reviewable) -> Option<&BasicUser> {
+        self.user(reviewable.target_created_by_id?)
+    }
+
+    /// The topic a reviewable is in.
+    pub fn topic(&self, reviewable: &Reviewable) -> Option<&crate::types::BasicTopic> {
+        let topic_id = reviewable.topic_id?;
+        self.topics.iter().find(|topic| topic.id == Some(topic_id))
+    }
+
+    /// The actions that can be taken on a reviewable.
+    pub fn actions(&self, reviewable: &Reviewable) -> Vec<&ReviewableAction> {
+        reviewable
+            .bundled_action_ids
+            .iter()
+            .filter_map(|id| self.bundled_actions.iter().find(|b| &b.id == id))
+            .flat_map(|bundle| &bundle.action_ids)
+            .filter_map(|id| self.actions.iter().find(|a| &a.id == id))
+            .collect()
+    }
+
+    /// The reasons a reviewable is in the queue.
+    pub fn scores(&self, reviewable: &Reviewable) -> Vec<&ReviewableScore> {
+        reviewable
+            .reviewable_score_ids
+            .iter()
+            .filter_map(|id| self.reviewable_scores.iter().find(|s| s.id == *id))
+            .collect()
+    }
+
+    fn extend(&mut self, other: ReviewableSideloads) {
+        fn merge<T, K: PartialEq>(into: &mut Vec<T>, from: Vec<T>, key: impl Fn(&T) -> K) {
+            for item in from {
+                if !into.iter().any(|i| key(i) == key(&item)) {
+                    into.push(item);
+                }
+            }
+        }
+        merge(&mut self.users, other.users, |u| u.id);
+        merge(&mut self.topics, other.topics, |t| t.id);
+        merge(&mut self.bundled_actions, other.bundled_actions, |b| {
+            b.id.clone()
+        });
+        merge(&mut self.actions, other.actions, |a| a.id.clone());
+        merge(&mut self.reviewable_scores, other.reviewable_scores, |s| {
+            s.id
+        });
+    }
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ReviewablesMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_rows_reviewables: Option<i64>,
+    /// The path of the next page, if there is one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub load_more_reviewables: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewable_count: Option<i64>,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ListReviewablesResponse {
+    pub reviewables: Vec<Reviewable>,
+    #[serde(flatten)]
+    pub sideloads: ReviewableSideloads,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ReviewablesMeta>,
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct GetReviewableResponse {
+    pub reviewable: Reviewable,
+    #[serde(flatten)]
+    pub sideloads: ReviewableSideloads,
+}
+
+impl GetReviewableResponse {
+    /// The actions that can be taken on the reviewable.
+    pub fn actions(&self) -> Vec<&ReviewableAction> {
+        self.sideloads.actions(&self.reviewable)
+    }
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ReviewablePerformResult {
+    pub success: bool,
+    /// The status the reviewable moved to, e.g. `approved`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transition_to: Option<String>,
+    /// The reviewable's new version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
+    /// The post created by approving a queued post.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_post_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_post_topic_id: Option<i64>,
+    /// Reviewables removed from the queue along with this one.
+    #[serde(default)]
+    pub remove_reviewable_ids: Vec<i64>,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(serde::Deserialize)]
+struct PerformResponse {
+    reviewable_perform_result: ReviewablePerformResult,
+}
+
+/// Options of an action.
+#[derive(Clone, Debug, Default)]
+pub struct PerformParams {
+    /// Why a queued post or user was rejected.
+    pub reject_reason: Option<String>,
+    /// Email the user the reject reason.
+    pub send_email: Option<bool>,
+}
+
+/// A decision, mapped to the matching action of each reviewable kind by
+/// [`Reviewables::take_action`].
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Debug,
+    Clone,
+    Copy,
+    schemars :: JsonSchema,
+    parse_display :: FromStr,
+    parse_display :: Display,
+)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "tabled", derive(tabled::Tabled))]
+pub enum ReviewAction {
+    /// Agree with the flags and keep the post, or approve the post or user.
+    #[serde(rename = "approve")]
+    #[display("approve")]
+    Approve,
+    /// Disagree with the flags, or reject the post or user.
+    #[serde(rename = "reject")]
+    #[display("reject")]
+    Reject,
+    /// Delete the post, or the user.
+    #[serde(rename = "delete")]
+    #[display("delete")]
+    Delete,
+    /// Dismiss the flags without acting.
+    #[serde(rename = "ignore")]
+    #[display("ignore")]
+    Ignore,
+}
+
+impl ReviewAction {
+    /// The action IDs that carry out the decision, most preferred first.
+    /// Discourse versions name some actions differently.
+    pub fn action_ids(&self) -> &'static [&'static str] {
+        match self {
+            ReviewAction::Approve => &["agree_and_keep", "approve_post", "approve_user", "approve"],
+            ReviewAction::Reject => &[
+                "disagree",
+                "reject_post",
+                "reject_user_delete",
+                "delete_user",
+                "reject",
+            ],
+            ReviewAction::Delete => &[
+                "delete_and_agree",
+                "delete_post",
+                "delete_user",
+                "reject_user_delete",
+                "delete_and_ignore",
+            ],
+            ReviewAction::Ignore => &["ignore_and_do_nothing", "ignore"],
+        }
+    }
+
+    /// The first of [`ReviewAction::action_ids`] among `available`.
+    pub fn resolve<'a>(&self, available: &[&'a ReviewableAction]) -> Option<&'a ReviewableAction> {
+        self.action_ids()
+            .iter()
+            .find_map(|id| available.iter().find(|a| a.id == *id).copied())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Reviewables {
+    pub client: Client,
+}
+
+impl Reviewables {
+    #[doc(hidden)]
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    /// List a page of the review queue.
+    #[tracing::instrument]
+    pub async fn list<'a>(
+        &'a self,
+        params: ListReviewablesParams,
+    ) -> Result<ListReviewablesResponse, crate::types::error::Error> {
+        let ListReviewablesParams {
+            type_,
+            status,
+            category_id,
+            topic_id,
+            priority,
+            username,
+            reviewed_by,
+            sort_order,
+            offset,
+        } = params;
+        let mut req = self.client.client.request(
+            http::Method::GET,
+            format!("{}/{}", self.client.base_url, "review.json"),
+        );
+        req = req.bearer_auth(&self.client.token);
+        let mut query_params = vec![];
+        if let Some(p) = type_ {
+            query_params.push(("type", format!("{}", p)));
+        }
+
+        if let Some(p) = status {
+            query_params.push(("status", format!("{}", p)));
+        }
+
+        if let Some(p) = category_id {
+            query_params.push(("category_id", format!("{}", p)));
+        }
+
+        if let Some(p) = topic_id {
+            query_params.push(("topic_id", format!("{}", p)));
+        }
+
+        if let Some(p) = priority {
+            query_params.push(("priority", format!("{}", p)));
+        }
+
+        if let Some(p) = username {
+            query_params.push(("username", p));
+        }
+
+        if let Some(p) = reviewed_by {
+            query_params.push(("reviewed_by", p));
+        }
+
+        if let Some(p) = sort_order {
+            query_params.push(("sort_order", format!("{}", p)));
+        }
+
+        if let Some(p) = offset {
+            query_params.push(("offset", format!("{}", p)));
+        }
+
+        req = req.query(&query_params);
+        parse_response(req.send().await?).await
+    }
+
+    /// List the whole review queue, page by page, starting at
+    /// `params.offset`.
+    pub async fn list_all(
+        &self,
+        mut params: ListReviewablesParams,
+    ) -> Result<ListReviewablesResponse, crate::types::error::Error> {
+        let mut all = self.list(params.clone()).await?;
+        let mut offset = params.offset.unwrap_or(0) + all.reviewables.len() as i64;
+        while !all.reviewables.is_empty()
+            && all
+                .meta
+                .as_ref()
+                .is_some_and(|meta| meta.load_more_reviewables.is_some())
+        {
+            params.offset = Some(offset);
+            let page = self.list(params.clone()).await?;
+            if page.reviewables.is_empty() {
+                break;
+            }
+            offset += page.reviewables.len() as i64;
+            all.reviewables.extend(page.reviewables);
+            all.sideloads.extend(page.sideloads);
+            all.meta = page.meta;
+        }
+        Ok(all)
+    }
+
+    /// Get a reviewable with its actions, scores and users.
+    #[tracing::instrument]
+    pub async fn get<'a>(
+        &'a self,
+        id: i64,
+    ) -> Result<GetReviewableResponse, crate::types::error::Error> {
+        let mut req = self.client.client.request(
+            http::Method::GET,
+            format!("{}/review/{}.json", self.client.base_url, id),
+        );
+        req = req.bearer_auth(&self.client.token);
+        parse_response(req.send().await?).await
+    }
+
+    /// Perform an action by ID on the given version of a reviewable. A
+    /// reviewable changed since `version` is answered with `409 Conflict`.
+    #[tracing::instrument]
+    pub async fn perform<'a>(
+        &'a self,
+        id: i64,
+        action_id: &'a str,
+        version: i64,
+        params: PerformParams,
+    ) -> Result<ReviewablePerformResult, crate::types::error::Error> {
+        let mut req = self.client.client.request(
+            http::Method::PUT,
+            format!(
+                "{}/review/{}/perform/{}.json",
+                self.client.base_url, id, action_id
+            ),
+        );
+        req = req.bearer_auth(&self.client.token);
+        let mut query_params = vec![("version", version.to_string())];
+        if let Some(p) = params.reject_reason {
+            query_params.push(("reject_reason", p));
+        }
+
+        if let Some(p) = params.send_email {
+            query_params.push(("send_email", format!("{}", p)));
+        }
+
+        req = req.query(&query_params);
+        let resp: PerformResponse = parse_response(req.send().await?).await?;
+        Ok(resp.reviewable_perform_result)
+    }
+
+    /// Approve, reject, delete or ignore a reviewable, whatever its kind.
+    ///
+    /// Fetches the reviewable for its current version and available actions,
+    /// and fails with [`crate::types::error::Error::InvalidRequest`] when none
+    /// of them carries out the decision.
+    pub async fn take_action(
+        &self,
+        id: i64,
+        action: ReviewAction,
+    ) -> Result<ReviewablePerformResult, crate::types::error::Error> {
+        self.take_action_with(id, action, PerformParams::default())
+            .await
+    }
+
+    /// [`Reviewables::take_action`] with a reject reason.
+    pub async fn take_action_with(
+        &self,
+        id: i64,
+        action: ReviewAction,
+        params: PerformParams,
+    ) -> Result<ReviewablePerformResult, crate::types::error::Error> {
+        let detail = self.get(id).await?;
+        let available = detail.actions();
+        let action_id = action
+            .resolve(&available)
+            .ok_or_else(|| {
+                crate::types::error::Error::InvalidRequest(format!(
+                    "{} cannot be done on {} {} (available: {})",
+                    action,
+                    detail.reviewable.type_,
+                    id,
+                    itertools::join(available.iter().map(|a| &a.id), ", ")
+                ))
+            })?
+            .id
+            .clone();
+        self.perform(id, &action_id, detail.reviewable.version, params)
+            .await
+    }
+
+    /// Get the post under review, for flagged and reviewable posts.
+    pub async fn target_post(
+        &self,
+        reviewable: &Reviewable,
+    ) -> Result<Option<crate::types::GetPostResponse>, crate::types::error::Error> {
+        match reviewable.post_id() {
+            Some(post_id) => Ok(Some(
+                crate::posts::Posts::new(self.client.clone())
+                    .get(&post_id.to_string())
+                    .await?,
+            )),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,411 @@
+//! DiscourseConnect (single sign-on) payloads.
+//!
+//! A DiscourseConnect payload is a URL-encoded query string, Base64-encoded
+//! into `sso`, and signed with the shared secret into `sig`, the hex
+//! HMAC-SHA256 of `sso`.
+//!
+//! When Discourse uses an external identity provider, it redirects users to
+//! the provider with a payload holding a nonce and a `return_sso_url`; the
+//! provider answers with the user's attributes and the same nonce. The same
+//! user payload pushed to `admin/users/sync_sso` creates or updates a user
+//! without a login.
+//!
+//! ```rust,no_run
+//! use discourse_api::sso::{DiscourseConnect, SsoUser};
+//!
+//! fn example_sso(sso: &str, sig: &str) -> anyhow::Result<String> {
+//!     let connect = DiscourseConnect::new("connect-secret");
+//!     let request = connect.decode_request(sso, sig)?;
+//!
+//!     let user = SsoUser {
+//!         external_id: "42".to_string(),
+//!         email: "alice@example.com".to_string(),
+//!         username: Some("alice".to_string()),
+//!         groups: Some(vec!["staff".to_string()]),
+//!         moderator: Some(true),
+//!         ..Default::default()
+//!     };
+//!     Ok(connect.return_url(&request, &user))
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Why a DiscourseConnect payload was rejected.
+#[derive(Debug, thiserror::Error)]
+pub enum SsoError {
+    /// `sig` does not match `sso`.
+    #[error("invalid signature")]
+    InvalidSignature,
+    /// `sso` is not Base64.
+    #[error("invalid payload: {0}")]
+    InvalidPayload(#[from] base64::DecodeError),
+    /// A required field is missing from the payload.
+    #[error("missing field `{0}`")]
+    MissingField(&'static str),
+    /// The nonce of a response is not the one that was sent.
+    #[error("nonce mismatch")]
+    NonceMismatch,
+}
+
+#[cfg(feature = "requests")]
+impl From<SsoError> for crate::types::error::Error {
+    fn from(err: SsoError) -> Self {
+        crate::types::error::Error::InvalidRequest(err.to_string())
+    }
+}
+
+/// Encodes, signs and verifies DiscourseConnect payloads with the shared
+/// secret (the `discourse_connect_secret` site setting).
+#[derive(Clone)]
+pub struct DiscourseConnect {
+    secret: String,
+}
+
+impl std::fmt::Debug for DiscourseConnect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiscourseConnect").finish_non_exhaustive()
+    }
+}
+
+impl DiscourseConnect {
+    /// Create a DiscourseConnect signer with the given shared secret.
+    pub fn new<S: ToString>(secret: S) -> Self {
+        DiscourseConnect {
+            secret: secret.to_string(),
+        }
+    }
+
+    /// Create a DiscourseConnect signer from the `DISCOURSE_CONNECT_SECRET`
+    /// environment variable.
+    pub fn new_from_env() -> Self {
+        let secret =
+            std::env::var("DISCOURSE_CONNECT_SECRET").expect("must set DISCOURSE_CONNECT_SECRET");
+        DiscourseConnect::new(secret)
+    }
+
+    fn mac(&self, sso: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(sso.as_bytes());
+        mac
+    }
+
+    /// Compute the `sig` of an `sso` value.
+    pub fn sign(&self, sso: &str) -> String {
+        data_encoding::HEXLOWER.encode(&self.mac(sso).finalize().into_bytes())
+    }
+
+    /// Encode and sign payload fields, returning `(sso, sig)`.
+    pub fn encode<I, K, V>(&self, fields: I) -> (String, String)
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(fields)
+            .finish();
+        let sso = base64::engine::general_purpose::STANDARD.encode(query);
+        let sig = self.sign(&sso);
+        (sso, sig)
+    }
+
+    /// Verify `sig` and decode the fields of `sso`.
+    pub fn decode(&self, sso: &str, sig: &str) -> Result<BTreeMap<String, String>, SsoError> {
+        let sig = data_encoding::HEXLOWER_PERMISSIVE
+            .decode(sig.trim().as_bytes())
+            .map_err(|_| SsoError::InvalidSignature)?;
+        self.mac(sso)
+            .verify_slice(&sig)
+            .map_err(|_| SsoError::InvalidSignature)?;
+
+        // Discourse wraps its Base64 output at 60 characters.
+        let sso: String = sso.chars().filter(|c| !c.is_whitespace()).collect();
+        let query = base64::engine::general_purpose::STANDARD.decode(sso)?;
+        Ok(url::form_urlencoded::parse(&query).into_owned().collect())
+    }
+
+    /// Verify and decode the `sso` and `sig` query parameters Discourse sends
+    /// to an identity provider.
+    pub fn decode_request(&self, sso: &str, sig: &str) -> Result<SsoRequest, SsoError> {
+        let mut fields = self.decode(sso, sig)?;
+        Ok(SsoRequest {
+            nonce: fields
+                .remove("nonce")
+                .ok_or(SsoError::MissingField("nonce"))?,
+            return_sso_url: fields.remove("return_sso_url"),
+            extra: fields,
+        })
+    }
+
+    /// Build the signed `(sso, sig)` answer to a request.
+    pub fn encode_response(&self, request: &SsoRequest, user: &SsoUser) -> (String, String) {
+        let mut fields = vec![("nonce".to_string(), request.nonce.clone())];
+        fields.extend(user.fields());
+        self.encode(fields)
+    }
+
+    /// Build the URL to redirect the user to after signing in: the request's
+    /// `return_sso_url` with the signed answer.
+    pub fn return_url(&self, request: &SsoRequest, user: &SsoUser) -> String {
+        let (sso, sig) = self.encode_response(request, user);
+        let return_sso_url = request.return_sso_url.as_deref().unwrap_or_default();
+        let separator = if return_sso_url.contains('?') {
+            '&'
+        } else {
+            '?'
+        };
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("sso", &sso)
+            .append_pair("sig", &sig)
+            .finish();
+        format!("{}{}{}", return_sso_url, separator, query)
+    }
+
+    /// Build the URL sending a user to a Discourse site acting as identity
+    /// provider (`enable_discourse_connect_provider`). Returns the URL and the
+    /// nonce to check the answer against.
+    pub fn provider_url(&self, discourse_url: &str, return_sso_url: &str) -> (String, String) {
+        let nonce = uuid::Uuid::new_v4().simple().to_string();
+        let (sso, sig) = self.encode([
+            ("nonce", nonce.as_str()),
+            ("return_sso_url", return_sso_url),
+        ]);
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("sso", &sso)
+            .append_pair("sig", &sig)
+            .finish();
+        let url = format!(
+            "{}/session/sso_provider?{}",
+            discourse_url.trim_end_matches('/'),
+            query
+        );
+        (url, nonce)
+    }
+
+    /// Verify and decode the answer of a Discourse identity provider, checking
+    /// it carries the nonce from [`DiscourseConnect::provider_url`].
+    pub fn decode_response(&self, sso: &str, sig: &str, nonce: &str) -> Result<SsoUser, SsoError> {
+        let mut fields = self.decode(sso, sig)?;
+        if fields.remove("nonce").as_deref() != Some(nonce) {
+            return Err(SsoError::NonceMismatch);
+        }
+        fields.remove("return_sso_url");
+        SsoUser::from_fields(fields)
+    }
+}
+
+/// A sign-in request from Discourse to an identity provider.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct SsoRequest {
+    pub nonce: String,
+    /// Where to send the user with the answer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub return_sso_url: Option<String>,
+    /// Other fields, e.g. `prompt`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, String>,
+}
+
+/// The attributes of a user in a DiscourseConnect payload.
+///
+/// Fields left as `None` are not sent, so Discourse keeps its current values.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Debug,
+    Clone,
+    Default,
+    schemars :: JsonSchema,
+)]
+pub struct SsoUser {
+    /// The ID of the user at the identity provider.
+    pub external_id: String,
+    pub email: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar_force_update: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_background_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub card_background_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bio: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub website: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale_force_update: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moderator: Option<bool>,
+    /// The exact set of groups the user is in, when
+    /// `discourse_connect_overrides_groups` is enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub groups: Option<Vec<String>>,
+    /// Groups to add the user to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub add_groups: Vec<String>,
+    /// Groups to remove the user from.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove_groups: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub require_activation: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suppress_welcome_message: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logout: Option<bool>,
+    /// Custom user fields, sent as `custom.<name>`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom_fields: BTreeMap<String, String>,
+}
+
+impl SsoUser {
+    /// The payload fields of the user.
+    pub fn fields(&self) -> Vec<(String, String)> {
+        let mut fields = vec![
+            ("external_id".to_string(), self.external_id.clone()),
+            ("email".to_string(), self.email.clone()),
+        ];
+        let mut push = |key: &str, value: Option<String>| {
+            if let Some(value) = value {
+                fields.push((key.to_string(), value));
+            }
+        };
+        let list = |groups: &[String]| (!groups.is_empty()).then(|| groups.join(","));
+
+        push("username", self.username.clone());
+        push("name", self.name.clone());
+        push("avatar_url", self.avatar_url.clone());
+        push(
+            "avatar_force_update",
+            self.avatar_force_update.map(|v| v.to_string()),
+        );
+        push(
+            "profile_background_url",
+            self.profile_background_url.clone(),
+        );
+        push("card_background_url", self.card_background_url.clone());
+        push("bio", self.bio.clone());
+        push("title", self.title.clone());
+        push("website", self.website.clone());
+        push("location", self.location.clone());
+        push("locale", self.locale.clone());
+        push(
+            "locale_force_update",
+            self.locale_force_update.map(|v| v.to_string()),
+        );
+        push("admin", self.admin.map(|v| v.to_string()));
+        push("moderator", self.moderator.map(|v| v.to_string()));
+        push("groups", self.groups.as_ref().map(|g| g.join(",")));
+        push("add_groups", list(&self.add_groups));
+        push("remove_groups", list(&self.remove_groups));
+        push(
+            "require_activation",
+            self.require_activation.map(|v| v.to_string()),
+        );
+        push(
+            "suppress_welcome_message",
+            self.suppress_welcome_message.map(|v| v.to_string()),
+        );
+        push("logout", self.logout.map(|v| v.to_string()));
+        for (name, value) in &self.custom_fields {
+            push(&format!("custom.{}", name), Some(value.clone()));
+        }
+        fields
+    }
+
+    /// Read a user from decoded payload fields.
+    pub fn from_fields(mut fields: BTreeMap<String, String>) -> Result<Self, SsoError> {
+        let mut take = |key: &str| fields.remove(key).filter(|v| !v.is_empty());
+        let flag = |v: Option<String>| v.map(|v| v == "true");
+        let list = |v: Option<String>| -> Vec<String> {
+            v.map(|v| v.split(',').map(|g| g.trim().to_string()).collect())
+                .unwrap_or_default()
+        };
+
+        let user = SsoUser {
+            external_id: take("external_id").ok_or(SsoError::MissingField("external_id"))?,
+            email: take("email").ok_or(SsoError::MissingField("email"))?,
+            username: take("username"),
+            name: take("name"),
+            avatar_url: take("avatar_url"),
+            avatar_force_update: flag(take("avatar_force_update")),
+            profile_background_url: take("profile_background_url"),
+            card_background_url: take("card_background_url"),
+            bio: take("bio"),
+            title: take("title"),
+            website: take("website"),
+            location: take("location"),
+            locale: take("locale"),
+            locale_force_update: flag(take("locale_force_update")),
+            admin: flag(take("admin")),
+            moderator: flag(take("moderator")),
+            groups: take("groups").map(|v| list(Some(v))),
+            add_groups: list(take("add_groups")),
+            remove_groups: list(take("remove_groups")),
+            require_activation: flag(take("require_activation")),
+            suppress_welcome_message: flag(take("suppress_welcome_message")),
+            logout: flag(take("logout")),
+            custom_fields: BTreeMap::new(),
+        };
+        let custom_fields = fields
+            .into_iter()
+            .filter_map(|(k, v)| Some((k.strip_prefix("custom.")?.to_string(), v)))
+            .collect();
+        Ok(SsoUser {
+            custom_fields,
+            ..user
+        })
+    }
+}
+
+#[cfg(feature = "requests")]
+impl crate::users::Users {
+    /// Create or update a user from DiscourseConnect attributes, without the
+    /// user signing in.
+    ///
+    /// Requires an admin API key and DiscourseConnect to be enabled.
+    #[tracing::instrument]
+    pub async fn sync_sso<'a>(
+        &'a self,
+        connect: &DiscourseConnect,
+        user: &SsoUser,
+    ) -> Result<serde_json::Value, crate::types::error::Error> {
+        let mut req = self.client.client.request(
+            http::Method::POST,
+            format!("{}/admin/users/sync_sso", self.client.base_url),
+        );
+        req = req.bearer_auth(&self.client.token);
+        let mut fields = user.fields();
+        fields.insert(
+            0,
+            (
+                "nonce".to_string(),
+                uuid::Uuid::new_v4().simple().to_string(),
+            ),
+        );
+        let (sso, sig) = connect.encode(fields);
+        req = req.form(&[("sso", sso), ("sig", sig)]);
+        crate::methods::parse_response(req.send().await?).await
+    }
+}
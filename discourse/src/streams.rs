@@ -0,0 +1,283 @@
+//! Streams over paged list endpoints.
+//!
+//! Topic lists page by following `topic_list.more_topics_url`; the user
+//! directory and the admin user list take a numeric `page` and end with an
+//! empty page, and search takes one and ends once `more_full_page_results` is
+//! no longer set. Each `_stream` method here reads pages until the endpoint
+//! reports no more, and the numeric ones can fetch several pages at once.
+//!
+//! ```rust,no_run
+//! use futures_util::TryStreamExt;
+//!
+//! async fn example_streams() -> anyhow::Result<()> {
+//!     let client = discourse_api::Client::new_from_env();
+//!
+//!     let topics = client.topics();
+//!     let mut latest = topics.list_latest_stream(None, Some("created".to_string()), None);
+//!     while let Some(topic) = latest.try_next().await? {
+//!         println!("{:?} {:?}", topic.id, topic.title);
+//!     }
+//!
+//!     let users = client.users();
+//!     let everyone: Vec<_> = users
+//!         .admin_list_stream(discourse_api::users::AdminListParams::default(), 4)
+//!         .try_collect()
+//!         .await?;
+//!     println!("{} users", everyone.len());
+//!     Ok(())
+//! }
+//! ```
+
+use futures::{Future, Stream, StreamExt, TryStreamExt};
+
+use crate::{
+    categories::Categories,
+    methods::parse_response,
+    search::Search,
+    topics::Topics,
+    types::SearchResponse,
+    users::{AdminListParams, Users},
+    Client,
+};
+
+/// Read pages starting at `first_page` until `fetch` reports no more.
+///
+/// `fetch` returns the items of a page and whether another page follows. With
+/// a `concurrency` above one, that many pages are requested at a time and the
+/// items are still yielded in page order.
+pub(crate) fn paged<'a, T, F, Fut>(
+    first_page: i64,
+    concurrency: usize,
+    fetch: F,
+) -> impl Stream<Item = Result<T, crate::types::error::Error>> + Unpin + 'a
+where
+    T: 'a,
+    F: Fn(i64) -> Fut + 'a,
+    Fut: Future<Output = Result<(Vec<T>, bool), crate::types::error::Error>> + 'a,
+{
+    // Pages are only requested while the last one read said more follow, so
+    // reading one page at a time sends no request past the end.
+    let pages = futures::stream::unfold(
+        (
+            fetch,
+            first_page,
+            futures::stream::FuturesOrdered::new(),
+            false,
+        ),
+        move |(fetch, mut next, mut in_flight, done)| async move {
+            if done {
+                return None;
+            }
+            while in_flight.len() < concurrency.max(1) {
+                in_flight.push_back(fetch(next));
+                next += 1;
+            }
+            let page = in_flight.next().await?;
+            let done = match &page {
+                Ok((items, more)) => items.is_empty() || !more,
+                Err(_) => true,
+            };
+            Some((page, (fetch, next, in_flight, done)))
+        },
+    );
+    Box::pin(
+        pages
+            .map_ok(|(items, _)| futures::stream::iter(items.into_iter().map(Ok)))
+            .try_flatten(),
+    )
+}
+
+/// The JSON URL of the page `more_topics_url` points to: `/latest?page=1`
+/// becomes `{base_url}/latest.json?page=1`.
+fn more_topics_url(base_url: &str, more: &str) -> Result<String, url::ParseError> {
+    let mut url = url::Url::parse(&format!("{}/", base_url))?.join(more)?;
+    if !url.path().ends_with(".json") {
+        let path = format!("{}.json", url.path());
+        url.set_path(&path);
+    }
+    Ok(url.to_string())
+}
+
+/// Read topic list pages, starting at `path` and following
+/// `topic_list.more_topics_url` until a page has none.
+pub(crate) fn topic_list_pages<'a, T>(
+    client: &'a Client,
+    path: &str,
+    query_params: Vec<(&'static str, String)>,
+) -> impl Stream<Item = Result<T, crate::types::error::Error>> + Unpin + 'a
+where
+    T: serde::de::DeserializeOwned + 'a,
+{
+    let first = format!("{}/{}", client.base_url, path);
+    Box::pin(futures::stream::try_unfold(
+        Some((first, query_params)),
+        move |next| async move {
+            let Some((url, query_params)) = next else {
+                return Ok(None);
+            };
+            let mut req = client.client.request(http::Method::GET, &url);
+            req = req.bearer_auth(&client.token);
+            req = req.query(&query_params);
+            let value: serde_json::Value = parse_response(req.send().await?).await?;
+            let next = match value
+                .pointer("/topic_list/more_topics_url")
+                .and_then(|v| v.as_str())
+                .filter(|more| !more.is_empty())
+            {
+                Some(more) => {
+                    let more = more_topics_url(&client.base_url, more).map_err(|err| {
+                        crate::types::error::Error::InvalidRequest(format!(
+                            "more_topics_url {}: {}",
+                            more, err
+                        ))
+                    })?;
+                    // Guard against a page that links to itself.
+                    (more != url).then(|| (more, vec![]))
+                }
+                None => None,
+            };
+            Ok(Some((serde_json::from_value(value)?, next)))
+        },
+    ))
+}
+
+impl Topics {
+    /// Stream every topic of the latest topics list.
+    pub fn list_latest_stream<'a>(
+        &'a self,
+        ascending: Option<String>,
+        order: Option<String>,
+        per_page: Option<i64>,
+    ) -> impl Stream<
+        Item = Result<
+            crate::types::ListLatestTopicsResponseTopicListTopics,
+            crate::types::error::Error,
+        >,
+    > + Unpin
+           + 'a {
+        let mut query_params = vec![];
+        if let Some(p) = ascending {
+            query_params.push(("ascending", p));
+        }
+        if let Some(p) = order {
+            query_params.push(("order", p));
+        }
+        if let Some(p) = per_page {
+            query_params.push(("per_page", format!("{}", p)));
+        }
+        topic_list_pages(&self.client, "latest.json", query_params)
+            .map_ok(|resp: crate::types::ListLatestTopicsResponse| {
+                let topics = resp.topic_list.and_then(|l| l.topics).unwrap_or_default();
+                futures::stream::iter(topics.into_iter().map(Ok))
+            })
+            .try_flatten()
+    }
+
+    /// Stream every topic of the top topics list.
+    pub fn list_top_stream<'a>(
+        &'a self,
+        per_page: Option<i64>,
+        period: Option<String>,
+    ) -> impl Stream<
+        Item = Result<
+            crate::types::ListTopTopicsResponseTopicListTopics,
+            crate::types::error::Error,
+        >,
+    > + Unpin
+           + 'a {
+        let mut query_params = vec![];
+        if let Some(p) = per_page {
+            query_params.push(("per_page", format!("{}", p)));
+        }
+        if let Some(p) = period {
+            query_params.push(("period", p));
+        }
+        topic_list_pages(&self.client, "top.json", query_params)
+            .map_ok(|resp: crate::types::ListTopTopicsResponse| {
+                let topics = resp.topic_list.and_then(|l| l.topics).unwrap_or_default();
+                futures::stream::iter(topics.into_iter().map(Ok))
+            })
+            .try_flatten()
+    }
+}
+
+impl Categories {
+    /// Stream every topic of a category.
+    pub fn list_category_topics_stream<'a>(
+        &'a self,
+        id: i64,
+        slug: &'a str,
+    ) -> impl Stream<Item = Result<crate::types::Topics, crate::types::error::Error>> + Unpin + 'a
+    {
+        topic_list_pages(&self.client, &format!("c/{}/{}.json", slug, id), vec![])
+            .map_ok(|resp: crate::types::ListCategoryTopicsResponse| {
+                futures::stream::iter(resp.topic_list.topics.into_iter().map(Ok))
+            })
+            .try_flatten()
+    }
+}
+
+impl Users {
+    /// Stream every entry of the public user directory, fetching up to
+    /// `concurrency` pages at a time.
+    pub fn list_public_stream<'a>(
+        &'a self,
+        asc: Option<crate::types::Asc>,
+        order: crate::types::ListPublicOrder,
+        period: crate::types::ListPublicPeriod,
+        concurrency: usize,
+    ) -> impl Stream<Item = Result<crate::types::DirectoryItems, crate::types::error::Error>> + Unpin + 'a
+    {
+        paged(0, concurrency, move |page| {
+            let (asc, order, period) = (asc.clone(), order.clone(), period.clone());
+            async move {
+                let resp = self.list_public(asc, order, Some(page), period).await?;
+                Ok((resp.directory_items, true))
+            }
+        })
+    }
+
+    /// Stream every user of the admin user list, fetching up to `concurrency`
+    /// pages at a time. Paging starts at `params.page`, or the first page.
+    pub fn admin_list_stream<'a>(
+        &'a self,
+        params: AdminListParams,
+        concurrency: usize,
+    ) -> impl Stream<Item = Result<crate::types::AdminListUsersResponse, crate::types::error::Error>>
+           + Unpin
+           + 'a {
+        paged(params.page.unwrap_or(1), concurrency, move |page| {
+            let params = AdminListParams {
+                page: Some(page),
+                ..params.clone()
+            };
+            async move {
+                let users = self.admin_list(params).await?;
+                Ok((users, true))
+            }
+        })
+    }
+}
+
+impl Search {
+    /// Stream the result pages of a search, fetching up to `concurrency` pages
+    /// at a time. The stream ends after the first page whose
+    /// `grouped_search_result.more_full_page_results` is not `true`.
+    ///
+    /// Pages are yielded whole because search posts are untyped in the
+    /// generated model; a page's `posts` holds that page's matches.
+    pub fn search_stream<'a>(
+        &'a self,
+        q: String,
+        concurrency: usize,
+    ) -> impl Stream<Item = Result<SearchResponse, crate::types::error::Error>> + Unpin + 'a {
+        paged(1, concurrency, move |page| {
+            let q = q.clone();
+            async move {
+                let resp = self.search(Some(page), Some(q)).await?;
+                let more = resp.grouped_search_result.more_full_page_results == Some(true);
+                Ok((vec![resp], more))
+            }
+        })
+    }
+}
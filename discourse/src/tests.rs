@@ -1 +1,2021 @@
+use pretty_assertions::assert_eq;
 
+#[tokio::test]
+async fn test_paged_stream_stops_at_last_page() {
+    use futures::TryStreamExt;
+
+    for concurrency in [1, 3] {
+        let items: Vec<i64> = crate::streams::paged(1, concurrency, |page| async move {
+            // Pages 1 to 4 hold two items each, page 5 is empty.
+            let items = if page <= 4 {
+                vec![page * 10, page * 10 + 1]
+            } else {
+                vec![]
+            };
+            Ok((items, true))
+        })
+        .try_collect()
+        .await
+        .unwrap();
+        assert_eq!(items, vec![10, 11, 20, 21, 30, 31, 40, 41]);
+    }
+
+    let items: Vec<i64> =
+        crate::streams::paged(0, 2, |page| async move { Ok((vec![page], page < 2)) })
+            .try_collect()
+            .await
+            .unwrap();
+    assert_eq!(items, vec![0, 1, 2]);
+}
+
+fn search_page(posts: serde_json::Value, more: Option<bool>) -> serde_json::Value {
+    serde_json::json!({
+        "posts": posts,
+        "users": [],
+        "categories": [],
+        "tags": [],
+        "groups": [],
+        "grouped_search_result": {
+            "term": "rust",
+            "search_log_id": 1,
+            "more_full_page_results": more,
+            "can_create_topic": false,
+            "post_ids": [],
+            "user_ids": [],
+            "category_ids": [],
+            "tag_ids": [],
+            "group_ids": [],
+        },
+    })
+}
+
+#[tokio::test]
+async fn test_topic_list_and_search_streams() {
+    use futures::TryStreamExt;
+
+    let ok = |body: serde_json::Value| (200, String::new(), body.to_string());
+    let stand_in = std::sync::Arc::new(std::sync::Mutex::new(QueuedStandIn {
+        responses: [
+            ok(serde_json::json!({"topic_list": {
+                "topics": [{"id": 1}, {"id": 2}],
+                "more_topics_url": "/latest?order=created&page=1",
+            }})),
+            ok(serde_json::json!({"topic_list": {"topics": [{"id": 3}]}})),
+            ok(search_page(serde_json::json!([{"id": 10}]), Some(true))),
+            ok(search_page(serde_json::json!([{"id": 11}]), None)),
+        ]
+        .into(),
+        ..Default::default()
+    }));
+    let mut client = crate::Client::new("token");
+    client.set_base_url(serve_stand_in(stand_in.clone()).await);
+
+    let topics: Vec<_> = client
+        .topics()
+        .list_latest_stream(None, Some("created".to_string()), None)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(
+        topics.iter().map(|topic| topic.id).collect::<Vec<_>>(),
+        vec![Some(1), Some(2), Some(3)]
+    );
+
+    let pages: Vec<_> = client
+        .search()
+        .search_stream("rust".to_string(), 1)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(
+        pages
+            .into_iter()
+            .flat_map(|page| page.posts)
+            .collect::<Vec<_>>(),
+        vec![serde_json::json!({"id": 10}), serde_json::json!({"id": 11})]
+    );
+
+    assert_eq!(
+        stand_in
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .map(|(_, path)| path.as_str())
+            .collect::<Vec<_>>(),
+        vec![
+            "/latest.json?order=created",
+            "/latest.json?order=created&page=1",
+            "/search.json?page=1&q=rust",
+            "/search.json?page=2&q=rust",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_thread_pages_through_post_stream() {
+    use futures::TryStreamExt;
+
+    let post = |id: i64| {
+        serde_json::json!({
+            "id": id, "name": "", "username": format!("user{}", id), "avatar_template": "",
+            "created_at": "", "cooked": "", "post_number": id - 100, "post_type": 1,
+            "updated_at": "", "reply_count": 0, "quote_count": 0, "incoming_link_count": 0,
+            "reads": 0, "readers_count": 0, "score": 0.0, "yours": false, "topic_id": 7,
+            "topic_slug": "", "display_username": "", "version": 1, "can_edit": false,
+            "can_delete": false, "can_recover": false, "can_wiki": false, "link_counts": [],
+            "read": true, "bookmarked": false, "actions_summary": [], "moderator": false,
+            "admin": false, "staff": false, "user_id": id, "hidden": false, "trust_level": 0,
+            "user_deleted": false, "can_view_edit_history": false, "wiki": false,
+            "reviewable_id": 0, "reviewable_score_count": 0, "reviewable_score_pending_count": 0,
+        })
+    };
+    // Discourse sends the post number a reply answers as an integer.
+    let mut reply = post(102);
+    reply["reply_to_post_number"] = 1.into();
+    let user = serde_json::json!({"id": 1, "username": "", "name": "", "avatar_template": ""});
+    let details = serde_json::json!({
+        "can_edit": false, "notification_level": 1, "can_move_posts": false,
+        "can_delete": false, "can_remove_allowed_users": false, "can_create_post": true,
+        "can_reply_as_new_topic": true, "can_convert_topic": false,
+        "can_review_topic": false, "can_close_topic": false, "can_archive_topic": false,
+        "can_split_merge_topic": false, "can_edit_staff_notes": false,
+        "can_toggle_topic_visibility": false, "can_pin_unpin_topic": false,
+        "can_moderate_category": false, "can_remove_self_id": 0,
+        "created_by": user.clone(), "last_poster": user,
+    });
+    let mut topic = serde_json::json!({
+        "post_stream": {"posts": [post(101), reply], "stream": [101, 102, 103, 104, 105]},
+        "timeline_lookup": [], "suggested_topics": [], "tags": [], "tags_descriptions": {},
+        "id": 7, "title": "", "fancy_title": "", "posts_count": 5, "created_at": "",
+        "views": 0, "reply_count": 0, "like_count": 0, "visible": true, "closed": false,
+        "archived": false, "has_summary": false, "archetype": "regular", "slug": "",
+        "category_id": 1, "user_id": 1, "pinned_globally": false, "slow_mode_seconds": 0,
+        "draft_key": "", "draft_sequence": 0, "pinned": false, "has_deleted": false,
+        "actions_summary": [], "chunk_size": 2, "bookmarked": false, "bookmarks": [],
+        "message_bus_last_id": 0, "participant_count": 1, "show_read_indicator": false,
+    });
+    topic["details"] = details;
+    let ok = |body: serde_json::Value| (200, String::new(), body.to_string());
+    let stand_in = std::sync::Arc::new(std::sync::Mutex::new(QueuedStandIn {
+        // The chunk of 103 and 104 comes back out of order, and 105 is left out
+        // as if it were deleted.
+        responses: [
+            ok(topic.clone()),
+            ok(serde_json::json!({"post_stream": {"posts": [
+                {"id": 104, "reply_to_post_number": 3},
+                {"id": 103, "reply_to_post_number": null},
+            ]}})),
+            ok(serde_json::json!({"post_stream": {"posts": []}})),
+            ok(topic),
+            ok(serde_json::json!({"post_stream": {"posts": [{"id": 103}, {"id": 104}]}})),
+            ok(serde_json::json!({"post_stream": {"posts": [{"id": 105}]}})),
+        ]
+        .into(),
+        ..Default::default()
+    }));
+    let mut client = crate::Client::new("token");
+    client.set_base_url(serve_stand_in(stand_in.clone()).await);
+    let topics = client.topics();
+
+    let posts = topics.get_thread("7").await.unwrap();
+    assert_eq!(
+        posts.iter().map(|post| post.id).collect::<Vec<_>>(),
+        vec![Some(101), Some(102), Some(103), Some(104)]
+    );
+    assert_eq!(posts[0].username.as_deref(), Some("user101"));
+    assert_eq!(
+        posts
+            .iter()
+            .map(|post| post.reply_to_post_number)
+            .collect::<Vec<_>>(),
+        vec![None, Some(1), None, Some(3)]
+    );
+
+    let posts: Vec<_> = topics.get_thread_stream("7").try_collect().await.unwrap();
+    assert_eq!(
+        posts.iter().map(|post| post.id).collect::<Vec<_>>(),
+        vec![Some(101), Some(102), Some(103), Some(104), Some(105)]
+    );
+
+    let requests: Vec<String> = stand_in
+        .lock()
+        .unwrap()
+        .requests
+        .iter()
+        .map(|(_, path)| path.clone())
+        .collect();
+    let chunks = [
+        "/t/7.json",
+        "/t/7/posts.json?post_ids%5B%5D=103&post_ids%5B%5D=104",
+        "/t/7/posts.json?post_ids%5B%5D=105",
+    ];
+    assert_eq!(requests, [chunks, chunks].concat());
+}
+
+#[test]
+fn test_webhook_verify_and_parse() {
+    use crate::webhooks::{WebhookError, WebhookEvent, WebhookVerifier};
+
+    let verifier = WebhookVerifier::new("secret");
+    let body = br#"{"topic":{"id":12,"title":"Hello","category_id":3,"pinned":false,"created_by":{"id":1,"username":"system"}}}"#;
+    let signature = verifier.sign(body);
+    assert!(signature.starts_with("sha256="));
+    verifier.verify(body, &signature).unwrap();
+    assert!(matches!(
+        WebhookVerifier::new("other").verify(body, &signature),
+        Err(WebhookError::InvalidSignature)
+    ));
+
+    let mut headers = http::HeaderMap::new();
+    headers.insert("X-Discourse-Event-Type", "topic".parse().unwrap());
+    headers.insert("X-Discourse-Event", "topic_created".parse().unwrap());
+    headers.insert("X-Discourse-Event-Signature", signature.parse().unwrap());
+    let event = verifier.verify_and_parse(&headers, body).unwrap();
+    assert_eq!(event.event(), "topic_created");
+    let WebhookEvent::Topic { topic, .. } = event else {
+        panic!("expected a topic event, got {:?}", event);
+    };
+    assert_eq!(topic.id, 12);
+    assert_eq!(topic.category_id, Some(3));
+    assert_eq!(
+        topic.created_by.unwrap().username.as_deref(),
+        Some("system")
+    );
+    assert_eq!(topic.other.get("pinned"), Some(&serde_json::json!(false)));
+
+    let event = WebhookEvent::parse(
+        "group",
+        "group_updated",
+        br#"{"group":{"id":41,"automatic":false,"name":"staff-writers","user_count":3,
+            "mentionable_level":0,"messageable_level":0,"visibility_level":0,
+            "primary_group":false,"title":null,"grant_trust_level":null,
+            "has_messages":false,"public_admission":false,"public_exit":false,
+            "allow_membership_requests":true,"full_name":"Staff writers",
+            "default_notification_level":3,"members_visibility_level":0,
+            "can_see_members":true,"can_admin_group":true,"publish_read_state":false}}"#,
+    )
+    .unwrap();
+    let WebhookEvent::Group { group, .. } = event else {
+        panic!("expected a group event, got {:?}", event);
+    };
+    assert_eq!(group.name, "staff-writers");
+    assert_eq!(group.full_name.as_deref(), Some("Staff writers"));
+    assert!(group.allow_membership_requests);
+
+    let event = WebhookEvent::parse(
+        "post",
+        "post_edited",
+        br#"{"post":{"id":7,"topic_id":12,"username":"alice","raw":"hi"}}"#,
+    )
+    .unwrap();
+    let WebhookEvent::Post { post, .. } = event else {
+        panic!("expected a post event, got {:?}", event);
+    };
+    assert_eq!(post.id, Some(7));
+    assert_eq!(post.username.as_deref(), Some("alice"));
+
+    let event = WebhookEvent::parse(
+        "post",
+        "post_created",
+        br#"{"post":{"id":8,"topic_id":12,"post_number":2,"reply_to_post_number":1,
+            "username":"bob","raw":"hello alice"}}"#,
+    )
+    .unwrap();
+    let WebhookEvent::Post { post, .. } = event else {
+        panic!("expected a post event, got {:?}", event);
+    };
+    assert_eq!(post.post_number, Some(2));
+    assert_eq!(post.reply_to_post_number, Some(1));
+
+    let event =
+        WebhookEvent::parse("solved", "accepted_solution", br#"{"solved":{"id":1}}"#).unwrap();
+    assert_eq!(
+        event,
+        WebhookEvent::Other {
+            event_type: "solved".to_string(),
+            event: "accepted_solution".to_string(),
+            payload: serde_json::json!({"solved": {"id": 1}}),
+        }
+    );
+}
+
+#[test]
+fn test_discourse_connect_round_trip() {
+    use crate::sso::{DiscourseConnect, SsoError, SsoUser};
+
+    // The example from the DiscourseConnect documentation.
+    let connect = DiscourseConnect::new("d836444a9e4084d5b224a60c208dce14");
+    let sso = "bm9uY2U9Y2I2ODI1MWVlZmI1MjExZTU4YzAwZmYxMzk1ZjBjMGI=\n";
+    let sig = "2828aa29899722b35a2f191d34ef9b3ce695e0e6eeec47deb46d588d70c7cb56";
+    let request = connect.decode_request(sso, sig).unwrap();
+    assert_eq!(request.nonce, "cb68251eefb5211e58c00ff1395f0c0b");
+    assert_eq!(request.return_sso_url, None);
+    assert!(matches!(
+        connect.decode_request(sso, &sig.replace('2', "3")),
+        Err(SsoError::InvalidSignature)
+    ));
+
+    let user = SsoUser {
+        external_id: "42".to_string(),
+        email: "alice@example.com".to_string(),
+        username: Some("alice".to_string()),
+        admin: Some(false),
+        moderator: Some(true),
+        groups: Some(vec!["staff".to_string(), "support".to_string()]),
+        custom_fields: [("team".to_string(), "A & B".to_string())].into(),
+        ..Default::default()
+    };
+    let (sso, sig) = connect.encode_response(&request, &user);
+    assert_eq!(
+        connect.decode_response(&sso, &sig, &request.nonce).unwrap(),
+        user
+    );
+    assert!(matches!(
+        connect.decode_response(&sso, &sig, "other"),
+        Err(SsoError::NonceMismatch)
+    ));
+}
+
+/// A local HTTP server answering requests in place of Discourse.
+trait StandIn: Send + 'static {
+    fn set_base_url(&mut self, _base_url: &str) {}
+
+    /// See the request line and headers of a request before it is answered.
+    fn observe_head(&mut self, _head: &str) {}
+
+    /// Answer a request with a status, extra header lines and a body.
+    fn handle(&mut self, method: &str, path: &str, body: &[u8]) -> (u16, String, String);
+}
+
+/// A stand-in for Discourse's multipart upload endpoints and the storage
+/// service behind the presigned URLs.
+#[derive(Default)]
+struct UploadStandIn {
+    base_url: String,
+    /// A part answered with a status, this many times.
+    failing_part: Option<(u64, u16, usize)>,
+    /// How many times each part was sent.
+    part_puts: std::collections::BTreeMap<u64, usize>,
+    parts: std::collections::BTreeMap<u64, Vec<u8>>,
+    completed_parts: Option<serde_json::Value>,
+    aborted: bool,
+}
+
+impl StandIn for UploadStandIn {
+    fn set_base_url(&mut self, base_url: &str) {
+        self.base_url = base_url.to_string();
+    }
+
+    fn handle(&mut self, method: &str, path: &str, body: &[u8]) -> (u16, String, String) {
+        let json = |v: serde_json::Value| (200, String::new(), v.to_string());
+        match (method, path) {
+            ("POST", "/uploads/create-multipart.json") => json(serde_json::json!({
+                "key": "temp/big.bin",
+                "external_upload_identifier": "s3-upload-id",
+                "unique_identifier": "unique-id",
+            })),
+            ("POST", "/uploads/batch-presign-multipart-parts.json") => {
+                let body: serde_json::Value = serde_json::from_slice(body).unwrap();
+                let urls: serde_json::Map<String, serde_json::Value> = body["part_numbers"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|n| {
+                        (
+                            n.to_string(),
+                            format!("{}/parts/{}", self.base_url, n).into(),
+                        )
+                    })
+                    .collect();
+                json(serde_json::json!({ "presigned_urls": urls }))
+            }
+            ("PUT", path) => {
+                let number: u64 = path.trim_start_matches("/parts/").parse().unwrap();
+                *self.part_puts.entry(number).or_default() += 1;
+                if let Some((failing, status, times)) = self.failing_part {
+                    if failing == number && times > 0 {
+                        self.failing_part = Some((failing, status, times - 1));
+                        return (status, String::new(), String::new());
+                    }
+                }
+                self.parts.insert(number, body.to_vec());
+                (200, format!("ETag: \"etag-{}\"\r\n", number), String::new())
+            }
+            ("POST", "/uploads/complete-multipart.json") => {
+                let body: serde_json::Value = serde_json::from_slice(body).unwrap();
+                self.completed_parts = Some(body["parts"].clone());
+                json(serde_json::json!({
+                    "id": 1, "url": "/uploads/big.bin", "original_filename": "big.bin",
+                    "filesize": 25, "width": 0, "height": 0, "thumbnail_width": 0,
+                    "thumbnail_height": 0, "extension": "bin", "short_url": "upload://big.bin",
+                    "short_path": "/uploads/short-url/big.bin", "retain_hours": null,
+                    "human_filesize": "25 Bytes", "dominant_color": null,
+                }))
+            }
+            ("POST", "/uploads/abort-multipart.json") => {
+                self.aborted = true;
+                json(serde_json::json!({ "success": "OK" }))
+            }
+            _ => (404, String::new(), String::new()),
+        }
+    }
+}
+
+/// Serve `stand_in` on a local port and return its base URL.
+async fn serve_stand_in<S: StandIn>(stand_in: std::sync::Arc<std::sync::Mutex<S>>) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    stand_in.lock().unwrap().set_base_url(&base_url);
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let stand_in = stand_in.clone();
+            tokio::spawn(async move {
+                let mut buf = vec![];
+                let header_end = loop {
+                    let mut chunk = [0; 4096];
+                    let n = socket.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                    if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break i + 4;
+                    }
+                };
+                let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+                let content_length: usize = head
+                    .lines()
+                    .find_map(|l| {
+                        let (name, value) = l.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse().unwrap())
+                    })
+                    .unwrap_or(0);
+                while buf.len() < header_end + content_length {
+                    let mut chunk = [0; 4096];
+                    let n = socket.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                let mut request_line = head.split_whitespace();
+                let (method, path) = (request_line.next().unwrap(), request_line.next().unwrap());
+                let (status, headers, body) = {
+                    let mut stand_in = stand_in.lock().unwrap();
+                    stand_in.observe_head(&head);
+                    stand_in.handle(method, path, &buf[header_end..])
+                };
+                let response = format!(
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    headers,
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            });
+        }
+    });
+    base_url
+}
+
+#[tokio::test]
+async fn test_upload_large_stream() {
+    use crate::large_uploads::{LargeUploadOptions, MIN_PART_SIZE};
+
+    let options = LargeUploadOptions {
+        concurrency: 2,
+        presign_batch_size: 2,
+        retry_backoff: std::time::Duration::from_millis(1),
+        ..Default::default()
+    };
+    let data: Vec<u8> = (0..2 * MIN_PART_SIZE + MIN_PART_SIZE / 2)
+        .map(|i| i as u8)
+        .collect();
+    let size = data.len() as u64;
+    let chunks = || {
+        Box::pin(futures::stream::iter(
+            data.chunks(1_000_003)
+                .map(|c| Ok(bytes::Bytes::copy_from_slice(c)))
+                .collect::<Vec<_>>(),
+        ))
+    };
+
+    let stand_in = std::sync::Arc::new(std::sync::Mutex::new(UploadStandIn {
+        failing_part: Some((2, 503, 1)),
+        ..Default::default()
+    }));
+    let mut client = crate::Client::new("token");
+    client.set_base_url(serve_stand_in(stand_in.clone()).await);
+    let upload = client
+        .uploads()
+        .upload_large_stream(
+            "big.bin",
+            size,
+            chunks(),
+            crate::types::UploadType::Composer,
+            options.clone(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(upload.short_url, "upload://big.bin");
+    {
+        let stand_in = stand_in.lock().unwrap();
+        assert_eq!(
+            stand_in
+                .parts
+                .values()
+                .flatten()
+                .copied()
+                .collect::<Vec<u8>>(),
+            data
+        );
+        assert_eq!(stand_in.parts[&3].len(), MIN_PART_SIZE / 2);
+        assert_eq!(stand_in.part_puts[&2], 2);
+        assert_eq!(
+            stand_in.completed_parts,
+            Some(serde_json::json!([
+                {"part_number": 1, "etag": "\"etag-1\""},
+                {"part_number": 2, "etag": "\"etag-2\""},
+                {"part_number": 3, "etag": "\"etag-3\""},
+            ]))
+        );
+        assert!(!stand_in.aborted);
+    }
+
+    let stand_in = std::sync::Arc::new(std::sync::Mutex::new(UploadStandIn {
+        failing_part: Some((3, 403, usize::MAX)),
+        ..Default::default()
+    }));
+    client.set_base_url(serve_stand_in(stand_in.clone()).await);
+    let result = client
+        .uploads()
+        .upload_large_stream(
+            "big.bin",
+            size,
+            chunks(),
+            crate::types::UploadType::Composer,
+            options.clone(),
+        )
+        .await;
+    assert_eq!(
+        result.unwrap_err().status(),
+        Some(reqwest::StatusCode::FORBIDDEN)
+    );
+    {
+        let stand_in = stand_in.lock().unwrap();
+        assert!(stand_in.aborted);
+        assert_eq!(stand_in.completed_parts, None);
+        assert_eq!(stand_in.part_puts[&3], 1);
+    }
+
+    // A part that keeps failing is sent `max_retries` more times, not more
+    // because of the client's own retries.
+    let stand_in = std::sync::Arc::new(std::sync::Mutex::new(UploadStandIn {
+        failing_part: Some((1, 503, usize::MAX)),
+        ..Default::default()
+    }));
+    client.set_base_url(serve_stand_in(stand_in.clone()).await);
+    let result = client
+        .uploads()
+        .upload_large_stream(
+            "big.bin",
+            size,
+            chunks(),
+            crate::types::UploadType::Composer,
+            LargeUploadOptions {
+                max_retries: 2,
+                ..options.clone()
+            },
+        )
+        .await;
+    assert_eq!(
+        result.unwrap_err().status(),
+        Some(reqwest::StatusCode::SERVICE_UNAVAILABLE)
+    );
+    {
+        let stand_in = stand_in.lock().unwrap();
+        assert!(stand_in.aborted);
+        assert_eq!(stand_in.part_puts[&1], 3);
+    }
+
+    // S3 would reject the parts, so nothing is sent.
+    let stand_in = std::sync::Arc::new(std::sync::Mutex::new(UploadStandIn::default()));
+    client.set_base_url(serve_stand_in(stand_in.clone()).await);
+    let result = client
+        .uploads()
+        .upload_large_stream(
+            "big.bin",
+            size,
+            chunks(),
+            crate::types::UploadType::Composer,
+            LargeUploadOptions {
+                part_size: MIN_PART_SIZE - 1,
+                ..options.clone()
+            },
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(crate::types::error::Error::InvalidRequest(_))
+    ));
+    assert!(stand_in.lock().unwrap().part_puts.is_empty());
+
+    assert_eq!(options.retry_delay(2), std::time::Duration::from_millis(4));
+    assert_eq!(options.retry_delay(40), options.max_retry_backoff);
+}
+
+#[test]
+fn test_user_api_key_request() {
+    use base64::Engine;
+
+    use crate::user_api_keys::{UserApiKeyError, UserApiKeyRequest, UserApiScope};
+
+    let request = UserApiKeyRequest::new(
+        "Test app",
+        "client-1",
+        "https://app.example.com/callback",
+        vec![UserApiScope::Read, UserApiScope::MessageBus],
+    )
+    .unwrap();
+    let url = url::Url::parse(&request.authorization_url("https://forum.example.com/")).unwrap();
+    assert_eq!(url.path(), "/user-api-key/new");
+    let query: std::collections::BTreeMap<String, String> =
+        url.query_pairs().into_owned().collect();
+    assert_eq!(query["scopes"], "read,message_bus");
+    assert_eq!(query["client_id"], "client-1");
+    assert_eq!(query["nonce"], request.nonce);
+    assert_eq!(query["public_key"], request.public_key_pem().unwrap());
+
+    // What Discourse sends back, encrypted with the public key.
+    let encrypt = |nonce: &str| {
+        let public_key = rsa::RsaPublicKey::from(
+            <rsa::RsaPrivateKey as rsa::pkcs8::DecodePrivateKey>::from_pkcs8_pem(
+                &request.private_key_pem().unwrap(),
+            )
+            .unwrap(),
+        );
+        let json = serde_json::json!({"key": "user-key", "nonce": nonce, "push": false, "api": 4});
+        let encrypted = public_key
+            .encrypt(
+                &mut rand_core::OsRng,
+                rsa::Oaep::new::<sha1::Sha1>(),
+                json.to_string().as_bytes(),
+            )
+            .unwrap();
+        base64::engine::general_purpose::STANDARD.encode(encrypted)
+    };
+
+    let resumed = UserApiKeyRequest::from_private_key_pem(
+        "Test app",
+        "client-1",
+        "https://app.example.com/callback",
+        vec![UserApiScope::Read, UserApiScope::MessageBus],
+        &request.private_key_pem().unwrap(),
+        &request.nonce,
+    )
+    .unwrap();
+    let key = resumed.decrypt_payload(&encrypt(&request.nonce)).unwrap();
+    assert_eq!(key.key, "user-key");
+    assert_eq!(key.api, Some(4));
+    assert!(matches!(
+        resumed.decrypt_payload(&encrypt("other")),
+        Err(UserApiKeyError::NonceMismatch)
+    ));
+}
+
+#[tokio::test]
+async fn test_user_api_key_client_sends_no_bearer_token() {
+    let stand_in = std::sync::Arc::new(std::sync::Mutex::new(QueuedStandIn {
+        responses: [(200, String::new(), r#"{"success":"OK"}"#.to_string())].into(),
+        ..Default::default()
+    }));
+    let mut client = crate::Client::new_from_user_api_key("user-key", "client-1").unwrap();
+    client.set_base_url(serve_stand_in(stand_in.clone()).await);
+    let _ = client.posts().get("3").await;
+    let head = stand_in.lock().unwrap().heads[0].clone();
+    assert!(head.contains("user-api-key: user-key\r\n"));
+    assert!(head.contains("user-api-client-id: client-1\r\n"));
+    assert!(!head.contains("authorization"));
+
+    assert!(matches!(
+        crate::Client::new_from_user_api_key("bad\nkey", "client-1"),
+        Err(crate::user_api_keys::UserApiKeyError::InvalidHeader(
+            "user API key"
+        ))
+    ));
+}
+
+#[test]
+fn test_parse_and_write_ics() {
+    use crate::ics::{parse_ics, to_ics, CalendarEvent, EventTime, IcsProperty};
+
+    let feed = "BEGIN:VCALENDAR\r\n\
+        VERSION:2.0\r\n\
+        PRODID:-//Discourse//EN\r\n\
+        BEGIN:VTIMEZONE\r\n\
+        TZID:Europe/Paris\r\n\
+        END:VTIMEZONE\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:discourse-post-event-12@forum.example.com\r\n\
+        DTSTAMP:20260101T000000Z\r\n\
+        DTSTART;TZID=Europe/Paris:20260310T090000\r\n\
+        DTEND;TZID=Europe/Paris:20260310T100000\r\n\
+        RRULE:FREQ=WEEKLY;BYDAY=TU\r\n\
+        EXDATE;TZID=Europe/Paris:20260317T090000\r\n\
+        EXDATE;TZID=Europe/Paris:20260324T090000\r\n\
+        ATTENDEE;CN=\"Doe, Jane\";PARTSTAT=ACCEPTED:mailto:jane@example.com\r\n\
+        ATTENDEE;CN=Bob:mailto:bob@example.com\r\n\
+        SUMMARY:Weekly sync\\, team A\r\n\
+        DESCRIPTION:Agenda:\\nUpdates\r\n\
+        LOCATION:Room 4\r\n\
+        URL:https://forum.example.com/t/weekly-sync/34/1\r\n\
+        BEGIN:VALARM\r\n\
+        SUMMARY:Reminder\r\n\
+        END:VALARM\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:discourse-post-event-13@forum.example.com\r\n\
+        DTSTART:20260312T150000Z\r\n\
+        SUMMARY:A very long event name that does not fit on a single line of an iCal\r\n \
+        endar feed\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+    let events = parse_ics(feed).unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(
+        events[0].start,
+        EventTime::Local {
+            datetime: chrono::NaiveDate::from_ymd_opt(2026, 3, 10)
+                .unwrap()
+                .and_hms_opt(9, 0, 0)
+                .unwrap(),
+            tzid: Some("Europe/Paris".to_string()),
+        }
+    );
+    assert_eq!(events[0].rrule.as_deref(), Some("FREQ=WEEKLY;BYDAY=TU"));
+    assert_eq!(events[0].summary.as_deref(), Some("Weekly sync, team A"));
+    assert_eq!(events[0].description.as_deref(), Some("Agenda:\nUpdates"));
+    assert_eq!(events[0].other["DTSTAMP"][0].value, "20260101T000000Z");
+    assert_eq!(
+        events[0].other["EXDATE"]
+            .iter()
+            .map(|exdate| exdate.value.as_str())
+            .collect::<Vec<_>>(),
+        vec!["20260317T090000", "20260324T090000"]
+    );
+    assert_eq!(events[0].other["EXDATE"][1].params["TZID"], "Europe/Paris");
+    assert_eq!(
+        events[0].other["ATTENDEE"],
+        vec![
+            IcsProperty {
+                params: [
+                    ("CN".to_string(), "Doe, Jane".to_string()),
+                    ("PARTSTAT".to_string(), "ACCEPTED".to_string()),
+                ]
+                .into(),
+                value: "mailto:jane@example.com".to_string(),
+            },
+            IcsProperty {
+                params: [("CN".to_string(), "Bob".to_string())].into(),
+                value: "mailto:bob@example.com".to_string(),
+            },
+        ]
+    );
+    assert_eq!(
+        events[1].start.to_utc().unwrap().to_rfc3339(),
+        "2026-03-12T15:00:00+00:00"
+    );
+    assert_eq!(
+        events[1].summary.as_deref(),
+        Some("A very long event name that does not fit on a single line of an iCalendar feed")
+    );
+
+    let written = to_ics(&events, "Team calendar");
+    assert!(written.lines().all(|line| line.len() <= 75));
+    let reparsed: Vec<CalendarEvent> = parse_ics(&written)
+        .unwrap()
+        .into_iter()
+        .map(|mut event| {
+            event.other.remove("DTSTAMP");
+            event
+        })
+        .collect();
+    let mut expected = events.clone();
+    expected[0].other.remove("DTSTAMP");
+    assert_eq!(reparsed, expected);
+}
+
+#[test]
+fn test_message_bus_positions_and_events() {
+    use crate::message_bus::{MessageBusEvent, MessageBusMessage};
+
+    let mut bus = crate::Client::new("token").message_bus();
+    bus.subscribe("/notification-alert/42", -1);
+    bus.subscribe("/topic/7", -1);
+    bus.subscribe("/latest", 10);
+
+    let message = |channel: &str, message_id: i64, data: serde_json::Value| MessageBusMessage {
+        global_id: 1000 + message_id,
+        message_id,
+        channel: channel.to_string(),
+        data,
+    };
+    let received = bus.receive(vec![
+        message(
+            "/__status",
+            -1,
+            serde_json::json!({"/notification-alert/42": 5, "/topic/7": 3, "/other": 9}),
+        ),
+        message(
+            "/notification-alert/42",
+            6,
+            serde_json::json!({"notification_type": 1, "username": "alice", "topic_id": 7, "post_number": 2}),
+        ),
+        message("/unsubscribed", 1, serde_json::json!({})),
+        message(
+            "/topic/7",
+            4,
+            serde_json::json!({"id": 70, "post_number": 2, "type": "revised", "version": 2}),
+        ),
+        message(
+            "/latest",
+            11,
+            serde_json::json!({"topic_id": 8, "message_type": "latest", "payload": {"bumped": true}}),
+        ),
+    ]);
+    assert_eq!(
+        bus.positions()
+            .iter()
+            .map(|(c, i)| (c.as_str(), *i))
+            .collect::<Vec<_>>(),
+        vec![
+            ("/latest", 11),
+            ("/notification-alert/42", 6),
+            ("/topic/7", 4)
+        ]
+    );
+
+    let events: Vec<MessageBusEvent> = received.into_iter().map(Into::into).collect();
+    assert_eq!(events.len(), 3);
+    let MessageBusEvent::NotificationAlert { user_id, alert } = &events[0] else {
+        panic!("expected a notification alert, got {:?}", events[0]);
+    };
+    assert_eq!(*user_id, 42);
+    assert!(alert.is_mention());
+    let MessageBusEvent::Post { topic_id, change } = &events[1] else {
+        panic!("expected a post change, got {:?}", events[1]);
+    };
+    assert_eq!(
+        (*topic_id, change.id, change.type_.as_deref()),
+        (7, Some(70), Some("revised"))
+    );
+    assert_eq!(
+        events[2],
+        MessageBusEvent::TopicList {
+            channel: "/latest".to_string(),
+            topic_id: 8,
+            message_type: Some("latest".to_string()),
+            payload: serde_json::json!({"bumped": true}),
+        }
+    );
+}
+
+#[test]
+fn test_pm_archive_rendering() {
+    use crate::pm_archive::{
+        render_html, render_markdown, render_mbox, upload_links, ArchivedAttachment, ArchivedPost,
+        ArchivedThread, PmTopic,
+    };
+
+    let cooked = "<p>See <a href=\"/uploads/short-url/abc.pdf?dl=1&amp;x=2\">report</a></p>\n\
+                  <p><img src=\"https://cdn.example.com/uploads/default/original/1X/f00.png\">\
+                  <a href=\"https://example.com/page\">elsewhere</a></p>";
+    assert_eq!(
+        upload_links(cooked),
+        vec![
+            "/uploads/short-url/abc.pdf?dl=1&x=2".to_string(),
+            "https://cdn.example.com/uploads/default/original/1X/f00.png".to_string(),
+        ]
+    );
+
+    let post = |post_number: i64, username: &str, cooked: &str, raw: &str| ArchivedPost {
+        id: 100 + post_number,
+        post_number,
+        username: username.to_string(),
+        name: None,
+        created_at: Some(format!("2026-01-0{}T10:00:00.000Z", post_number)),
+        updated_at: None,
+        reply_to_post_number: None,
+        cooked: cooked.to_string(),
+        raw: Some(raw.to_string()),
+    };
+    let thread = ArchivedThread {
+        topic: PmTopic {
+            id: 9,
+            title: "Contrat signé".to_string(),
+            slug: Some("contrat-signe".to_string()),
+            posts_count: Some(2),
+            created_at: None,
+            last_posted_at: None,
+        },
+        posts: vec![
+            post(1, "alice", cooked, "See [report](upload://abc.pdf)"),
+            post(2, "bob", "<p>Thanks</p>", "Thanks\nFrom now on, ok"),
+        ],
+        attachments: vec![ArchivedAttachment {
+            url: "/uploads/short-url/abc.pdf?dl=1&x=2".to_string(),
+            path: "attachments/001-abc.pdf".to_string(),
+            size: 3,
+            data: bytes::Bytes::from_static(b"pdf"),
+        }],
+        failed_attachments: vec![],
+    };
+    assert_eq!(thread.directory(), "9-contrat-signe");
+
+    let html = render_html(&thread);
+    assert!(html.contains("<title>Contrat signé</title>"));
+    assert!(html.contains("<a href=\"attachments/001-abc.pdf\">report</a>"));
+    assert!(html.contains("<article id=\"post-2\">"));
+
+    let markdown = render_markdown(&thread);
+    assert!(markdown.starts_with("# Contrat signé\n"));
+    assert!(markdown.contains("## #2 bob (2026-01-02T10:00:00.000Z)\n\nThanks\n"));
+    assert!(markdown.contains("- [/uploads/short-url/abc.pdf?dl=1&x=2](attachments/001-abc.pdf)"));
+
+    let mbox = render_mbox(&thread, "forum.example.com");
+    assert_eq!(mbox.matches("\nFrom bob@").count(), 1);
+    assert!(mbox.starts_with("From alice@forum.example.com Thu Jan  1 10:00:00 2026\n"));
+    assert!(mbox.contains("Subject: =?utf-8?B?"));
+    assert!(mbox.contains("In-Reply-To: <9.1@forum.example.com>"));
+    assert!(mbox.contains("\n>From now on, ok\n"));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_parse_invite_csv_and_report() {
+    use crate::bulk_invites::{
+        parse_invite_csv, results_csv, InviteCsvError, InviteOutcome, InviteResult, InviteRow,
+    };
+
+    let csv = "\u{feff}Email,Groups,topic_id,Custom_Message\r\n\
+               ana@example.com,staff;beta,12,\"Welcome, \"\"Ana\"\"\nSee you\"\r\n\
+               \r\n\
+               bo@example.com,,,\n";
+    let rows = parse_invite_csv(csv).unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            InviteRow {
+                email: "ana@example.com".to_string(),
+                groups: vec!["staff".to_string(), "beta".to_string()],
+                topic_id: Some(12),
+                custom_message: Some("Welcome, \"Ana\"\nSee you".to_string()),
+            },
+            InviteRow {
+                email: "bo@example.com".to_string(),
+                ..Default::default()
+            },
+        ]
+    );
+    assert_eq!(
+        parse_invite_csv("email,topic_id\na@b.co,x\n"),
+        Err(InviteCsvError::InvalidTopicId {
+            line: 2,
+            value: "x".to_string()
+        })
+    );
+    assert_eq!(
+        parse_invite_csv("name\nAna\n"),
+        Err(InviteCsvError::MissingEmailColumn)
+    );
+
+    let report = results_csv(&[
+        InviteResult {
+            row: 1,
+            email: "ana@example.com".to_string(),
+            outcome: InviteOutcome::Invited {
+                invite_id: 3,
+                link: "https://forum.example.com/invites/abc".to_string(),
+            },
+        },
+        InviteResult {
+            row: 2,
+            email: "ANA@example.com".to_string(),
+            outcome: InviteOutcome::Duplicate { first_row: 1 },
+        },
+        InviteResult {
+            row: 3,
+            email: "bo@example.com".to_string(),
+            outcome: InviteOutcome::Failed {
+                error: "Email is invalid, \"bo\"".to_string(),
+            },
+        },
+        InviteResult {
+            row: 4,
+            email: "cy@example.com".to_string(),
+            outcome: InviteOutcome::NotAttempted,
+        },
+    ]);
+    assert_eq!(
+        report,
+        "row,email,outcome,detail\n\
+         1,ana@example.com,invited,https://forum.example.com/invites/abc\n\
+         2,ANA@example.com,duplicate,same as row 1\n\
+         3,bo@example.com,failed,\"Email is invalid, \"\"bo\"\"\"\n\
+         4,cy@example.com,not_attempted,\n"
+    );
+}
+
+#[tokio::test]
+async fn test_create_bulk_reports_rows_after_a_stop() {
+    use crate::bulk_invites::{BulkInviteOptions, InviteOutcome, InviteRow};
+
+    let ok = |body: serde_json::Value| (200, String::new(), body.to_string());
+    let refused = || {
+        (
+            403,
+            String::new(),
+            r#"{"errors":["You are not permitted to view the requested resource."]}"#.to_string(),
+        )
+    };
+    let rows = || {
+        ["ana@example.com", "bo@example.com", " cy@example.com "]
+            .map(|email| InviteRow {
+                email: email.to_string(),
+                ..Default::default()
+            })
+            .to_vec()
+    };
+    let options = BulkInviteOptions {
+        min_interval: std::time::Duration::ZERO,
+        ..Default::default()
+    };
+
+    // The invite of the second row is refused.
+    let stand_in = std::sync::Arc::new(std::sync::Mutex::new(QueuedStandIn {
+        responses: [
+            ok(serde_json::json!([])),
+            ok(serde_json::json!({
+                "id": 3, "invite_key": "abc", "link": "https://forum.example.com/invites/abc",
+                "email": "ana@example.com", "emailed": true, "can_delete_invite": true,
+                "created_at": "2026-01-01T00:00:00Z", "updated_at": "2026-01-01T00:00:00Z",
+                "expires_at": "2026-02-01T00:00:00Z", "expired": false,
+                "topics": [], "groups": [],
+            })),
+            ok(serde_json::json!([])),
+            refused(),
+        ]
+        .into(),
+        ..Default::default()
+    }));
+    let mut client = crate::Client::new("token");
+    client.set_base_url(serve_stand_in(stand_in.clone()).await);
+    let results = client
+        .invites()
+        .create_bulk(rows(), options.clone())
+        .await
+        .unwrap();
+    assert_eq!(
+        results
+            .iter()
+            .map(|result| (result.row, result.email.as_str(), result.outcome.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                1,
+                "ana@example.com",
+                InviteOutcome::Invited {
+                    invite_id: 3,
+                    link: "https://forum.example.com/invites/abc".to_string(),
+                }
+            ),
+            (
+                2,
+                "bo@example.com",
+                InviteOutcome::Failed {
+                    error: "You are not permitted to view the requested resource.".to_string(),
+                }
+            ),
+            (3, "cy@example.com", InviteOutcome::NotAttempted),
+        ]
+    );
+    assert_eq!(stand_in.lock().unwrap().requests.len(), 4);
+
+    // The lookup of the first row is refused.
+    let stand_in = std::sync::Arc::new(std::sync::Mutex::new(QueuedStandIn {
+        responses: [refused()].into(),
+        ..Default::default()
+    }));
+    client.set_base_url(serve_stand_in(stand_in.clone()).await);
+    let results = client.invites().create_bulk(rows(), options).await.unwrap();
+    assert_eq!(
+        results
+            .iter()
+            .map(|result| result.outcome.clone())
+            .collect::<Vec<_>>(),
+        vec![
+            InviteOutcome::Failed {
+                error: "You are not permitted to view the requested resource.".to_string(),
+            },
+            InviteOutcome::NotAttempted,
+            InviteOutcome::NotAttempted,
+        ]
+    );
+    assert_eq!(stand_in.lock().unwrap().requests.len(), 1);
+}
+
+#[test]
+fn test_reviewable_actions() {
+    use crate::reviewables::{
+        ListReviewablesResponse, ReviewAction, ReviewableStatus, ReviewableType,
+    };
+
+    let body = r#"{
+        "reviewables": [{
+            "id": 7,
+            "type": "ReviewableFlaggedPost",
+            "status": 0,
+            "version": 3,
+            "target_type": "Post",
+            "target_id": 120,
+            "topic_id": 40,
+            "created_by_id": 1,
+            "target_created_by_id": 2,
+            "score": 9.5,
+            "bundled_action_ids": ["7-agree", "7-ignore"],
+            "reviewable_score_ids": [11],
+            "can_edit": false
+        }],
+        "users": [
+            {"id": 1, "username": "mod", "avatar_template": "/a/{size}.png"},
+            {"id": 2, "username": "spammer"}
+        ],
+        "bundled_actions": [
+            {"id": "7-agree", "label": "Agree", "action_ids": ["agree_and_hide", "agree_and_keep"]},
+            {"id": "7-ignore", "label": "Ignore", "action_ids": ["ignore_and_do_nothing"]}
+        ],
+        "actions": [
+            {"id": "agree_and_hide", "label": "Hide Post"},
+            {"id": "agree_and_keep", "label": "Keep Post"},
+            {"id": "ignore_and_do_nothing", "label": "Do Nothing"}
+        ],
+        "reviewable_scores": [{"id": 11, "score": 9.5, "user_id": 1, "reason": "spam"}],
+        "meta": {"total_rows_reviewables": 1, "reviewable_count": 1}
+    }"#;
+    let list: ListReviewablesResponse = serde_json::from_str(body).unwrap();
+    let reviewable = &list.reviewables[0];
+    assert_eq!(reviewable.kind(), Some(ReviewableType::FlaggedPost));
+    assert_eq!(reviewable.status(), Some(ReviewableStatus::Pending));
+    assert_eq!(reviewable.post_id(), Some(120));
+    assert_eq!(
+        list.sideloads
+            .target_created_by(reviewable)
+            .and_then(|u| u.username.as_deref()),
+        Some("spammer")
+    );
+    assert_eq!(
+        list.sideloads.scores(reviewable)[0].reason.as_deref(),
+        Some("spam")
+    );
+
+    let actions = list.sideloads.actions(reviewable);
+    assert_eq!(
+        actions.iter().map(|a| a.id.as_str()).collect::<Vec<_>>(),
+        vec!["agree_and_hide", "agree_and_keep", "ignore_and_do_nothing"]
+    );
+    assert_eq!(
+        ReviewAction::Approve
+            .resolve(&actions)
+            .map(|a| a.id.as_str()),
+        Some("agree_and_keep")
+    );
+    assert_eq!(
+        ReviewAction::Ignore
+            .resolve(&actions)
+            .map(|a| a.id.as_str()),
+        Some("ignore_and_do_nothing")
+    );
+    assert_eq!(ReviewAction::Delete.resolve(&actions), None);
+}
+
+#[tokio::test]
+async fn test_reviewables_requests() {
+    use crate::reviewables::{
+        ListReviewablesParams, ReviewAction, ReviewableStatus, ReviewableType,
+    };
+
+    let ok = |body: serde_json::Value| (200, String::new(), body.to_string());
+    let queued = |id: i64, raw: &str| {
+        serde_json::json!({
+            "id": id, "type": "ReviewableQueuedPost", "status": 0, "version": 2,
+            "topic_id": 40, "created_by_id": 5,
+            "payload": {"raw": raw, "title": "Buy now", "category": 3, "tags": ["spam"]},
+            "bundled_action_ids": [format!("{}-approve", id), format!("{}-reject", id)],
+        })
+    };
+    let sideloads = |id: i64| {
+        serde_json::json!({
+            "users": [{"id": 5, "username": "newbie", "name": null}],
+            "topics": [{"id": 40, "title": "Watches", "slug": "watches"}],
+            "bundled_actions": [
+                {"id": format!("{}-approve", id), "action_ids": ["approve_post"]},
+                {"id": format!("{}-reject", id), "action_ids": ["reject_post"]},
+            ],
+            "actions": [{"id": "approve_post"}, {"id": "reject_post"}],
+        })
+    };
+    let with_sideloads = |mut body: serde_json::Value, id: i64| {
+        body.as_object_mut()
+            .unwrap()
+            .extend(sideloads(id).as_object().unwrap().clone());
+        body
+    };
+    let stand_in = std::sync::Arc::new(std::sync::Mutex::new(QueuedStandIn {
+        responses: [
+            ok(with_sideloads(
+                serde_json::json!({
+                    "reviewables": [queued(8, "cheap watches")],
+                    "meta": {"total_rows_reviewables": 2, "load_more_reviewables": "/review.json?offset=1"},
+                }),
+                8,
+            )),
+            ok(with_sideloads(
+                serde_json::json!({"reviewables": [queued(9, "hello")], "meta": {}}),
+                9,
+            )),
+            ok(with_sideloads(
+                serde_json::json!({"reviewable": queued(8, "cheap watches")}),
+                8,
+            )),
+            ok(serde_json::json!({"reviewable_perform_result": {
+                "success": true, "transition_to": "rejected", "version": 3,
+                "remove_reviewable_ids": [8],
+            }})),
+            ok(serde_json::json!({"reviewable_perform_result": {
+                "success": true, "transition_to": "approved", "created_post_id": 120,
+            }})),
+        ]
+        .into(),
+        ..Default::default()
+    }));
+    let mut client = crate::Client::new("token");
+    client.set_base_url(serve_stand_in(stand_in.clone()).await);
+    let reviewables = client.reviewables();
+
+    let queue = reviewables
+        .list_all(ListReviewablesParams {
+            type_: Some(ReviewableType::QueuedPost),
+            status: Some(ReviewableStatus::Pending),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        queue.reviewables.iter().map(|r| r.id).collect::<Vec<_>>(),
+        vec![8, 9]
+    );
+    let first = &queue.reviewables[0];
+    let post = first.queued_post().unwrap();
+    assert_eq!(post.raw, "cheap watches");
+    assert_eq!(post.category, Some(3));
+    assert_eq!(first.user_payload(), None);
+    assert_eq!(
+        queue
+            .sideloads
+            .topic(first)
+            .and_then(|t| t.title.as_deref()),
+        Some("Watches")
+    );
+    assert_eq!(
+        queue
+            .sideloads
+            .created_by(first)
+            .and_then(|u| u.username.as_deref()),
+        Some("newbie")
+    );
+    // Records sent with both pages are kept once.
+    assert_eq!(queue.sideloads.users.len(), 1);
+    assert_eq!(queue.sideloads.bundled_actions.len(), 4);
+
+    let result = reviewables
+        .take_action(8, ReviewAction::Reject)
+        .await
+        .unwrap();
+    assert_eq!(result.transition_to.as_deref(), Some("rejected"));
+    assert_eq!(result.remove_reviewable_ids, vec![8]);
+
+    let result = reviewables
+        .perform(9, "approve_post", 2, Default::default())
+        .await
+        .unwrap();
+    assert_eq!(result.created_post_id, Some(120));
+
+    assert_eq!(
+        stand_in
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .map(|(method, path)| format!("{} {}", method, path))
+            .collect::<Vec<_>>(),
+        vec![
+            "GET /review.json?type=ReviewableQueuedPost&status=pending",
+            "GET /review.json?type=ReviewableQueuedPost&status=pending&offset=1",
+            "GET /review/8.json",
+            "PUT /review/8/perform/reject_post.json?version=2",
+            "PUT /review/9/perform/approve_post.json?version=2",
+        ]
+    );
+}
+
+#[test]
+fn test_chat_events_and_cursors() {
+    use crate::chat::{ChatDirection, ChatEvent, ChatMessagesPage, ReactAction};
+
+    let sent = serde_json::json!({
+        "type": "sent",
+        "chat_message": {
+            "id": 31,
+            "message": "hello :wave:",
+            "cooked": "<p>hello</p>",
+            "chat_channel_id": 4,
+            "user": {"id": 2, "username": "ana"},
+            "reactions": [{"emoji": "wave", "count": 1, "users": [{"id": 3, "username": "bo"}]}]
+        }
+    });
+    match ChatEvent::parse(4, &sent) {
+        ChatEvent::Sent {
+            channel_id,
+            message,
+        } => {
+            assert_eq!(channel_id, 4);
+            assert_eq!(message.channel_id, Some(4));
+            assert_eq!(message.user.unwrap().username.as_deref(), Some("ana"));
+            assert_eq!(
+                message.reactions[0].users[0].username.as_deref(),
+                Some("bo")
+            );
+        }
+        event => panic!("unexpected {:?}", event),
+    }
+    assert_eq!(
+        ChatEvent::parse(
+            4,
+            &serde_json::json!({"type": "reaction", "action": "remove", "emoji": "+1",
+                                "chat_message_id": 31, "user": {"id": 3, "username": "bo"}})
+        ),
+        ChatEvent::Reaction {
+            channel_id: 4,
+            message_id: 31,
+            emoji: "+1".to_string(),
+            action: ReactAction::Remove,
+            user: Some(crate::webhooks::BasicUser {
+                id: Some(3),
+                username: Some("bo".to_string()),
+                name: None,
+                avatar_template: None,
+            }),
+        }
+    );
+    assert_eq!(
+        ChatEvent::parse(4, &serde_json::json!({"type": "delete", "deleted_id": 31})),
+        ChatEvent::Deleted {
+            channel_id: 4,
+            message_id: 31
+        }
+    );
+    assert!(matches!(
+        ChatEvent::parse(4, &serde_json::json!({"type": "typing"})),
+        ChatEvent::Other { type_, .. } if type_ == "typing"
+    ));
+
+    let page: ChatMessagesPage = serde_json::from_value(serde_json::json!({
+        "messages": [{"id": 10, "message": "a"}, {"id": 12, "message": "b"}],
+        "meta": {"can_load_more_past": true, "can_load_more_future": false}
+    }))
+    .unwrap();
+    let past = page.past(Some(50)).unwrap();
+    assert_eq!(past.target_message_id, Some(10));
+    assert_eq!(past.direction, Some(ChatDirection::Past));
+    assert!(page.future(Some(50)).is_none());
+}
+
+#[tokio::test]
+async fn test_chat_requests_act_as_user() {
+    use futures_util::TryStreamExt;
+
+    use crate::chat::{ChatEvent, ReactAction, SendChatMessage};
+
+    let ok = |body: &str| (200, String::new(), body.to_string());
+    let stand_in = std::sync::Arc::new(std::sync::Mutex::new(QueuedStandIn {
+        responses: [
+            ok(r#"{"message_id":31}"#),
+            ok("{}"),
+            ok("{}"),
+            ok(r#"[{"global_id":70,"channel":"/chat/4","message_id":7,
+                    "data":{"type":"delete","deleted_id":31}}]"#),
+        ]
+        .into(),
+        ..Default::default()
+    }));
+    let mut client = crate::Client::new("token");
+    client.set_base_url(serve_stand_in(stand_in.clone()).await);
+    let chat = client.chat().as_user("bridge-bot");
+
+    let message = SendChatMessage {
+        in_reply_to_id: Some(30),
+        ..SendChatMessage::new("hello")
+    };
+    assert_eq!(chat.send(4, &message).await.unwrap(), 31);
+    chat.edit(4, 31, "hello again").await.unwrap();
+    chat.react(4, 31, "+1", ReactAction::Add).await.unwrap();
+    let event = chat.event_stream(&[4]).try_next().await.unwrap();
+    assert_eq!(
+        event,
+        Some(ChatEvent::Deleted {
+            channel_id: 4,
+            message_id: 31
+        })
+    );
+
+    let stand_in = stand_in.lock().unwrap();
+    assert_eq!(
+        stand_in
+            .requests
+            .iter()
+            .map(|(method, path)| format!("{} {}", method, path))
+            .collect::<Vec<_>>()[..3],
+        [
+            "POST /chat/4.json",
+            "PUT /chat/api/channels/4/messages/31.json",
+            "PUT /chat/4/react/31.json",
+        ]
+    );
+    assert!(stand_in.requests[3].1.starts_with("/message-bus/"));
+    let bodies: Vec<serde_json::Value> = stand_in.bodies[..3]
+        .iter()
+        .map(|body| serde_json::from_str(body).unwrap())
+        .collect();
+    assert_eq!(
+        bodies,
+        [
+            serde_json::json!({"message": "hello", "in_reply_to_id": 30}),
+            serde_json::json!({"message": "hello again"}),
+            serde_json::json!({"emoji": "+1", "react_action": "add"}),
+        ]
+    );
+    assert!(stand_in.bodies[3].contains("%2Fchat%2F4=-1"));
+    // Every request, MessageBus polls included, acts as the same user.
+    for head in &stand_in.heads {
+        assert!(head.contains("api-username: bridge-bot\r\n"), "{}", head);
+    }
+}
+
+/// Answers every request with the next queued response.
+#[derive(Default)]
+struct QueuedStandIn {
+    responses: std::collections::VecDeque<(u16, String, String)>,
+    requests: Vec<(String, String)>,
+    /// The body of each request, in the order of `requests`.
+    bodies: Vec<String>,
+    /// The request line and headers of each request, lowercased.
+    heads: Vec<String>,
+}
+
+impl StandIn for QueuedStandIn {
+    fn observe_head(&mut self, head: &str) {
+        self.heads.push(head.to_lowercase());
+    }
+
+    fn handle(&mut self, method: &str, path: &str, body: &[u8]) -> (u16, String, String) {
+        self.requests.push((method.to_string(), path.to_string()));
+        self.bodies.push(String::from_utf8_lossy(body).into_owned());
+        self.responses
+            .pop_front()
+            .unwrap_or((404, String::new(), String::new()))
+    }
+}
+
+#[tokio::test]
+async fn test_rate_limited_requests_wait_and_surface_typed_error() {
+    use crate::{
+        errors::ErrorExt,
+        rate_limit::{RateLimitConfig, RateLimiter},
+    };
+
+    let too_many = || {
+        (
+            429,
+            "Retry-After: 30\r\n".to_string(),
+            r#"{"errors":["Slow down"],"error_type":"rate_limit","extras":{"wait_seconds":0.2}}"#
+                .to_string(),
+        )
+    };
+    let stand_in = std::sync::Arc::new(std::sync::Mutex::new(QueuedStandIn {
+        responses: [
+            too_many(),
+            (200, String::new(), r#"{"message_id":5}"#.to_string()),
+            too_many(),
+            too_many(),
+        ]
+        .into(),
+        ..Default::default()
+    }));
+    let mut client =
+        crate::Client::new("token").with_rate_limiter(RateLimiter::new(RateLimitConfig {
+            max_retries: 1,
+            ..Default::default()
+        }));
+    client.set_base_url(serve_stand_in(stand_in.clone()).await);
+
+    // The body's `wait_seconds` is used over `Retry-After`.
+    let start = std::time::Instant::now();
+    let message_id = client
+        .chat()
+        .send(3, &crate::chat::SendChatMessage::new("hi"))
+        .await
+        .unwrap();
+    assert_eq!(message_id, 5);
+    let elapsed = start.elapsed();
+    assert!(elapsed >= std::time::Duration::from_millis(200));
+    assert!(elapsed < std::time::Duration::from_secs(5));
+    assert_eq!(stand_in.lock().unwrap().requests.len(), 2);
+
+    // Generated methods get the typed error too, once retries run out.
+    let err = client
+        .posts()
+        .delete(
+            9,
+            &crate::types::DeletePostRequestBody {
+                force_destroy: None,
+            },
+        )
+        .await
+        .unwrap_err();
+    assert!(err.rate_limited().unwrap().body.contains("Slow down"));
+    assert_eq!(
+        err.retry_after(),
+        Some(std::time::Duration::from_millis(200))
+    );
+    assert_eq!(stand_in.lock().unwrap().requests.len(), 4);
+}
+
+#[tokio::test]
+async fn test_rate_limit_budget_spaces_requests() {
+    let limiter = crate::rate_limit::RateLimiter::new(crate::rate_limit::RateLimitConfig {
+        max_per_minute: Some(1200),
+        ..Default::default()
+    });
+
+    let start = std::time::Instant::now();
+    for _ in 0..1201 {
+        limiter.acquire().await;
+    }
+    assert!(start.elapsed() >= std::time::Duration::from_millis(40));
+}
+
+/// A group's member endpoints, paging members two at a time.
+#[derive(Default)]
+struct GroupStandIn {
+    members: std::collections::BTreeSet<String>,
+    /// Owners, who are members too.
+    owners: std::collections::BTreeSet<String>,
+    batches: Vec<(String, String)>,
+}
+
+impl StandIn for GroupStandIn {
+    fn handle(&mut self, method: &str, path: &str, body: &[u8]) -> (u16, String, String) {
+        let (path, query) = path.split_once('?').unwrap_or((path, ""));
+        let query: std::collections::HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect();
+        let usernames = || -> Vec<String> {
+            let body: serde_json::Value = serde_json::from_slice(body).unwrap();
+            body["usernames"]
+                .as_str()
+                .unwrap()
+                .split(',')
+                .map(|u| u.to_string())
+                .collect()
+        };
+        let json = |v: serde_json::Value| (200, String::new(), v.to_string());
+        match (method, path) {
+            ("GET", "/groups/team.json") => json(serde_json::json!({"group": {"id": 8}})),
+            ("GET", "/groups/team/members.json") => {
+                let offset: usize = query["offset"].parse().unwrap();
+                let limit: usize = query["limit"].parse().unwrap();
+                let members: Vec<_> = self
+                    .members
+                    .iter()
+                    .enumerate()
+                    .skip(offset)
+                    .take(limit)
+                    .map(|(id, u)| serde_json::json!({"id": id, "username": u, "last_seen_at": null}))
+                    .collect();
+                let owners: Vec<_> = self
+                    .members
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, u)| self.owners.contains(*u))
+                    .map(|(id, u)| serde_json::json!({"id": id, "username": u}))
+                    .collect();
+                json(serde_json::json!({
+                    "members": members,
+                    "owners": owners,
+                    "meta": {"total": self.members.len(), "limit": limit, "offset": offset},
+                }))
+            }
+            ("PUT", "/groups/8/members.json") => {
+                let usernames = usernames();
+                self.batches.push(("add".to_string(), usernames.join(",")));
+                self.members.extend(usernames.iter().cloned());
+                json(serde_json::json!({"success": "OK", "usernames": usernames, "emails": []}))
+            }
+            ("DELETE", "/groups/8/members.json") => {
+                let usernames = usernames();
+                self.batches
+                    .push(("remove".to_string(), usernames.join(",")));
+                for username in &usernames {
+                    self.members.remove(username);
+                }
+                json(
+                    serde_json::json!({"success": "OK", "usernames": usernames, "skipped_usernames": []}),
+                )
+            }
+            _ => (404, String::new(), String::new()),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_group_sync() {
+    use crate::group_sync::{GroupSyncOptions, GroupSyncPlan, MemberRef};
+
+    let plan = GroupSyncPlan::new(
+        "team",
+        ["Ana", "bo", "cy"].map(String::from),
+        ["ana", "dee"].map(String::from),
+    );
+    assert_eq!(plan.add, vec!["dee"]);
+    assert_eq!(plan.remove, vec!["bo", "cy"]);
+    assert_eq!(plan.unchanged, 1);
+    assert_eq!(
+        MemberRef::from(" ana@example.com "),
+        MemberRef::Email("ana@example.com".to_string())
+    );
+
+    let stand_in = std::sync::Arc::new(std::sync::Mutex::new(GroupStandIn {
+        members: ["ana", "bo", "cy", "eve", "fay", "owen"]
+            .map(String::from)
+            .into(),
+        owners: ["owen".to_string()].into(),
+        ..Default::default()
+    }));
+    let mut client = crate::Client::new("token");
+    client.set_base_url(serve_stand_in(stand_in.clone()).await);
+    let groups = client.groups();
+    let desired = || ["ana", "gus", "hal", "ida"].map(MemberRef::from);
+    let options = GroupSyncOptions {
+        max_removals: 3,
+        batch_size: 2,
+        page_size: 2,
+        ..Default::default()
+    };
+
+    let report = groups
+        .sync_members(
+            "team",
+            desired(),
+            GroupSyncOptions {
+                dry_run: true,
+                ..options.clone()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(report.plan.add, vec!["gus", "hal", "ida"]);
+    assert_eq!(report.plan.remove, vec!["bo", "cy", "eve", "fay"]);
+    assert_eq!(report.plan.owners_kept, vec!["owen"]);
+    assert!(report.removals_held_back);
+    assert!(report.added.is_empty());
+    assert!(stand_in.lock().unwrap().batches.is_empty());
+
+    let report = groups
+        .sync_members("team", desired(), options.clone())
+        .await
+        .unwrap();
+    assert_eq!(report.added, vec!["gus", "hal", "ida"]);
+    assert!(report.removed.is_empty());
+    assert_eq!(
+        stand_in.lock().unwrap().batches,
+        vec![
+            ("add".to_string(), "gus,hal".to_string()),
+            ("add".to_string(), "ida".to_string()),
+        ]
+    );
+
+    let report = groups
+        .sync_members(
+            "team",
+            desired(),
+            GroupSyncOptions {
+                max_removals: 4,
+                ..options.clone()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(report.removed, vec!["bo", "cy", "eve", "fay"]);
+    assert_eq!(
+        stand_in.lock().unwrap().members,
+        ["ana", "gus", "hal", "ida", "owen"]
+            .map(String::from)
+            .into()
+    );
+
+    // Owners are only removed when asked to.
+    let report = groups
+        .sync_members(
+            "team",
+            desired(),
+            GroupSyncOptions {
+                remove_owners: true,
+                ..options
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(report.removed, vec!["owen"]);
+    assert!(report.plan.owners_kept.is_empty());
+    assert_eq!(
+        stand_in.lock().unwrap().members,
+        ["ana", "gus", "hal", "ida"].map(String::from).into()
+    );
+}
+
+#[tokio::test]
+async fn test_category_tree_and_permissions() {
+    use crate::category_tree::{CategoryNode, CategoryPermissions, CategoryTree, PermissionType};
+
+    let body = r#"[
+        {"id": 1, "name": "Engineering", "slug": "engineering", "position": 2},
+        {"id": 2, "name": "Backend", "slug": "backend", "parent_category_id": 1, "position": 1,
+         "read_restricted": true,
+         "group_permissions": [
+            {"permission_type": 1, "group_name": "platform-team", "group_id": 41},
+            {"permission_type": 3, "group_name": "everyone", "group_id": 0},
+            {"permission_type": 7, "group_name": "bots", "group_id": 42}
+         ]},
+        {"id": 3, "name": "Frontend", "slug": "frontend", "parent_category_id": 1, "position": 0},
+        {"id": 4, "name": "General", "slug": "general", "position": 1, "color": "0088CC"},
+        {"id": 5, "name": "Orphan", "slug": "orphan", "parent_category_id": 99}
+    ]"#;
+    let nodes: Vec<CategoryNode> = serde_json::from_str(body).unwrap();
+    assert_eq!(nodes[3].other["color"], "0088CC");
+    let tree = CategoryTree::new(nodes);
+
+    assert_eq!(
+        tree.walk()
+            .iter()
+            .map(|(depth, category)| (*depth, category.slug.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            (0, "general"),
+            (0, "engineering"),
+            (1, "frontend"),
+            (1, "backend"),
+            (0, "orphan"),
+        ]
+    );
+    assert_eq!(tree.by_slug_path("engineering/Backend").unwrap().id, 2);
+    assert_eq!(tree.by_slug_path("/engineering/").unwrap().id, 1);
+    assert!(tree.by_slug_path("backend").is_none());
+    assert!(tree.by_slug_path("engineering/missing").is_none());
+    assert_eq!(tree.slug_path(2).as_deref(), Some("engineering/backend"));
+    assert_eq!(tree.parent(2).unwrap().id, 1);
+    assert_eq!(tree.ancestors(2).len(), 1);
+    assert_eq!(tree.children(1).len(), 2);
+
+    assert_eq!(
+        tree.permission(2, "platform-team"),
+        Some(PermissionType::Full)
+    );
+    assert!(tree.can_create_topics(2, "Platform-Team"));
+    assert_eq!(
+        tree.permission(2, "marketing"),
+        Some(PermissionType::Readonly)
+    );
+    assert!(tree.can_see(2, "marketing"));
+    assert!(!tree.can_reply(2, "marketing"));
+    // Unknown codes are kept and grant nothing beyond `everyone`.
+    assert_eq!(
+        tree.get(2).unwrap().group_permissions.as_ref().unwrap()[2].permission_type,
+        PermissionType::Other(7)
+    );
+    assert_eq!(tree.permission(2, "bots"), Some(PermissionType::Readonly));
+    // Permissions not loaded.
+    assert_eq!(tree.permission(3, "platform-team"), None);
+    assert_eq!(
+        tree.postable_by("platform-team")
+            .iter()
+            .map(|category| category.id)
+            .collect::<Vec<_>>(),
+        vec![2]
+    );
+
+    let permissions = CategoryPermissions::everyone(PermissionType::Readonly)
+        .group("staff", PermissionType::Full)
+        .group("support", PermissionType::CreatePost);
+    assert_eq!(
+        serde_json::to_value(&permissions).unwrap(),
+        serde_json::json!({"everyone": 3, "staff": 1, "support": 2})
+    );
+    assert_eq!(
+        serde_json::from_value::<CategoryPermissions>(serde_json::json!({"staff": 1, "bots": 7}))
+            .unwrap(),
+        [
+            ("staff", PermissionType::Full),
+            ("bots", PermissionType::Other(7))
+        ]
+        .into_iter()
+        .collect()
+    );
+    assert_eq!(
+        CategoryPermissions::from(&crate::types::Permissions {
+            everyone: Some(3),
+            staff: None,
+        }),
+        CategoryPermissions::everyone(PermissionType::Readonly)
+    );
+    assert_eq!(
+        "create_post".parse::<PermissionType>().unwrap(),
+        PermissionType::CreatePost
+    );
+    assert_eq!(
+        "7".parse::<PermissionType>().unwrap(),
+        PermissionType::Other(7)
+    );
+
+    let stand_in = std::sync::Arc::new(std::sync::Mutex::new(QueuedStandIn {
+        responses: [(
+            200,
+            String::new(),
+            r#"{"category":{"id":6,"name":"Backend","slug":"backend","group_permissions":[]}}"#
+                .to_string(),
+        )]
+        .into(),
+        ..Default::default()
+    }));
+    let mut client = crate::Client::new("token");
+    client.set_base_url(serve_stand_in(stand_in.clone()).await);
+    let body: crate::types::CreateCategoryRequestBody = serde_json::from_value(serde_json::json!({
+        "name": "Backend",
+        "parent_category_id": 1,
+        "permissions": {"everyone": 1}
+    }))
+    .unwrap();
+    // Only the request is checked here.
+    let _ = client
+        .categories()
+        .create_category_with_permissions(&body, &permissions)
+        .await;
+    let stand_in = stand_in.lock().unwrap();
+    assert_eq!(
+        stand_in.requests,
+        vec![("POST".to_string(), "/categories.json".to_string())]
+    );
+    let sent: serde_json::Value = serde_json::from_str(&stand_in.bodies[0]).unwrap();
+    assert_eq!(sent["name"], "Backend");
+    assert_eq!(sent["parent_category_id"], 1);
+    assert_eq!(
+        sent["permissions"],
+        serde_json::json!({"everyone": 3, "staff": 1, "support": 2})
+    );
+}
+
+#[tokio::test]
+async fn test_badge_grants_are_idempotent() {
+    use crate::{
+        badge_grants::{BadgeReason, UserBadgeOutcome},
+        group_sync::MemberRef,
+    };
+
+    let ok = |body: &str| (200, String::new(), body.to_string());
+    let stand_in = std::sync::Arc::new(std::sync::Mutex::new(QueuedStandIn {
+        responses: [
+            ok(r#"{"badge":{"id":7,"name":"Helper"}}"#),
+            ok(r#"{"badges":[],"user_badges":[{"id":1,"badge_id":3}]}"#),
+            ok(r#"{"badges":[],"user_badge":{"id":10,"badge_id":7,"post_id":99}}"#),
+            ok(r#"{"user_badges":[{"id":11,"badge_id":7,"is_favorite":false}]}"#),
+            (
+                404,
+                String::new(),
+                r#"{"errors":["Not found"]}"#.to_string(),
+            ),
+            ok(r#"{"user_badges":[{"id":11,"badge_id":7},{"id":12,"badge_id":7}]}"#),
+            ok("{}"),
+            ok("{}"),
+            (
+                403,
+                String::new(),
+                r#"{"errors":["You are not permitted to view the requested resource."]}"#
+                    .to_string(),
+            ),
+            ok(r#"{"user_badges":[]}"#),
+            (
+                404,
+                String::new(),
+                r#"{"errors":["Not found"]}"#.to_string(),
+            ),
+        ]
+        .into(),
+        ..Default::default()
+    }));
+    let mut client = crate::Client::new("token");
+    let base_url = serve_stand_in(stand_in.clone()).await;
+    client.set_base_url(&base_url);
+
+    let reason = BadgeReason::Post {
+        topic_id: 42,
+        post_number: 3,
+    };
+    assert_eq!(
+        reason.url(&format!("{}/", base_url)),
+        format!("{}/t/42/3", base_url)
+    );
+    let users = ["alice", "Alice", "bob", "ghost"].map(MemberRef::from);
+    let results = client
+        .badges()
+        .grant_bulk(7, users, Some(&reason))
+        .await
+        .unwrap();
+    assert_eq!(
+        results
+            .iter()
+            .map(|result| result.outcome.clone())
+            .collect::<Vec<_>>(),
+        vec![
+            UserBadgeOutcome::Granted { user_badge_id: 10 },
+            UserBadgeOutcome::Duplicate { first_index: 0 },
+            UserBadgeOutcome::AlreadyGranted { user_badge_id: 11 },
+            UserBadgeOutcome::UnknownUser,
+        ]
+    );
+    assert_eq!(
+        serde_json::to_value(&results[2]).unwrap(),
+        serde_json::json!({
+            "index": 2,
+            "user": {"username": "bob"},
+            "username": "bob",
+            "outcome": "already_granted",
+            "user_badge_id": 11
+        })
+    );
+
+    let results = client
+        .badges()
+        .revoke_bulk(7, ["bob", "dee@example.com", "carol"].map(MemberRef::from))
+        .await
+        .unwrap();
+    assert_eq!(
+        results[0].outcome,
+        UserBadgeOutcome::Revoked {
+            user_badge_ids: vec![11, 12]
+        }
+    );
+    assert!(matches!(
+        &results[1].outcome,
+        UserBadgeOutcome::Failed { error } if error.contains("403")
+    ));
+    assert_eq!(results[2].outcome, UserBadgeOutcome::NotHeld);
+
+    // A missing badge fails the run before any user is looked up.
+    assert!(matches!(
+        client
+            .badges()
+            .grant_bulk(8, ["erin"].map(MemberRef::from), None)
+            .await,
+        Err(crate::types::error::Error::InvalidRequest(_))
+    ));
+
+    let requests = stand_in.lock().unwrap().requests.clone();
+    assert_eq!(
+        requests
+            .iter()
+            .map(|(method, path)| format!("{} {}", method, path))
+            .collect::<Vec<_>>(),
+        vec![
+            "GET /badges/7.json",
+            "GET /user-badges/alice.json?grouped=false",
+            "POST /user_badges.json",
+            "GET /user-badges/bob.json?grouped=false",
+            "GET /user-badges/ghost.json?grouped=false",
+            "GET /user-badges/bob.json?grouped=false",
+            "DELETE /user_badges/11.json",
+            "DELETE /user_badges/12.json",
+            "GET /admin/users.json?email=dee%40example.com&show_emails=true",
+            "GET /user-badges/carol.json?grouped=false",
+            "GET /badges/8.json",
+        ]
+    );
+}
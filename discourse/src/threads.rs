@@ -0,0 +1,147 @@
+//! Complete topic threads.
+//!
+//! `Topics::get` only returns the first chunk of posts of a topic, along with
+//! the IDs of all posts in `post_stream.stream`. These helpers fetch the
+//! remaining posts in chunks of the topic's `chunk_size` and return the whole
+//! thread in order.
+//!
+//! ```rust,no_run
+//! use futures_util::TryStreamExt;
+//!
+//! async fn example_threads() -> anyhow::Result<()> {
+//!     let client = discourse_api::Client::new_from_env();
+//!     let topics = client.topics();
+//!
+//!     let mut thread = topics.get_thread_stream("1234");
+//!     while let Some(post) = thread.try_next().await? {
+//!         println!("#{:?} {:?}", post.post_number, post.username);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use crate::{methods::parse_response, topics::Topics, types::Posts};
+
+impl Topics {
+    /// Get posts of a topic by ID, in the order of `post_ids`.
+    ///
+    /// Posts that are deleted or not visible to the user are left out.
+    ///
+    /// Unlike [`Topics::get_specific_posts_from`], which sends the IDs as a
+    /// JSON body that Discourse ignores on a GET, the IDs are sent as
+    /// `post_ids[]` query parameters.
+    #[tracing::instrument]
+    pub async fn get_posts<'a>(
+        &'a self,
+        id: &'a str,
+        post_ids: &[i64],
+    ) -> Result<Vec<Posts>, crate::types::error::Error> {
+        if post_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut req = self.client.client.request(
+            http::Method::GET,
+            format!("{}/t/{}/posts.json", self.client.base_url, id),
+        );
+        req = req.bearer_auth(&self.client.token);
+        let query_params: Vec<(&str, String)> = post_ids
+            .iter()
+            .map(|post_id| ("post_ids[]", post_id.to_string()))
+            .collect();
+        req = req.query(&query_params);
+        let resp: crate::types::GetSpecificPostsFromTopicResponse =
+            parse_response(req.send().await?).await?;
+
+        let mut by_id: HashMap<i64, Posts> = resp
+            .post_stream
+            .and_then(|s| s.posts)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|p| Some((p.id?, p)))
+            .collect();
+        Ok(post_ids.iter().filter_map(|i| by_id.remove(i)).collect())
+    }
+
+    /// Get every post of a topic in order, as a stream.
+    ///
+    /// The topic is read first; the posts it already includes are not fetched
+    /// again.
+    pub fn get_thread_stream<'a>(
+        &'a self,
+        id: &'a str,
+    ) -> impl futures::Stream<Item = Result<Posts, crate::types::error::Error>> + Unpin + 'a {
+        use futures::{StreamExt, TryStreamExt};
+
+        Box::pin(
+            futures::stream::once(self.get(id))
+                .map(|topic| {
+                    let topic = topic?;
+                    let chunk_size = usize::try_from(topic.chunk_size)
+                        .ok()
+                        .filter(|size| *size > 0)
+                        .unwrap_or(20);
+                    let stream: Vec<i64> = topic
+                        .post_stream
+                        .stream
+                        .iter()
+                        .filter_map(|v| v.as_i64())
+                        .collect();
+                    let mut loaded = HashMap::new();
+                    for post in topic.post_stream.posts {
+                        let post: Posts = serde_json::from_value(serde_json::to_value(post)?)?;
+                        if let Some(post_id) = post.id {
+                            loaded.insert(post_id, post);
+                        }
+                    }
+                    Ok::<_, crate::types::error::Error>((stream, chunk_size, loaded))
+                })
+                .map_ok(move |(stream, chunk_size, mut loaded)| {
+                    let chunks: Vec<Vec<i64>> =
+                        stream.chunks(chunk_size).map(|c| c.to_vec()).collect();
+                    let chunks: Vec<(Vec<i64>, HashMap<i64, Posts>)> = chunks
+                        .into_iter()
+                        .map(|ids| {
+                            let preloaded = ids
+                                .iter()
+                                .filter_map(|i| loaded.remove(i).map(|p| (*i, p)))
+                                .collect();
+                            (ids, preloaded)
+                        })
+                        .collect();
+                    futures::stream::iter(chunks)
+                        .then(move |(ids, mut preloaded)| async move {
+                            let missing: Vec<i64> = ids
+                                .iter()
+                                .filter(|i| !preloaded.contains_key(i))
+                                .copied()
+                                .collect();
+                            for post in self.get_posts(id, &missing).await? {
+                                if let Some(post_id) = post.id {
+                                    preloaded.insert(post_id, post);
+                                }
+                            }
+                            let posts: Vec<Posts> =
+                                ids.iter().filter_map(|i| preloaded.remove(i)).collect();
+                            Ok::<_, crate::types::error::Error>(futures::stream::iter(
+                                posts.into_iter().map(Ok),
+                            ))
+                        })
+                        .try_flatten()
+                })
+                .try_flatten(),
+        )
+    }
+
+    /// Get every post of a topic in order.
+    pub async fn get_thread<'a>(
+        &'a self,
+        id: &'a str,
+    ) -> Result<Vec<Posts>, crate::types::error::Error> {
+        use futures::TryStreamExt;
+
+        self.get_thread_stream(id).try_collect().await
+    }
+}
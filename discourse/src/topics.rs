@@ -12,7 +12,7 @@ impl Topics {
         Self { client }
     }
 
-    #[doc = "Get specific posts from a topic\n\n**Parameters:**\n\n- `id: &'astr` (required)\n\n```rust,no_run\nasync fn example_topics_get_specific_posts_from() -> anyhow::Result<()> {\n    let client = discourse_api::Client::new_from_env();\n    let result: discourse_api::types::GetSpecificPostsFromTopicResponse = client\n        .topics()\n        .get_specific_posts_from(\n            \"some-string\",\n            &discourse_api::types::GetSpecificPostsFromTopicRequestBody { post_ids: 4 as i64 },\n        )\n        .await?;\n    println!(\"{:?}\", result);\n    Ok(())\n}\n```"]
+    #[doc = "Get specific posts from a topic\n\n**Parameters:**\n\n- `id: &'astr` (required)\n\n```rust,no_run\nasync fn example_topics_get_specific_posts_from() -> anyhow::Result<()> {\n    let client = discourse_api::Client::new_from_env();\n    let result: discourse_api::types::GetSpecificPostsFromTopicResponse = client\n        .topics()\n        .get_specific_posts_from(\n            \"some-string\",\n            &discourse_api::types::GetSpecificPostsFromTopicRequestBody {\n                post_ids: vec![4 as i64],\n            },\n        )\n        .await?;\n    println!(\"{:?}\", result);\n    Ok(())\n}\n```"]
     #[tracing::instrument]
     pub async fn get_specific_posts_from<'a>(
         &'a self,
@@ -6192,7 +6192,7 @@ pub struct LatestPosts {
     pub updated_at: String,
     pub reply_count: i64,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub reply_to_post_number: Option<String>,
+    pub reply_to_post_number: Option<i64>,
     pub quote_count: i64,
     pub incoming_link_count: i64,
     pub reads: i64,
@@ -6574,7 +6574,7 @@ pub struct CreateTopicPostPMResponse {
     pub updated_at: String,
     pub reply_count: i64,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub reply_to_post_number: Option<String>,
+    pub reply_to_post_number: Option<i64>,
     pub quote_count: i64,
     pub incoming_link_count: i64,
     pub reads: i64,
@@ -6876,7 +6876,7 @@ pub struct GetPostResponse {
     pub updated_at: String,
     pub reply_count: i64,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub reply_to_post_number: Option<String>,
+    pub reply_to_post_number: Option<i64>,
     pub quote_count: i64,
     pub incoming_link_count: i64,
     pub reads: i64,
@@ -7172,7 +7172,7 @@ pub struct UpdatePostResponsePost {
     pub updated_at: String,
     pub reply_count: i64,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub reply_to_post_number: Option<String>,
+    pub reply_to_post_number: Option<i64>,
     pub quote_count: i64,
     pub incoming_link_count: i64,
     pub reads: i64,
@@ -7931,7 +7931,7 @@ pub struct PerformPostActionResponse {
     pub reply_count: i64,
     #[doc = "Post number this post is replying to"]
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub reply_to_post_number: Option<String>,
+    pub reply_to_post_number: Option<i64>,
     #[doc = "Number of times this post has been quoted"]
     pub quote_count: i64,
     #[doc = "Number of incoming links to this post"]
@@ -9369,7 +9369,7 @@ pub struct GroupedSearchResult {
     pub term: String,
     pub search_log_id: i64,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub more_full_page_results: Option<String>,
+    pub more_full_page_results: Option<bool>,
     pub can_create_topic: bool,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
@@ -12230,7 +12230,7 @@ impl tabled::Tabled for GetTagResponse {
 )]
 pub struct GetSpecificPostsFromTopicRequestBody {
     #[serde(rename = "post_ids[]")]
-    pub post_ids: i64,
+    pub post_ids: Vec<i64>,
 }
 
 impl std::fmt::Display for GetSpecificPostsFromTopicRequestBody {
@@ -12323,7 +12323,7 @@ pub struct Posts {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub reply_count: Option<i64>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub reply_to_post_number: Option<String>,
+    pub reply_to_post_number: Option<i64>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub quote_count: Option<i64>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -12841,7 +12841,7 @@ pub struct GetTopicResponsePostStreamPosts {
     pub updated_at: String,
     pub reply_count: i64,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub reply_to_post_number: Option<String>,
+    pub reply_to_post_number: Option<i64>,
     pub quote_count: i64,
     pub incoming_link_count: i64,
     pub reads: i64,
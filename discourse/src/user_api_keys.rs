@@ -0,0 +1,346 @@
+//! User API keys.
+//!
+//! A user API key lets an application act as one user, with the scopes that
+//! user granted. The application generates an RSA key pair and sends the user
+//! to `/user-api-key/new` with the public key, a nonce and the scopes it
+//! wants. Once the user approves, Discourse redirects to `auth_redirect` with
+//! a `payload` parameter: the key and the nonce, encrypted with the public
+//! key.
+//!
+//! ```rust,no_run
+//! use discourse_api::user_api_keys::{UserApiKeyRequest, UserApiScope};
+//!
+//! async fn example_user_api_keys(payload: &str) -> anyhow::Result<()> {
+//!     let request = UserApiKeyRequest::new(
+//!         "My app",
+//!         "my-app-client-id",
+//!         "https://app.example.com/discourse/callback",
+//!         vec![UserApiScope::Read, UserApiScope::Write],
+//!     )?;
+//!     println!("{}", request.authorization_url("https://discourse.example.com"));
+//!
+//!     // Once Discourse redirects back with `?payload=...`:
+//!     let key = request.decrypt_payload(payload)?;
+//!     let mut client = discourse_api::Client::new_from_user_api_key(key.key, &request.client_id)?;
+//!     client.set_base_url("https://discourse.example.com");
+//!     Ok(())
+//! }
+//! ```
+
+use base64::Engine;
+use rsa::{
+    pkcs8::{DecodePrivateKey, EncodePrivateKey, EncodePublicKey, LineEnding},
+    Oaep, RsaPrivateKey,
+};
+
+/// The header holding a user API key.
+pub const USER_API_KEY_HEADER: &str = "User-Api-Key";
+/// The header holding the client ID a user API key was issued to.
+pub const USER_API_CLIENT_ID_HEADER: &str = "User-Api-Client-Id";
+
+/// The size of generated RSA keys.
+const KEY_BITS: usize = 2048;
+
+/// What a user API key is allowed to do.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Hash,
+    Debug,
+    Clone,
+    schemars :: JsonSchema,
+    parse_display :: FromStr,
+    parse_display :: Display,
+)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "tabled", derive(tabled::Tabled))]
+pub enum UserApiScope {
+    #[serde(rename = "read")]
+    #[display("read")]
+    Read,
+    #[serde(rename = "write")]
+    #[display("write")]
+    Write,
+    #[serde(rename = "message_bus")]
+    #[display("message_bus")]
+    MessageBus,
+    #[serde(rename = "push")]
+    #[display("push")]
+    Push,
+    #[serde(rename = "notifications")]
+    #[display("notifications")]
+    Notifications,
+    #[serde(rename = "session_info")]
+    #[display("session_info")]
+    SessionInfo,
+    #[serde(rename = "one_time_password")]
+    #[display("one_time_password")]
+    OneTimePassword,
+    #[serde(rename = "bookmarks_calendar")]
+    #[display("bookmarks_calendar")]
+    BookmarksCalendar,
+    #[serde(rename = "user_status")]
+    #[display("user_status")]
+    UserStatus,
+}
+
+/// Why a user API key could not be requested or read.
+#[derive(Debug, thiserror::Error)]
+pub enum UserApiKeyError {
+    /// The RSA key could not be generated, encoded or used.
+    #[error("rsa: {0}")]
+    Rsa(#[from] rsa::Error),
+    /// The private key PEM could not be read or written.
+    #[error("private key: {0}")]
+    Pkcs8(#[from] rsa::pkcs8::Error),
+    /// The public key PEM could not be written.
+    #[error("public key: {0}")]
+    Spki(#[from] rsa::pkcs8::spki::Error),
+    /// The payload is not Base64.
+    #[error("invalid payload: {0}")]
+    InvalidPayload(#[from] base64::DecodeError),
+    /// The decrypted payload is not the expected JSON.
+    #[error("invalid payload: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+    /// The payload answers another request.
+    #[error("nonce mismatch")]
+    NonceMismatch,
+    /// The key or client ID cannot be sent as a header value.
+    #[error("{0} is not a valid header value")]
+    InvalidHeader(&'static str),
+}
+
+/// The decrypted callback payload.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct UserApiKey {
+    /// The user API key.
+    pub key: String,
+    pub nonce: String,
+    /// Whether push notifications were granted.
+    #[serde(default)]
+    pub push: bool,
+    /// The user API version of the site.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api: Option<i64>,
+}
+
+/// A pending user API key request: the key pair and nonce the callback is
+/// checked against.
+///
+/// Keep it, or [`UserApiKeyRequest::private_key_pem`] and the nonce, until the
+/// user comes back.
+#[derive(Clone)]
+pub struct UserApiKeyRequest {
+    pub application_name: String,
+    /// A unique ID for the application instance. Requesting a new key with
+    /// the same client ID revokes the previous one.
+    pub client_id: String,
+    /// Where Discourse sends the user with the payload.
+    pub auth_redirect: String,
+    pub scopes: Vec<UserApiScope>,
+    /// Where Discourse posts notifications, with the `push` scope.
+    pub push_url: Option<String>,
+    pub nonce: String,
+    private_key: RsaPrivateKey,
+}
+
+impl std::fmt::Debug for UserApiKeyRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UserApiKeyRequest")
+            .field("application_name", &self.application_name)
+            .field("client_id", &self.client_id)
+            .field("auth_redirect", &self.auth_redirect)
+            .field("scopes", &self.scopes)
+            .field("push_url", &self.push_url)
+            .field("nonce", &self.nonce)
+            .finish_non_exhaustive()
+    }
+}
+
+impl UserApiKeyRequest {
+    /// Start a request with a new key pair and nonce.
+    pub fn new<A, C, R>(
+        application_name: A,
+        client_id: C,
+        auth_redirect: R,
+        scopes: Vec<UserApiScope>,
+    ) -> Result<Self, UserApiKeyError>
+    where
+        A: ToString,
+        C: ToString,
+        R: ToString,
+    {
+        let private_key = RsaPrivateKey::new(&mut rand_core::OsRng, KEY_BITS)?;
+        Ok(UserApiKeyRequest {
+            application_name: application_name.to_string(),
+            client_id: client_id.to_string(),
+            auth_redirect: auth_redirect.to_string(),
+            scopes,
+            push_url: None,
+            nonce: uuid::Uuid::new_v4().simple().to_string(),
+            private_key,
+        })
+    }
+
+    /// Resume a request from its saved private key and nonce.
+    pub fn from_private_key_pem<A, C, R>(
+        application_name: A,
+        client_id: C,
+        auth_redirect: R,
+        scopes: Vec<UserApiScope>,
+        private_key_pem: &str,
+        nonce: &str,
+    ) -> Result<Self, UserApiKeyError>
+    where
+        A: ToString,
+        C: ToString,
+        R: ToString,
+    {
+        Ok(UserApiKeyRequest {
+            application_name: application_name.to_string(),
+            client_id: client_id.to_string(),
+            auth_redirect: auth_redirect.to_string(),
+            scopes,
+            push_url: None,
+            nonce: nonce.to_string(),
+            private_key: RsaPrivateKey::from_pkcs8_pem(private_key_pem)?,
+        })
+    }
+
+    /// The private key as PKCS#8 PEM, to save the request.
+    pub fn private_key_pem(&self) -> Result<String, UserApiKeyError> {
+        Ok(self.private_key.to_pkcs8_pem(LineEnding::LF)?.to_string())
+    }
+
+    /// The public key as PEM, as sent to Discourse.
+    pub fn public_key_pem(&self) -> Result<String, UserApiKeyError> {
+        Ok(self
+            .private_key
+            .to_public_key()
+            .to_public_key_pem(LineEnding::LF)?)
+    }
+
+    /// The URL to send the user to on a Discourse site.
+    pub fn authorization_url(&self, base_url: &str) -> String {
+        let scopes: Vec<String> = self.scopes.iter().map(|s| s.to_string()).collect();
+        let public_key = self
+            .public_key_pem()
+            .expect("an RSA public key can always be encoded");
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        query
+            .append_pair("application_name", &self.application_name)
+            .append_pair("client_id", &self.client_id)
+            .append_pair("scopes", &scopes.join(","))
+            .append_pair("public_key", &public_key)
+            .append_pair("nonce", &self.nonce)
+            .append_pair("auth_redirect", &self.auth_redirect)
+            .append_pair("padding", "oaep");
+        if let Some(push_url) = &self.push_url {
+            query.append_pair("push_url", push_url);
+        }
+        format!(
+            "{}/user-api-key/new?{}",
+            base_url.trim_end_matches('/'),
+            query.finish()
+        )
+    }
+
+    /// Decrypt the `payload` parameter of the callback and check its nonce.
+    pub fn decrypt_payload(&self, payload: &str) -> Result<UserApiKey, UserApiKeyError> {
+        let payload: String = payload.chars().filter(|c| !c.is_whitespace()).collect();
+        let encrypted = base64::engine::general_purpose::STANDARD.decode(payload)?;
+        let json = self
+            .private_key
+            .decrypt(Oaep::new::<sha1::Sha1>(), &encrypted)?;
+        let key: UserApiKey = serde_json::from_slice(&json)?;
+        if key.nonce != self.nonce {
+            return Err(UserApiKeyError::NonceMismatch);
+        }
+        Ok(key)
+    }
+}
+
+/// Drops the `Authorization: Bearer ` header every generated method adds, for
+/// clients that authenticate with a user API key and have no token.
+#[cfg(feature = "requests")]
+#[cfg(feature = "retry")]
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct OmitEmptyBearer;
+
+#[cfg(feature = "requests")]
+#[cfg(feature = "retry")]
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait::async_trait]
+impl reqwest_middleware::Middleware for OmitEmptyBearer {
+    async fn handle(
+        &self,
+        mut req: reqwest::Request,
+        extensions: &mut http::Extensions,
+        next: reqwest_middleware::Next<'_>,
+    ) -> reqwest_middleware::Result<reqwest::Response> {
+        let empty = req
+            .headers()
+            .get(reqwest::header::AUTHORIZATION)
+            .is_some_and(|v| v.as_bytes().trim_ascii() == b"Bearer");
+        if empty {
+            req.headers_mut().remove(reqwest::header::AUTHORIZATION);
+        }
+        next.run(req, extensions).await
+    }
+}
+
+#[cfg(feature = "requests")]
+impl crate::Client {
+    /// Create a new Client struct that authenticates with a user API key
+    /// instead of an API token.
+    ///
+    /// Requests carry no `Authorization` header, except without the `retry`
+    /// feature, where they carry an empty bearer token that Discourse ignores.
+    #[tracing::instrument(skip(key))]
+    pub fn new_from_user_api_key<K, C>(key: K, client_id: C) -> Result<Self, UserApiKeyError>
+    where
+        K: ToString,
+        C: ToString + std::fmt::Debug,
+    {
+        let mut headers = reqwest::header::HeaderMap::new();
+        let mut key = reqwest::header::HeaderValue::from_str(&key.to_string())
+            .map_err(|_| UserApiKeyError::InvalidHeader("user API key"))?;
+        key.set_sensitive(true);
+        headers.insert(USER_API_KEY_HEADER, key);
+        headers.insert(
+            USER_API_CLIENT_ID_HEADER,
+            reqwest::header::HeaderValue::from_str(&client_id.to_string())
+                .map_err(|_| UserApiKeyError::InvalidHeader("client ID"))?,
+        );
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let client = reqwest::Client::builder()
+            .user_agent(crate::APP_USER_AGENT)
+            .default_headers(headers.clone())
+            .timeout(std::time::Duration::from_secs(600))
+            .connect_timeout(std::time::Duration::from_secs(60));
+        #[cfg(target_arch = "wasm32")]
+        let client = reqwest::Client::builder().default_headers(headers.clone());
+        #[cfg(not(target_arch = "wasm32"))]
+        let client_http1 = reqwest::Client::builder()
+            .user_agent(crate::APP_USER_AGENT)
+            .default_headers(headers)
+            .timeout(std::time::Duration::from_secs(600))
+            .connect_timeout(std::time::Duration::from_secs(60))
+            .http1_only();
+        #[cfg(not(target_arch = "wasm32"))]
+        let client = Self::new_from_reqwest("", client, client_http1);
+        #[cfg(target_arch = "wasm32")]
+        let client = Self::new_from_reqwest("", client);
+
+        // Drop the empty bearer token after the generated middleware.
+        #[cfg(feature = "retry")]
+        #[cfg(not(target_arch = "wasm32"))]
+        let client = client.with_middleware(OmitEmptyBearer);
+        Ok(client)
+    }
+}
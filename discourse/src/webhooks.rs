@@ -0,0 +1,334 @@
+//! Verification and parsing of webhook requests.
+//!
+//! Discourse sends webhook requests with the event type and name in the
+//! `X-Discourse-Event-Type` and `X-Discourse-Event` headers, and signs the body
+//! with the webhook's secret: `X-Discourse-Event-Signature` holds
+//! `sha256=` followed by the hex HMAC-SHA256 of the body.
+//!
+//! ```rust,no_run
+//! use discourse_api::webhooks::{WebhookEvent, WebhookVerifier};
+//!
+//! fn example_webhooks(headers: &http::HeaderMap, body: &[u8]) -> anyhow::Result<()> {
+//!     let verifier = WebhookVerifier::new("webhook-secret");
+//!
+//!     match verifier.verify_and_parse(headers, body)? {
+//!         WebhookEvent::Post { event, post } if event == "post_created" => {
+//!             println!("{:?} posted in topic {:?}", post.username, post.topic_id);
+//!         }
+//!         WebhookEvent::Topic { event, topic } => {
+//!             println!("{} {:?}", event, topic.title);
+//!         }
+//!         _ => {}
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// The header holding the kind of object the event is about, e.g. `topic`.
+pub const EVENT_TYPE_HEADER: &str = "X-Discourse-Event-Type";
+/// The header holding the event name, e.g. `topic_created`.
+pub const EVENT_HEADER: &str = "X-Discourse-Event";
+/// The header holding the body signature.
+pub const SIGNATURE_HEADER: &str = "X-Discourse-Event-Signature";
+/// The header holding the ID of the delivery.
+pub const EVENT_ID_HEADER: &str = "X-Discourse-Event-Id";
+/// The header holding the base URL of the sending site.
+pub const INSTANCE_HEADER: &str = "X-Discourse-Instance";
+
+/// Why a webhook request was rejected.
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookError {
+    /// A required header is missing or not valid UTF-8.
+    #[error("missing header `{0}`")]
+    MissingHeader(&'static str),
+    /// The signature does not match the body.
+    #[error("invalid signature")]
+    InvalidSignature,
+    /// The body does not match the event type.
+    #[error("invalid webhook payload: {0}")]
+    InvalidPayload(#[from] serde_json::Error),
+}
+
+/// Checks the signatures of webhook requests with the webhook's secret.
+#[derive(Clone)]
+pub struct WebhookVerifier {
+    secret: String,
+}
+
+impl std::fmt::Debug for WebhookVerifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookVerifier").finish_non_exhaustive()
+    }
+}
+
+impl WebhookVerifier {
+    /// Create a verifier for the webhook with the given secret.
+    pub fn new<S: ToString>(secret: S) -> Self {
+        WebhookVerifier {
+            secret: secret.to_string(),
+        }
+    }
+
+    fn mac(&self, body: &[u8]) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(body);
+        mac
+    }
+
+    /// Compute the signature header Discourse sends for a body.
+    pub fn sign(&self, body: &[u8]) -> String {
+        format!(
+            "sha256={}",
+            data_encoding::HEXLOWER.encode(&self.mac(body).finalize().into_bytes())
+        )
+    }
+
+    /// Check the `X-Discourse-Event-Signature` value of a body.
+    pub fn verify(&self, body: &[u8], signature: &str) -> Result<(), WebhookError> {
+        let hex = signature
+            .trim()
+            .strip_prefix("sha256=")
+            .ok_or(WebhookError::InvalidSignature)?;
+        let signature = data_encoding::HEXLOWER_PERMISSIVE
+            .decode(hex.as_bytes())
+            .map_err(|_| WebhookError::InvalidSignature)?;
+        self.mac(body)
+            .verify_slice(&signature)
+            .map_err(|_| WebhookError::InvalidSignature)
+    }
+
+    /// Check a request using its signature header and parse its event.
+    #[cfg(feature = "requests")]
+    pub fn verify_and_parse(
+        &self,
+        headers: &http::HeaderMap,
+        body: &[u8],
+    ) -> Result<WebhookEvent, WebhookError> {
+        let header = |name: &'static str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .ok_or(WebhookError::MissingHeader(name))
+        };
+        self.verify(body, header(SIGNATURE_HEADER)?)?;
+        WebhookEvent::parse(header(EVENT_TYPE_HEADER)?, header(EVENT_HEADER)?, body)
+    }
+}
+
+/// A user as embedded in other payloads: the generated model of Discourse's
+/// basic user, with every field optional since `name` may be null.
+pub type BasicUser = crate::types::ListLatestTopicsResponseUsers;
+
+/// The topic of a `topic` event.
+///
+/// Not one of the generated topic models: `GetTopicResponse` requires the
+/// `post_stream` and `details` webhooks leave out, and the topic list models
+/// lack `user_id`, `deleted_at`, `tags`, `created_by` and `last_poster`.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct WebhookTopic {
+    pub id: i64,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fancy_title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub posts_count: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_posted_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archetype: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visible: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub closed: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<BasicUser>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_poster: Option<BasicUser>,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The user of a `user` event.
+///
+/// Not one of the generated user models: those require fields of the user
+/// page or admin views, like `can_ignore_user` or `time_read`, that webhooks
+/// leave out.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct WebhookUser {
+    pub id: i64,
+    pub username: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar_template: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moderator: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub staged: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trust_level: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The reviewable of a `reviewable` event.
+///
+/// The API spec has no reviewable model; the `reviewables` module reads the
+/// review queue with its own.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct WebhookReviewable {
+    pub id: i64,
+    /// E.g. `ReviewableFlaggedPost` or `ReviewableUser`.
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// `0` pending, `1` approved, `2` rejected, `3` ignored, `4` deleted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_created_by_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    /// Fields not listed above.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A webhook event, by `X-Discourse-Event-Type`. `event` is the
+/// `X-Discourse-Event` name, e.g. `topic_created` or `post_edited`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebhookEvent {
+    /// Sent when the webhook is tested.
+    Ping,
+    Topic {
+        event: String,
+        topic: Box<WebhookTopic>,
+    },
+    Post {
+        event: String,
+        post: Box<crate::types::Posts>,
+    },
+    User {
+        event: String,
+        user: Box<WebhookUser>,
+    },
+    Group {
+        event: String,
+        group: Box<crate::types::BasicGroup>,
+    },
+    Reviewable {
+        event: String,
+        reviewable: Box<WebhookReviewable>,
+    },
+    /// An event type without a variant, with its whole body.
+    Other {
+        event_type: String,
+        event: String,
+        payload: serde_json::Value,
+    },
+}
+
+impl WebhookEvent {
+    /// Parse a webhook body given its event type and name headers.
+    pub fn parse(event_type: &str, event: &str, body: &[u8]) -> Result<Self, WebhookError> {
+        let mut payload: serde_json::Value = serde_json::from_slice(body)?;
+        let mut take = |key: &str| payload.get_mut(key).map(serde_json::Value::take);
+        let event = event.to_string();
+
+        Ok(match (event_type, take(event_type)) {
+            ("ping", _) => WebhookEvent::Ping,
+            ("topic", Some(v)) => WebhookEvent::Topic {
+                event,
+                topic: serde_json::from_value(v)?,
+            },
+            ("post", Some(v)) => WebhookEvent::Post {
+                event,
+                post: serde_json::from_value(v)?,
+            },
+            ("user", Some(v)) => WebhookEvent::User {
+                event,
+                user: serde_json::from_value(v)?,
+            },
+            ("group", Some(v)) => WebhookEvent::Group {
+                event,
+                group: serde_json::from_value(v)?,
+            },
+            ("reviewable", Some(v)) => WebhookEvent::Reviewable {
+                event,
+                reviewable: serde_json::from_value(v)?,
+            },
+            (_, taken) => {
+                if let Some(v) = taken {
+                    payload[event_type] = v;
+                }
+                WebhookEvent::Other {
+                    event_type: event_type.to_string(),
+                    event,
+                    payload,
+                }
+            }
+        })
+    }
+
+    /// The `X-Discourse-Event` name of the event.
+    pub fn event(&self) -> &str {
+        match self {
+            WebhookEvent::Ping => "ping",
+            WebhookEvent::Topic { event, .. }
+            | WebhookEvent::Post { event, .. }
+            | WebhookEvent::User { event, .. }
+            | WebhookEvent::Group { event, .. }
+            | WebhookEvent::Reviewable { event, .. }
+            | WebhookEvent::Other { event, .. } => event,
+        }
+    }
+}
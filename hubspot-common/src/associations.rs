@@ -0,0 +1,672 @@
+//! Associations (v4) between CRM objects.
+//!
+//! Reading associations is possible through the `associations` parameter of
+//! the basic operations; this module creates, lists and removes them, one at a
+//! time or in batches, and manages custom association labels.
+//!
+//! The models are shared by the clients. Each client converts
+//! [`AssociationSpec`] to and from its generated `types::AssociationSpec`, so
+//! the constants below can also be used when creating objects.
+
+use std::marker::PhantomData;
+
+use crate::{
+    objects::ObjectType,
+    transport::{ClientError, Transport},
+};
+
+/// The kind of an association type.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Debug,
+    Clone,
+    schemars :: JsonSchema,
+    parse_display :: FromStr,
+    parse_display :: Display,
+)]
+pub enum AssociationCategory {
+    #[serde(rename = "HUBSPOT_DEFINED")]
+    #[display("HUBSPOT_DEFINED")]
+    HubspotDefined,
+    #[serde(rename = "USER_DEFINED")]
+    #[display("USER_DEFINED")]
+    UserDefined,
+    #[serde(rename = "INTEGRATOR_DEFINED")]
+    #[display("INTEGRATOR_DEFINED")]
+    IntegratorDefined,
+}
+
+/// An association type, by its category and ID.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Debug,
+    Clone,
+    schemars :: JsonSchema,
+)]
+pub struct AssociationSpec {
+    #[serde(rename = "associationCategory")]
+    pub association_category: AssociationCategory,
+    #[serde(rename = "associationTypeId")]
+    pub association_type_id: i32,
+}
+
+macro_rules! hubspot_defined {
+    ($($(#[$meta:meta])* $name:ident = $id:expr;)*) => {
+        /// The association types HubSpot defines between the built-in objects.
+        impl AssociationSpec {
+            $(
+                $(#[$meta])*
+                pub const $name: AssociationSpec = AssociationSpec {
+                    association_category: AssociationCategory::HubspotDefined,
+                    association_type_id: $id,
+                };
+            )*
+        }
+    };
+}
+
+hubspot_defined! {
+    /// The primary company of a contact.
+    CONTACT_TO_COMPANY_PRIMARY = 1;
+    /// The primary contact of a company.
+    COMPANY_TO_CONTACT_PRIMARY = 2;
+    DEAL_TO_CONTACT = 3;
+    CONTACT_TO_DEAL = 4;
+    /// The primary company of a deal.
+    DEAL_TO_COMPANY_PRIMARY = 5;
+    /// The primary deal of a company.
+    COMPANY_TO_DEAL_PRIMARY = 6;
+    CONTACT_TO_TICKET = 15;
+    TICKET_TO_CONTACT = 16;
+    /// The primary company of a ticket.
+    TICKET_TO_COMPANY_PRIMARY = 26;
+    DEAL_TO_TICKET = 27;
+    TICKET_TO_DEAL = 28;
+    CONTACT_TO_COMPANY = 279;
+    COMPANY_TO_CONTACT = 280;
+    TICKET_TO_COMPANY = 339;
+    COMPANY_TO_TICKET = 340;
+    DEAL_TO_COMPANY = 341;
+    COMPANY_TO_DEAL = 342;
+    CONTACT_TO_CONTACT = 449;
+}
+
+impl AssociationSpec {
+    /// An association type created by a user in the portal, e.g. a custom label.
+    pub const fn user_defined(type_id: i32) -> Self {
+        AssociationSpec {
+            association_category: AssociationCategory::UserDefined,
+            association_type_id: type_id,
+        }
+    }
+
+    /// An association type created by an app.
+    pub const fn integrator_defined(type_id: i32) -> Self {
+        AssociationSpec {
+            association_category: AssociationCategory::IntegratorDefined,
+            association_type_id: type_id,
+        }
+    }
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct PublicObjectId {
+    pub id: String,
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct NextPage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    pub after: String,
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct Paging {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<NextPage>,
+}
+
+/// The state of a batch operation.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Debug,
+    Clone,
+    schemars :: JsonSchema,
+    parse_display :: FromStr,
+    parse_display :: Display,
+)]
+pub enum Status {
+    #[serde(rename = "PENDING")]
+    #[display("PENDING")]
+    Pending,
+    #[serde(rename = "PROCESSING")]
+    #[display("PROCESSING")]
+    Processing,
+    #[serde(rename = "CANCELED")]
+    #[display("CANCELED")]
+    Canceled,
+    #[serde(rename = "COMPLETE")]
+    #[display("COMPLETE")]
+    Complete,
+}
+
+/// An item of a batch operation that failed.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct StandardError {
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub category: String,
+    pub message: String,
+    #[serde(default)]
+    pub context: std::collections::HashMap<String, Vec<String>>,
+}
+
+/// An association type as returned by the API, with its label if it has one.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct AssociationSpecWithLabel {
+    pub category: AssociationCategory,
+    #[serde(rename = "typeId")]
+    pub type_id: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl From<&AssociationSpecWithLabel> for AssociationSpec {
+    fn from(spec: &AssociationSpecWithLabel) -> Self {
+        AssociationSpec {
+            association_category: spec.category.clone(),
+            association_type_id: spec.type_id,
+        }
+    }
+}
+
+/// An object associated with another one, and the types of those associations.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct MultiAssociatedObjectWithLabel {
+    #[serde(rename = "toObjectId")]
+    pub to_object_id: i64,
+    #[serde(rename = "associationTypes")]
+    pub association_types: Vec<AssociationSpecWithLabel>,
+}
+
+/// A page of associated objects.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct CollectionResponseMultiAssociatedObjectWithLabel {
+    pub results: Vec<MultiAssociatedObjectWithLabel>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paging: Option<Paging>,
+}
+
+/// The labels between two objects after an association was created.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct LabelsBetweenObjectPair {
+    #[serde(rename = "fromObjectTypeId")]
+    pub from_object_type_id: String,
+    #[serde(rename = "fromObjectId")]
+    pub from_object_id: i64,
+    #[serde(rename = "toObjectTypeId")]
+    pub to_object_type_id: String,
+    #[serde(rename = "toObjectId")]
+    pub to_object_id: i64,
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+/// An association created with the default type.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct PublicDefaultAssociation {
+    pub from: PublicObjectId,
+    pub to: PublicObjectId,
+    #[serde(rename = "associationSpec")]
+    pub association_spec: AssociationSpec,
+}
+
+/// One association to create in a batch.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct AssociationInput {
+    pub from: PublicObjectId,
+    pub to: PublicObjectId,
+    pub types: Vec<AssociationSpec>,
+}
+
+/// One association with the default type to create in a batch.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct DefaultAssociationInput {
+    pub from: PublicObjectId,
+    pub to: PublicObjectId,
+}
+
+/// The associations of one object to remove in a batch.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ArchiveAssociationInput {
+    pub from: PublicObjectId,
+    pub to: Vec<PublicObjectId>,
+}
+
+/// One object to read the associations of in a batch.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct FetchAssociationsInput {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+/// The associations of one object read in a batch.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct MultiAssociatedObjects {
+    pub from: PublicObjectId,
+    pub to: Vec<MultiAssociatedObjectWithLabel>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paging: Option<Paging>,
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct BatchInput<T> {
+    pub inputs: Vec<T>,
+}
+
+/// The result of a batch operation. Items that failed are listed in `errors`.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct BatchResponse<T> {
+    pub status: Status,
+    pub results: Vec<T>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<StandardError>>,
+    #[serde(rename = "startedAt")]
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    #[serde(rename = "completedAt")]
+    pub completed_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// The definition of an association label between two object types.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct AssociationLabel {
+    pub category: AssociationCategory,
+    #[serde(rename = "typeId")]
+    pub type_id: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct CollectionResponseAssociationLabel {
+    pub results: Vec<AssociationLabel>,
+}
+
+/// A new association label.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct AssociationLabelCreate {
+    /// The internal name of the label.
+    pub name: String,
+    /// The label shown from the first object type.
+    pub label: String,
+    /// The label shown from the second object type, for asymmetric labels.
+    #[serde(
+        rename = "inverseLabel",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub inverse_label: Option<String>,
+}
+
+/// An update to an existing association label.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct AssociationLabelUpdate {
+    #[serde(rename = "associationTypeId")]
+    pub association_type_id: i32,
+    pub label: String,
+    #[serde(
+        rename = "inverseLabel",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub inverse_label: Option<String>,
+}
+
+/// Associations between CRM objects.
+pub struct Associations<E> {
+    transport: Transport,
+    error: PhantomData<fn() -> E>,
+}
+
+impl<E> Clone for Associations<E> {
+    fn clone(&self) -> Self {
+        Associations {
+            transport: self.transport.clone(),
+            error: PhantomData,
+        }
+    }
+}
+
+impl<E> std::fmt::Debug for Associations<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Associations")
+            .field("transport", &self.transport)
+            .finish()
+    }
+}
+
+impl<E: ClientError> Associations<E> {
+    #[doc(hidden)]
+    pub fn new(transport: Transport) -> Self {
+        Associations {
+            transport,
+            error: PhantomData,
+        }
+    }
+
+    fn object_path(
+        &self,
+        from_object_type: &ObjectType,
+        from_object_id: &str,
+        to_object_type: &ObjectType,
+    ) -> String {
+        format!(
+            "crm/v4/objects/{}/{}/associations/{}",
+            from_object_type, from_object_id, to_object_type
+        )
+    }
+
+    fn pair_path(
+        &self,
+        from_object_type: &ObjectType,
+        to_object_type: &ObjectType,
+        suffix: &str,
+    ) -> String {
+        format!(
+            "crm/v4/associations/{}/{}/{}",
+            from_object_type, to_object_type, suffix
+        )
+    }
+
+    /// Associate two objects with the default (unlabeled) association type.
+    #[tracing::instrument]
+    pub async fn create_default(
+        &self,
+        from_object_type: ObjectType,
+        from_object_id: &str,
+        to_object_type: ObjectType,
+        to_object_id: &str,
+    ) -> Result<BatchResponse<PublicDefaultAssociation>, E> {
+        let req = self.transport.request(
+            http::Method::PUT,
+            &format!(
+                "crm/v4/objects/{}/{}/associations/default/{}/{}",
+                from_object_type, from_object_id, to_object_type, to_object_id
+            ),
+        );
+        self.transport.send(req).await
+    }
+
+    /// Associate two objects with the given association types.
+    #[tracing::instrument]
+    pub async fn create(
+        &self,
+        from_object_type: ObjectType,
+        from_object_id: &str,
+        to_object_type: ObjectType,
+        to_object_id: &str,
+        types: &[AssociationSpec],
+    ) -> Result<LabelsBetweenObjectPair, E> {
+        let mut req = self.transport.request(
+            http::Method::PUT,
+            &format!(
+                "{}/{}",
+                self.object_path(&from_object_type, from_object_id, &to_object_type),
+                to_object_id
+            ),
+        );
+        req = req.json(types);
+        self.transport.send(req).await
+    }
+
+    /// List the objects of a type associated with an object.
+    #[tracing::instrument]
+    pub async fn list(
+        &self,
+        from_object_type: ObjectType,
+        from_object_id: &str,
+        to_object_type: ObjectType,
+        after: Option<String>,
+        limit: Option<i32>,
+    ) -> Result<CollectionResponseMultiAssociatedObjectWithLabel, E> {
+        let mut req = self.transport.request(
+            http::Method::GET,
+            &self.object_path(&from_object_type, from_object_id, &to_object_type),
+        );
+        let mut query_params = vec![];
+        if let Some(p) = after {
+            query_params.push(("after", p));
+        }
+
+        if let Some(p) = limit {
+            query_params.push(("limit", format!("{}", p)));
+        }
+
+        req = req.query(&query_params);
+        self.transport.send(req).await
+    }
+
+    /// Remove all associations between two objects.
+    #[tracing::instrument]
+    pub async fn archive(
+        &self,
+        from_object_type: ObjectType,
+        from_object_id: &str,
+        to_object_type: ObjectType,
+        to_object_id: &str,
+    ) -> Result<(), E> {
+        let req = self.transport.request(
+            http::Method::DELETE,
+            &format!(
+                "{}/{}",
+                self.object_path(&from_object_type, from_object_id, &to_object_type),
+                to_object_id
+            ),
+        );
+        self.transport.execute(req).await
+    }
+
+    /// Create a batch of associations between two object types.
+    #[tracing::instrument]
+    pub async fn batch_create(
+        &self,
+        from_object_type: ObjectType,
+        to_object_type: ObjectType,
+        body: &BatchInput<AssociationInput>,
+    ) -> Result<BatchResponse<LabelsBetweenObjectPair>, E> {
+        let mut req = self.transport.request(
+            http::Method::POST,
+            &self.pair_path(&from_object_type, &to_object_type, "batch/create"),
+        );
+        req = req.json(body);
+        self.transport.send(req).await
+    }
+
+    /// Create a batch of default (unlabeled) associations between two object types.
+    #[tracing::instrument]
+    pub async fn batch_create_default(
+        &self,
+        from_object_type: ObjectType,
+        to_object_type: ObjectType,
+        body: &BatchInput<DefaultAssociationInput>,
+    ) -> Result<BatchResponse<PublicDefaultAssociation>, E> {
+        let mut req = self.transport.request(
+            http::Method::POST,
+            &self.pair_path(
+                &from_object_type,
+                &to_object_type,
+                "batch/associate/default",
+            ),
+        );
+        req = req.json(body);
+        self.transport.send(req).await
+    }
+
+    /// Read the associations of a batch of objects.
+    #[tracing::instrument]
+    pub async fn batch_read(
+        &self,
+        from_object_type: ObjectType,
+        to_object_type: ObjectType,
+        body: &BatchInput<FetchAssociationsInput>,
+    ) -> Result<BatchResponse<MultiAssociatedObjects>, E> {
+        let mut req = self.transport.request(
+            http::Method::POST,
+            &self.pair_path(&from_object_type, &to_object_type, "batch/read"),
+        );
+        req = req.json(body);
+        self.transport.send(req).await
+    }
+
+    /// Remove all associations between a batch of object pairs.
+    #[tracing::instrument]
+    pub async fn batch_archive(
+        &self,
+        from_object_type: ObjectType,
+        to_object_type: ObjectType,
+        body: &BatchInput<ArchiveAssociationInput>,
+    ) -> Result<(), E> {
+        let mut req = self.transport.request(
+            http::Method::POST,
+            &self.pair_path(&from_object_type, &to_object_type, "batch/archive"),
+        );
+        req = req.json(body);
+        self.transport.execute(req).await
+    }
+
+    /// Remove only the given association types between a batch of object pairs.
+    #[tracing::instrument]
+    pub async fn batch_archive_labels(
+        &self,
+        from_object_type: ObjectType,
+        to_object_type: ObjectType,
+        body: &BatchInput<AssociationInput>,
+    ) -> Result<(), E> {
+        let mut req = self.transport.request(
+            http::Method::POST,
+            &self.pair_path(&from_object_type, &to_object_type, "batch/labels/archive"),
+        );
+        req = req.json(body);
+        self.transport.execute(req).await
+    }
+
+    /// List the association types, including labels, between two object types.
+    #[tracing::instrument]
+    pub async fn labels(
+        &self,
+        from_object_type: ObjectType,
+        to_object_type: ObjectType,
+    ) -> Result<CollectionResponseAssociationLabel, E> {
+        let req = self.transport.request(
+            http::Method::GET,
+            &self.pair_path(&from_object_type, &to_object_type, "labels"),
+        );
+        self.transport.send(req).await
+    }
+
+    /// Create an association label between two object types.
+    #[tracing::instrument]
+    pub async fn create_label(
+        &self,
+        from_object_type: ObjectType,
+        to_object_type: ObjectType,
+        body: &AssociationLabelCreate,
+    ) -> Result<CollectionResponseAssociationLabel, E> {
+        let mut req = self.transport.request(
+            http::Method::POST,
+            &self.pair_path(&from_object_type, &to_object_type, "labels"),
+        );
+        req = req.json(body);
+        self.transport.send(req).await
+    }
+
+    /// Rename an association label.
+    #[tracing::instrument]
+    pub async fn update_label(
+        &self,
+        from_object_type: ObjectType,
+        to_object_type: ObjectType,
+        body: &AssociationLabelUpdate,
+    ) -> Result<(), E> {
+        let mut req = self.transport.request(
+            http::Method::PUT,
+            &self.pair_path(&from_object_type, &to_object_type, "labels"),
+        );
+        req = req.json(body);
+        self.transport.execute(req).await
+    }
+
+    /// Delete an association label.
+    #[tracing::instrument]
+    pub async fn delete_label(
+        &self,
+        from_object_type: ObjectType,
+        to_object_type: ObjectType,
+        association_type_id: i32,
+    ) -> Result<(), E> {
+        let req = self.transport.request(
+            http::Method::DELETE,
+            &self.pair_path(
+                &from_object_type,
+                &to_object_type,
+                &format!("labels/{}", association_type_id),
+            ),
+        );
+        self.transport.execute(req).await
+    }
+}
@@ -0,0 +1,93 @@
+//! Errors the generated `Error` of the clients has no variant for.
+//!
+//! A request that stays rate limited fails with a [`RateLimitExceeded`]
+//! inside the generated error. Each client implements [`ErrorExt`] for its
+//! `types::error::Error`, so [`ErrorExt::rate_limited`] finds it whether it
+//! came from a generated or a hand-written method.
+
+use std::time::Duration;
+
+/// The body HubSpot sends with a `429 Too Many Requests` response.
+#[derive(serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitErrorBody {
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub error_type: Option<String>,
+    #[serde(default)]
+    pub correlation_id: Option<String>,
+    /// The limit that was hit, e.g. `TEN_SECONDLY_ROLLING`, `SECONDLY` or `DAILY`.
+    #[serde(default)]
+    pub policy_name: Option<String>,
+}
+
+/// A `429 Too Many Requests` response that was not retried.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimitExceeded {
+    /// How long to wait before sending again, or `None` when it is unknown or
+    /// the daily limit was hit.
+    pub wait: Option<Duration>,
+    /// The body of the response.
+    pub body: RateLimitErrorBody,
+}
+
+impl RateLimitExceeded {
+    /// Read a `429` response body. Bodies that are not the documented JSON
+    /// give an empty [`RateLimitErrorBody`].
+    pub fn from_body(wait: Option<Duration>, body: &str) -> Self {
+        RateLimitExceeded {
+            wait,
+            body: serde_json::from_str(body).unwrap_or_default(),
+        }
+    }
+
+    /// Find a rate limit error returned by a middleware, also when the retry
+    /// middleware wrapped it.
+    pub fn from_middleware_error(e: &reqwest_middleware::Error) -> Option<Self> {
+        let reqwest_middleware::Error::Middleware(e) = e else {
+            return None;
+        };
+        if let Some(e) = e.downcast_ref::<RateLimitExceeded>() {
+            return Some(e.clone());
+        }
+        match e.downcast_ref::<reqwest_retry::RetryError>()? {
+            reqwest_retry::RetryError::Error(e)
+            | reqwest_retry::RetryError::WithRetries { err: e, .. } => {
+                Self::from_middleware_error(e)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for RateLimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let policy = self.body.policy_name.as_deref().unwrap_or("unknown policy");
+        let message = self.body.message.as_deref().unwrap_or_default();
+        match self.wait {
+            Some(wait) => write!(
+                f,
+                "rate limited ({}), retry in {:?}: {}",
+                policy, wait, message
+            ),
+            None => write!(f, "rate limited ({}): {}", policy, message),
+        }
+    }
+}
+
+impl std::error::Error for RateLimitExceeded {}
+
+/// Typed details of the generated `Error` of a client.
+pub trait ErrorExt {
+    /// The `429` the request failed with, if it stayed rate limited. The rate
+    /// limiter of a client returns it as a middleware error; without one it
+    /// is a `429` server error.
+    fn rate_limited(&self) -> Option<RateLimitExceeded>;
+
+    /// How long HubSpot asked to wait, if it rate limited the request.
+    fn retry_after(&self) -> Option<Duration> {
+        self.rate_limited()?.wait
+    }
+}
@@ -0,0 +1,36 @@
+//! Hand-written building blocks shared by the Hubspot API clients:
+//! `hubspot-contacts`, `hubspot-tickets` and `hubspot-users`.
+//!
+//! [![docs.rs](https://docs.rs/hubspot-common/badge.svg)](https://docs.rs/hubspot-common)
+//!
+//! The clients re-export these modules, so they are usually used through them,
+//! e.g. `hubspot_contacts::rate_limit`. Each client hooks them in from its
+//! hand-written `methods.rs`, e.g. with constructors that append middleware to
+//! the stack the generated ones build. The generated `lib.rs` and
+//! `Cargo.toml` of a client only gain the module declarations and
+//! dependencies kept in its `hand-written.patch`, which `make` applies again
+//! after regenerating the client.
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+#[cfg(feature = "requests")]
+pub mod associations;
+#[cfg(feature = "requests")]
+pub mod errors;
+#[cfg(feature = "requests")]
+pub mod oauth;
+#[cfg(feature = "requests")]
+pub mod objects;
+#[cfg(feature = "requests")]
+pub mod properties;
+#[cfg(feature = "requests")]
+#[cfg(not(target_arch = "wasm32"))]
+pub mod rate_limit;
+#[cfg(any(test, feature = "test-util"))]
+#[cfg(not(target_arch = "wasm32"))]
+#[doc(hidden)]
+pub mod test_util;
+#[cfg(test)]
+mod tests;
+#[cfg(feature = "requests")]
+pub mod transport;
+pub mod webhooks;
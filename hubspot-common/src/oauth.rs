@@ -0,0 +1,702 @@
+//! OAuth authentication for public (marketplace) apps.
+//!
+//! An [`OAuthApp`] holds the app credentials and drives the authorization code
+//! flow: it builds the install URL, exchanges the returned code for tokens and
+//! refreshes them. Each portal (HubSpot account) that installs the app gets its
+//! own token set; [`Portals`] keeps one client per portal so a single process
+//! can serve many of them.
+//!
+//! The tokens are kept in a [`Session`]. A client built with the
+//! `new_from_session` constructor of the clients has the session installed as
+//! a middleware, shared by every clone of the client: it sets the current
+//! access token on each request and, once the token has expired, refreshes it
+//! first. Concurrent requests wait for a single refresh instead of each
+//! starting their own. Without the `retry` feature of the clients there is no
+//! middleware, so the token has to be refreshed by hand.
+
+use std::{
+    collections::HashMap,
+    env,
+    ops::Add,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+
+const AUTHORIZE_URL: &str = "https://app.hubspot.com/oauth/authorize";
+const DEFAULT_BASE_URL: &str = "https://api.hubapi.com";
+
+#[cfg(not(target_arch = "wasm32"))]
+static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), ".rs/", env!("CARGO_PKG_VERSION"),);
+
+/// Time in seconds before the access token expiration point that a refresh should
+/// be performed. This value is subtracted from the `expires_in` value returned by
+/// the provider prior to storing
+const REFRESH_THRESHOLD: Duration = Duration::from_secs(60);
+
+/// An access token.
+#[derive(Debug, schemars::JsonSchema, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct AccessToken {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub token_type: String,
+
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub access_token: String,
+    #[serde(default)]
+    pub expires_in: i64,
+
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub refresh_token: String,
+}
+
+/// An OAuth scope the app can request.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Debug,
+    Clone,
+    Copy,
+    schemars :: JsonSchema,
+    parse_display :: FromStr,
+    parse_display :: Display,
+)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum Scope {
+    #[serde(rename = "crm.import")]
+    #[display("crm.import")]
+    CrmImport,
+    #[serde(rename = "crm.objects.appointments.read")]
+    #[display("crm.objects.appointments.read")]
+    CrmObjectsAppointmentsRead,
+    #[serde(rename = "crm.objects.appointments.write")]
+    #[display("crm.objects.appointments.write")]
+    CrmObjectsAppointmentsWrite,
+    #[serde(rename = "crm.objects.carts.read")]
+    #[display("crm.objects.carts.read")]
+    CrmObjectsCartsRead,
+    #[serde(rename = "crm.objects.carts.write")]
+    #[display("crm.objects.carts.write")]
+    CrmObjectsCartsWrite,
+    #[serde(rename = "crm.objects.commercepayments.read")]
+    #[display("crm.objects.commercepayments.read")]
+    CrmObjectsCommercepaymentsRead,
+    #[serde(rename = "crm.objects.companies.read")]
+    #[display("crm.objects.companies.read")]
+    CrmObjectsCompaniesRead,
+    #[serde(rename = "crm.objects.companies.write")]
+    #[display("crm.objects.companies.write")]
+    CrmObjectsCompaniesWrite,
+    #[serde(rename = "crm.objects.contacts.read")]
+    #[display("crm.objects.contacts.read")]
+    CrmObjectsContactsRead,
+    #[serde(rename = "crm.objects.contacts.write")]
+    #[display("crm.objects.contacts.write")]
+    CrmObjectsContactsWrite,
+    #[serde(rename = "crm.objects.courses.read")]
+    #[display("crm.objects.courses.read")]
+    CrmObjectsCoursesRead,
+    #[serde(rename = "crm.objects.courses.write")]
+    #[display("crm.objects.courses.write")]
+    CrmObjectsCoursesWrite,
+    #[serde(rename = "crm.objects.custom.read")]
+    #[display("crm.objects.custom.read")]
+    CrmObjectsCustomRead,
+    #[serde(rename = "crm.objects.custom.write")]
+    #[display("crm.objects.custom.write")]
+    CrmObjectsCustomWrite,
+    #[serde(rename = "crm.objects.deals.read")]
+    #[display("crm.objects.deals.read")]
+    CrmObjectsDealsRead,
+    #[serde(rename = "crm.objects.deals.write")]
+    #[display("crm.objects.deals.write")]
+    CrmObjectsDealsWrite,
+    #[serde(rename = "crm.objects.goals.read")]
+    #[display("crm.objects.goals.read")]
+    CrmObjectsGoalsRead,
+    #[serde(rename = "crm.objects.goals.write")]
+    #[display("crm.objects.goals.write")]
+    CrmObjectsGoalsWrite,
+    #[serde(rename = "crm.objects.invoices.read")]
+    #[display("crm.objects.invoices.read")]
+    CrmObjectsInvoicesRead,
+    #[serde(rename = "crm.objects.invoices.write")]
+    #[display("crm.objects.invoices.write")]
+    CrmObjectsInvoicesWrite,
+    #[serde(rename = "crm.objects.leads.read")]
+    #[display("crm.objects.leads.read")]
+    CrmObjectsLeadsRead,
+    #[serde(rename = "crm.objects.leads.write")]
+    #[display("crm.objects.leads.write")]
+    CrmObjectsLeadsWrite,
+    #[serde(rename = "crm.objects.line_items.read")]
+    #[display("crm.objects.line_items.read")]
+    CrmObjectsLineItemsRead,
+    #[serde(rename = "crm.objects.line_items.write")]
+    #[display("crm.objects.line_items.write")]
+    CrmObjectsLineItemsWrite,
+    #[serde(rename = "crm.objects.listings.read")]
+    #[display("crm.objects.listings.read")]
+    CrmObjectsListingsRead,
+    #[serde(rename = "crm.objects.listings.write")]
+    #[display("crm.objects.listings.write")]
+    CrmObjectsListingsWrite,
+    #[serde(rename = "crm.objects.orders.read")]
+    #[display("crm.objects.orders.read")]
+    CrmObjectsOrdersRead,
+    #[serde(rename = "crm.objects.orders.write")]
+    #[display("crm.objects.orders.write")]
+    CrmObjectsOrdersWrite,
+    #[serde(rename = "crm.objects.partner-clients.read")]
+    #[display("crm.objects.partner-clients.read")]
+    CrmObjectsPartnerClientsRead,
+    #[serde(rename = "crm.objects.partner-clients.write")]
+    #[display("crm.objects.partner-clients.write")]
+    CrmObjectsPartnerClientsWrite,
+    #[serde(rename = "crm.objects.quotes.read")]
+    #[display("crm.objects.quotes.read")]
+    CrmObjectsQuotesRead,
+    #[serde(rename = "crm.objects.quotes.write")]
+    #[display("crm.objects.quotes.write")]
+    CrmObjectsQuotesWrite,
+    #[serde(rename = "crm.objects.services.read")]
+    #[display("crm.objects.services.read")]
+    CrmObjectsServicesRead,
+    #[serde(rename = "crm.objects.services.write")]
+    #[display("crm.objects.services.write")]
+    CrmObjectsServicesWrite,
+    #[serde(rename = "crm.objects.subscriptions.read")]
+    #[display("crm.objects.subscriptions.read")]
+    CrmObjectsSubscriptionsRead,
+    #[serde(rename = "crm.objects.users.read")]
+    #[display("crm.objects.users.read")]
+    CrmObjectsUsersRead,
+    #[serde(rename = "crm.objects.users.write")]
+    #[display("crm.objects.users.write")]
+    CrmObjectsUsersWrite,
+    #[serde(rename = "crm.schemas.companies.read")]
+    #[display("crm.schemas.companies.read")]
+    CrmSchemasCompaniesRead,
+    #[serde(rename = "crm.schemas.contacts.read")]
+    #[display("crm.schemas.contacts.read")]
+    CrmSchemasContactsRead,
+    #[serde(rename = "crm.schemas.contacts.write")]
+    #[display("crm.schemas.contacts.write")]
+    CrmSchemasContactsWrite,
+    #[serde(rename = "crm.schemas.custom.read")]
+    #[display("crm.schemas.custom.read")]
+    CrmSchemasCustomRead,
+    #[serde(rename = "crm.schemas.deals.read")]
+    #[display("crm.schemas.deals.read")]
+    CrmSchemasDealsRead,
+    #[serde(rename = "e-commerce")]
+    #[display("e-commerce")]
+    ECommerce,
+    #[serde(rename = "media_bridge.read")]
+    #[display("media_bridge.read")]
+    MediaBridgeRead,
+    #[serde(rename = "oauth")]
+    #[display("oauth")]
+    Oauth,
+    #[serde(rename = "settings.users.read")]
+    #[display("settings.users.read")]
+    SettingsUsersRead,
+    #[serde(rename = "settings.users.teams.read")]
+    #[display("settings.users.teams.read")]
+    SettingsUsersTeamsRead,
+    #[serde(rename = "settings.users.teams.write")]
+    #[display("settings.users.teams.write")]
+    SettingsUsersTeamsWrite,
+    #[serde(rename = "settings.users.write")]
+    #[display("settings.users.write")]
+    SettingsUsersWrite,
+    #[serde(rename = "tickets")]
+    #[display("tickets")]
+    Tickets,
+}
+
+/// Metadata about an access token, including the portal it belongs to.
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug, Clone, schemars::JsonSchema)]
+pub struct AccessTokenInfo {
+    /// The ID of the portal (HubSpot account) the token was issued for.
+    pub hub_id: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hub_domain: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<u64>,
+    /// The email of the user who installed the app.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<u64>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    #[serde(default)]
+    pub expires_in: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+}
+
+/// The credentials of an OAuth app.
+#[derive(Clone)]
+pub struct OAuthApp {
+    client_id: String,
+    client_secret: String,
+    redirect_uri: String,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl std::fmt::Debug for OAuthApp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OAuthApp")
+            .field("client_id", &self.client_id)
+            .field("redirect_uri", &self.redirect_uri)
+            .field("base_url", &self.base_url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl OAuthApp {
+    /// Create a new OAuth app from its credentials.
+    #[tracing::instrument(skip_all)]
+    pub fn new<I, K, R>(client_id: I, client_secret: K, redirect_uri: R) -> Self
+    where
+        I: ToString + std::fmt::Debug,
+        K: ToString + std::fmt::Debug,
+        R: ToString + std::fmt::Debug,
+    {
+        #[cfg(not(target_arch = "wasm32"))]
+        let client = reqwest::Client::builder()
+            .user_agent(APP_USER_AGENT)
+            .timeout(std::time::Duration::from_secs(60))
+            .connect_timeout(std::time::Duration::from_secs(60))
+            .build()
+            .expect("creating reqwest client failed");
+        #[cfg(target_arch = "wasm32")]
+        let client = reqwest::Client::new();
+
+        OAuthApp {
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            redirect_uri: redirect_uri.to_string(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            client,
+        }
+    }
+
+    /// Create a new OAuth app from the environment variables:
+    ///     - `HUBSPOT_CLIENT_ID`
+    ///     - `HUBSPOT_CLIENT_SECRET`
+    ///     - `HUBSPOT_REDIRECT_URI`
+    #[tracing::instrument]
+    pub fn new_from_env() -> Self {
+        let client_id = env::var("HUBSPOT_CLIENT_ID").expect("must set HUBSPOT_CLIENT_ID");
+        let client_secret =
+            env::var("HUBSPOT_CLIENT_SECRET").expect("must set HUBSPOT_CLIENT_SECRET");
+        let redirect_uri = env::var("HUBSPOT_REDIRECT_URI").expect("must set HUBSPOT_REDIRECT_URI");
+
+        OAuthApp::new(client_id, client_secret, redirect_uri)
+    }
+
+    /// Send the token requests of the app with a configured client, e.g. one
+    /// with a proxy or different timeouts.
+    pub fn set_client(&mut self, client: reqwest::Client) {
+        self.client = client;
+    }
+
+    /// Set the base URL of the token endpoints to something other than the
+    /// default: <https://api.hubapi.com>.
+    #[tracing::instrument]
+    pub fn set_base_url<H>(&mut self, base_url: H)
+    where
+        H: Into<String> + std::fmt::Display + std::fmt::Debug,
+    {
+        self.base_url = base_url.to_string().trim_end_matches('/').to_string();
+    }
+
+    /// The client ID of the app.
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// Return the URL a user visits to install the app.
+    ///
+    /// Installation fails if the portal cannot grant one of the required
+    /// `scopes`; `optional_scopes` are granted only when available. The `state`
+    /// is passed back unchanged to the redirect URI.
+    pub fn user_consent_url(
+        &self,
+        scopes: &[Scope],
+        optional_scopes: &[Scope],
+        state: &str,
+    ) -> String {
+        let join = |scopes: &[Scope]| itertools::join(scopes, " ");
+
+        let mut params = vec![
+            ("client_id", self.client_id.clone()),
+            ("redirect_uri", self.redirect_uri.clone()),
+        ];
+        if !scopes.is_empty() {
+            params.push(("scope", join(scopes)));
+        }
+        if !optional_scopes.is_empty() {
+            params.push(("optional_scope", join(optional_scopes)));
+        }
+        if !state.is_empty() {
+            params.push(("state", state.to_string()));
+        }
+
+        url::Url::parse_with_params(AUTHORIZE_URL, &params)
+            .expect("authorize URL is valid")
+            .to_string()
+    }
+
+    /// Get an access token from the code returned by the URL paramter sent to the
+    /// redirect URL.
+    pub async fn get_access_token(&self, code: &str) -> Result<AccessToken> {
+        self.token_request(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("client_id", &self.client_id),
+            ("client_secret", &self.client_secret),
+            ("redirect_uri", &self.redirect_uri),
+        ])
+        .await
+    }
+
+    /// Get a new access token from a refresh token.
+    ///
+    /// The response may carry a new refresh token, in which case it replaces the
+    /// one passed in.
+    pub async fn refresh_access_token(&self, refresh_token: &str) -> Result<AccessToken> {
+        if refresh_token.is_empty() {
+            anyhow::bail!("refresh token cannot be empty");
+        }
+
+        self.token_request(&[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("client_id", &self.client_id),
+            ("client_secret", &self.client_secret),
+            ("redirect_uri", &self.redirect_uri),
+        ])
+        .await
+    }
+
+    /// Look up which portal and user an access token belongs to, and the scopes it grants.
+    pub async fn access_token_info(&self, access_token: &str) -> Result<AccessTokenInfo> {
+        let resp = self
+            .client
+            .get(format!(
+                "{}/oauth/v1/access-tokens/{}",
+                self.base_url, access_token
+            ))
+            .header(
+                reqwest::header::ACCEPT,
+                reqwest::header::HeaderValue::from_static("application/json"),
+            )
+            .send()
+            .await?;
+
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        if !status.is_success() {
+            anyhow::bail!("looking up access token failed: {} {}", status, text);
+        }
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    async fn token_request(&self, params: &[(&str, &str)]) -> Result<AccessToken> {
+        let resp = self
+            .client
+            .post(format!("{}/oauth/v1/token", self.base_url))
+            .header(
+                reqwest::header::ACCEPT,
+                reqwest::header::HeaderValue::from_static("application/json"),
+            )
+            .form(params)
+            .send()
+            .await?;
+
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        if !status.is_success() {
+            anyhow::bail!("token request failed: {} {}", status, text);
+        }
+        Ok(serde_json::from_str(&text)?)
+    }
+}
+
+#[derive(Default)]
+struct TokenState {
+    /// The access token requests are sent with, or `None` to keep the one the
+    /// client was created with.
+    access_token: Option<String>,
+    refresh_token: String,
+    expires_at: Option<Instant>,
+    app: Option<OAuthApp>,
+    auto_refresh: bool,
+}
+
+impl std::fmt::Debug for TokenState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenState")
+            .field("expires_at", &self.expires_at)
+            .field("app", &self.app)
+            .field("auto_refresh", &self.auto_refresh)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TokenState {
+    fn needs_refresh(&self) -> bool {
+        self.auto_refresh && self.expires_at.is_some_and(|e| e <= Instant::now())
+    }
+}
+
+/// The tokens of a client, shared by all its clones.
+///
+/// Cloning a `Session` is cheap and the clones share the same tokens.
+#[derive(Clone, Debug, Default)]
+pub struct Session {
+    state: Arc<Mutex<TokenState>>,
+    /// Held for the length of a refresh, so only one runs at a time.
+    refreshing: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl Session {
+    /// Create a session for a token issued to an app, refreshed through the
+    /// app once it expires.
+    pub fn new(app: OAuthApp, token: &AccessToken) -> Self {
+        let session = Session::default();
+        session.set_app(Some(app));
+        session.set_token(token);
+        session.set_auto_refresh(true);
+        session
+    }
+
+    /// The app the tokens were issued to, if any.
+    pub fn app(&self) -> Option<OAuthApp> {
+        self.lock().app.clone()
+    }
+
+    /// Set the app the tokens are refreshed through.
+    pub fn set_app(&self, app: Option<OAuthApp>) {
+        self.lock().app = app;
+    }
+
+    /// Store a token, e.g. one refreshed elsewhere. An empty refresh token
+    /// keeps the current one.
+    pub fn set_token(&self, token: &AccessToken) {
+        let mut state = self.lock();
+        state.access_token = Some(token.access_token.clone());
+        if !token.refresh_token.is_empty() {
+            state.refresh_token = token.refresh_token.clone();
+        }
+        state.expires_at = compute_expires_at(token.expires_in);
+    }
+
+    /// The access token requests are sent with, if one was stored.
+    pub fn access_token(&self) -> Option<String> {
+        self.lock().access_token.clone()
+    }
+
+    /// The refresh token currently stored. HubSpot may rotate it on every
+    /// refresh, so persist it again after refreshing.
+    pub fn refresh_token(&self) -> String {
+        self.lock().refresh_token.clone()
+    }
+
+    /// Enables or disables the automatic refreshing of access tokens upon expiration
+    pub fn set_auto_refresh(&self, enabled: bool) {
+        self.lock().auto_refresh = enabled;
+    }
+
+    /// Sets a specific `Instant` at which the access token should be considered expired.
+    /// `None` may be passed in if the expiration is unknown.
+    pub fn set_expires_at(&self, expires_at: Option<Instant>) {
+        self.lock().expires_at = expires_at;
+    }
+
+    /// Gets the `Instant` at which the access token is set to expire if one is known
+    pub fn expires_at(&self) -> Option<Instant> {
+        self.lock().expires_at
+    }
+
+    /// Sets the number of seconds in which the current access token should be
+    /// considered expired, less a minute of leeway.
+    pub fn set_expires_in(&self, expires_in: i64) {
+        self.lock().expires_at = compute_expires_at(expires_in);
+    }
+
+    /// Gets the number of seconds from now in which the current access token will be
+    /// considered expired if one is known
+    pub fn expires_in(&self) -> Option<Duration> {
+        self.expires_at()
+            .map(|i| i.saturating_duration_since(Instant::now()))
+    }
+
+    /// Determines if the access token is expired. If the expiration can not be
+    /// determined, None is returned
+    pub fn is_expired(&self) -> Option<bool> {
+        self.expires_at()
+            .map(|expiration| expiration <= Instant::now())
+    }
+
+    /// Refresh the access token with the refresh token. A refresh already
+    /// running is waited for first.
+    pub async fn refresh(&self) -> Result<AccessToken> {
+        let _refreshing = self.refreshing.lock().await;
+        self.refresh_now().await
+    }
+
+    /// The access token to send a request with, refreshed first if automatic
+    /// refreshing is on and it has expired.
+    pub async fn fresh_access_token(&self) -> Result<Option<String>> {
+        if self.lock().needs_refresh() {
+            let _refreshing = self.refreshing.lock().await;
+            // Another request may have refreshed it while this one waited.
+            if self.lock().needs_refresh() {
+                self.refresh_now().await?;
+            }
+        }
+        Ok(self.access_token())
+    }
+
+    async fn refresh_now(&self) -> Result<AccessToken> {
+        let (app, refresh_token) = {
+            let state = self.lock();
+            let Some(app) = state.app.clone() else {
+                anyhow::bail!("client was not created from an OAuth app");
+            };
+            (app, state.refresh_token.clone())
+        };
+
+        let token = app.refresh_access_token(&refresh_token).await?;
+        self.set_token(&token);
+        Ok(token)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TokenState> {
+        // The state is always left consistent, so a poisoned lock is still usable.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait::async_trait]
+impl reqwest_middleware::Middleware for Session {
+    async fn handle(
+        &self,
+        mut req: reqwest::Request,
+        extensions: &mut http::Extensions,
+        next: reqwest_middleware::Next<'_>,
+    ) -> reqwest_middleware::Result<reqwest::Response> {
+        let token = self
+            .fresh_access_token()
+            .await
+            .map_err(reqwest_middleware::Error::Middleware)?;
+        if let Some(token) = token {
+            let value = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token))
+                .map_err(reqwest_middleware::Error::middleware)?;
+            req.headers_mut()
+                .insert(reqwest::header::AUTHORIZATION, value);
+        }
+        next.run(req, extensions).await
+    }
+}
+
+fn compute_expires_at(expires_in: i64) -> Option<Instant> {
+    let seconds_valid = expires_in
+        .try_into()
+        .ok()
+        .map(Duration::from_secs)
+        .and_then(|dur| dur.checked_sub(REFRESH_THRESHOLD))
+        .or_else(|| Some(Duration::from_secs(0)));
+
+    seconds_valid.map(|seconds_valid| Instant::now().add(seconds_valid))
+}
+
+/// A client that can be created for the tokens of an app, so that
+/// [`Portals`] can keep one per portal.
+pub trait OAuthClient: Clone {
+    /// Create a client that sends the access token of `session`, refreshed
+    /// through the session once it expires.
+    fn from_session(session: &Session) -> Self;
+}
+
+/// One client per portal that installed the app, keyed by hub ID.
+///
+/// Cloning `Portals` is cheap and the clones share the same clients.
+#[derive(Clone, Debug)]
+pub struct Portals<C> {
+    app: OAuthApp,
+    clients: Arc<tokio::sync::RwLock<HashMap<u64, (C, Session)>>>,
+}
+
+impl<C: OAuthClient> Portals<C> {
+    /// Create an empty set of portals for an app.
+    pub fn new(app: OAuthApp) -> Self {
+        Portals {
+            app,
+            clients: Default::default(),
+        }
+    }
+
+    /// The app the portals authenticate with.
+    pub fn app(&self) -> &OAuthApp {
+        &self.app
+    }
+
+    /// Complete an installation: exchange the authorization code, look up the
+    /// portal the token was issued for and register a client for it.
+    pub async fn install(&self, code: &str) -> Result<(u64, C)> {
+        let token = self.app.get_access_token(code).await?;
+        let info = self.app.access_token_info(&token.access_token).await?;
+        let client = self.insert(info.hub_id, &token).await;
+        Ok((info.hub_id, client))
+    }
+
+    /// Register a portal from a previously stored token, replacing any client
+    /// it already had.
+    pub async fn insert(&self, hub_id: u64, token: &AccessToken) -> C {
+        let session = Session::new(self.app.clone(), token);
+        let client = C::from_session(&session);
+        self.clients
+            .write()
+            .await
+            .insert(hub_id, (client.clone(), session));
+        client
+    }
+
+    /// The client for a portal, with its access token refreshed first if it has expired.
+    pub async fn get(&self, hub_id: u64) -> Result<Option<C>> {
+        let Some((client, session)) = self.clients.read().await.get(&hub_id).cloned() else {
+            return Ok(None);
+        };
+        session.fresh_access_token().await?;
+        Ok(Some(client))
+    }
+
+    /// The session holding the tokens of a portal, e.g. to persist its
+    /// refresh token after a refresh.
+    pub async fn session(&self, hub_id: u64) -> Option<Session> {
+        let clients = self.clients.read().await;
+        clients.get(&hub_id).map(|(_, session)| session.clone())
+    }
+
+    /// Forget a portal, e.g. after the app was uninstalled from it.
+    pub async fn remove(&self, hub_id: u64) -> Option<C> {
+        let removed = self.clients.write().await.remove(&hub_id);
+        removed.map(|(client, _)| client)
+    }
+
+    /// The hub IDs of all registered portals.
+    pub async fn hub_ids(&self) -> Vec<u64> {
+        self.clients.read().await.keys().copied().collect()
+    }
+}
@@ -0,0 +1,377 @@
+//! Basic, batch and search operations for any CRM object type.
+//!
+//! The generated `basic()`, `batch()` and `search()` modules of a client only
+//! cover its own object type. [`Objects`] exposes the same operations for
+//! every object type that lives under `/crm/v3/objects`, including companies,
+//! deals and custom objects addressed by their `objectTypeId`.
+//!
+//! The operations read and write the generated models of the client they are
+//! used through, named by its [`ObjectModels`].
+
+use std::marker::PhantomData;
+
+use crate::transport::{ClientError, Transport};
+
+/// A CRM object type.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ObjectType {
+    Contacts,
+    Companies,
+    Deals,
+    Tickets,
+    Products,
+    LineItems,
+    Quotes,
+    Calls,
+    Emails,
+    Meetings,
+    Notes,
+    Tasks,
+    /// A custom object, or any other type, by its `objectTypeId` (e.g. `2-1234567`)
+    /// or fully qualified name.
+    Custom(String),
+}
+
+impl ObjectType {
+    /// The name of the object type as used in API paths.
+    pub fn as_str(&self) -> &str {
+        match self {
+            ObjectType::Contacts => "contacts",
+            ObjectType::Companies => "companies",
+            ObjectType::Deals => "deals",
+            ObjectType::Tickets => "tickets",
+            ObjectType::Products => "products",
+            ObjectType::LineItems => "line_items",
+            ObjectType::Quotes => "quotes",
+            ObjectType::Calls => "calls",
+            ObjectType::Emails => "emails",
+            ObjectType::Meetings => "meetings",
+            ObjectType::Notes => "notes",
+            ObjectType::Tasks => "tasks",
+            ObjectType::Custom(s) => s,
+        }
+    }
+}
+
+impl std::fmt::Display for ObjectType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for ObjectType {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The built-in types also have `0-N` object type IDs.
+        Ok(match s {
+            "contacts" | "contact" | "0-1" => ObjectType::Contacts,
+            "companies" | "company" | "0-2" => ObjectType::Companies,
+            "deals" | "deal" | "0-3" => ObjectType::Deals,
+            "tickets" | "ticket" | "0-5" => ObjectType::Tickets,
+            "products" | "product" | "0-7" => ObjectType::Products,
+            "line_items" | "line_item" | "0-8" => ObjectType::LineItems,
+            "quotes" | "quote" | "0-14" => ObjectType::Quotes,
+            "calls" | "call" | "0-48" => ObjectType::Calls,
+            "emails" | "email" | "0-49" => ObjectType::Emails,
+            "meetings" | "meeting" | "0-47" => ObjectType::Meetings,
+            "notes" | "note" | "0-46" => ObjectType::Notes,
+            "tasks" | "task" | "0-27" => ObjectType::Tasks,
+            other => ObjectType::Custom(other.to_string()),
+        })
+    }
+}
+
+impl From<&str> for ObjectType {
+    fn from(s: &str) -> Self {
+        match s.parse() {
+            Ok(t) => t,
+            Err(e) => match e {},
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GetPageParams {
+    /// The paging cursor returned as `paging.next.after` by the previous page.
+    pub after: Option<String>,
+    pub archived: Option<bool>,
+    /// Object types to retrieve associated IDs for.
+    pub associations: Option<Vec<String>>,
+    pub limit: Option<i32>,
+    pub properties: Option<Vec<String>>,
+    pub properties_with_history: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GetByIdParams {
+    pub archived: Option<bool>,
+    /// Object types to retrieve associated IDs for.
+    pub associations: Option<Vec<String>>,
+    /// A unique property to look the object up by instead of its ID.
+    pub id_property: Option<String>,
+    pub properties: Option<Vec<String>>,
+    pub properties_with_history: Option<Vec<String>>,
+}
+
+/// The generated models and error of a client, which [`Objects`] reads and
+/// writes.
+pub trait ObjectModels {
+    /// `CollectionResponseSimplePublicObjectWithAssociationsForwardPaging`
+    type Page: serde::de::DeserializeOwned;
+    /// `SimplePublicObjectWithAssociations`
+    type ObjectWithAssociations: serde::de::DeserializeOwned;
+    /// `SimplePublicObject`
+    type Object: serde::de::DeserializeOwned;
+    /// `SimplePublicObjectInputForCreate`
+    type CreateInput: serde::Serialize + std::fmt::Debug;
+    /// `SimplePublicObjectInput`
+    type UpdateInput: serde::Serialize + std::fmt::Debug;
+    /// `PublicMergeInput`
+    type MergeInput: serde::Serialize + std::fmt::Debug;
+    /// `BatchReadInputSimplePublicObjectId`
+    type BatchReadInput: serde::Serialize + std::fmt::Debug;
+    /// The batch create input of the client, e.g.
+    /// `BatchInputSimplePublicObjectInputForCreate`
+    type BatchCreateInput: serde::Serialize + std::fmt::Debug;
+    /// `BatchInputSimplePublicObjectBatchInput`
+    type BatchUpdateInput: serde::Serialize + std::fmt::Debug;
+    /// `BatchInputSimplePublicObjectBatchInputUpsert`
+    type BatchUpsertInput: serde::Serialize + std::fmt::Debug;
+    /// `BatchInputSimplePublicObjectId`
+    type BatchArchiveInput: serde::Serialize + std::fmt::Debug;
+    /// `BatchResponseSimplePublicObject`
+    type BatchResponse: serde::de::DeserializeOwned;
+    /// `BatchResponseSimplePublicUpsertObject`
+    type BatchUpsertResponse: serde::de::DeserializeOwned;
+    /// `PublicObjectSearchRequest`
+    type SearchRequest: serde::Serialize + std::fmt::Debug;
+    /// `CollectionResponseWithTotalSimplePublicObjectForwardPaging`
+    type SearchPage: serde::de::DeserializeOwned;
+    /// `types::error::Error`
+    type Error: ClientError;
+}
+
+/// Basic, batch and search operations for one object type.
+pub struct Objects<M> {
+    transport: Transport,
+    pub object_type: ObjectType,
+    models: PhantomData<fn() -> M>,
+}
+
+impl<M> Clone for Objects<M> {
+    fn clone(&self) -> Self {
+        Objects {
+            transport: self.transport.clone(),
+            object_type: self.object_type.clone(),
+            models: PhantomData,
+        }
+    }
+}
+
+impl<M> std::fmt::Debug for Objects<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Objects")
+            .field("transport", &self.transport)
+            .field("object_type", &self.object_type)
+            .finish()
+    }
+}
+
+impl<M: ObjectModels> Objects<M> {
+    #[doc(hidden)]
+    pub fn new(transport: Transport, object_type: ObjectType) -> Self {
+        Objects {
+            transport,
+            object_type,
+            models: PhantomData,
+        }
+    }
+
+    fn request(&self, method: http::Method, suffix: &str) -> reqwest_middleware::RequestBuilder {
+        let path = format!("crm/v3/objects/{}", self.object_type.as_str());
+        if suffix.is_empty() {
+            self.transport.request(method, &path)
+        } else {
+            self.transport
+                .request(method, &format!("{}/{}", path, suffix))
+        }
+    }
+
+    /// Read a page of objects. Continue with `paging.next.after` from the response.
+    #[tracing::instrument]
+    pub async fn get_page(&self, params: GetPageParams) -> Result<M::Page, M::Error> {
+        let GetPageParams {
+            after,
+            archived,
+            associations,
+            limit,
+            properties,
+            properties_with_history,
+        } = params;
+        let mut query_params = vec![];
+        if let Some(p) = after {
+            query_params.push(("after", p));
+        }
+
+        if let Some(p) = archived {
+            query_params.push(("archived", format!("{}", p)));
+        }
+
+        if let Some(p) = associations {
+            query_params.push(("associations", itertools::join(p, ",")));
+        }
+
+        if let Some(p) = limit {
+            query_params.push(("limit", format!("{}", p)));
+        }
+
+        if let Some(p) = properties {
+            query_params.push(("properties", itertools::join(p, ",")));
+        }
+
+        if let Some(p) = properties_with_history {
+            query_params.push(("propertiesWithHistory", itertools::join(p, ",")));
+        }
+
+        let req = self.request(http::Method::GET, "").query(&query_params);
+        self.transport.send(req).await
+    }
+
+    /// Read an object by its ID, or by a unique property when `id_property` is set.
+    #[tracing::instrument]
+    pub async fn get_by_id(
+        &self,
+        object_id: &str,
+        params: GetByIdParams,
+    ) -> Result<M::ObjectWithAssociations, M::Error> {
+        let GetByIdParams {
+            archived,
+            associations,
+            id_property,
+            properties,
+            properties_with_history,
+        } = params;
+        let mut query_params = vec![];
+        if let Some(p) = archived {
+            query_params.push(("archived", format!("{}", p)));
+        }
+
+        if let Some(p) = associations {
+            query_params.push(("associations", itertools::join(p, ",")));
+        }
+
+        if let Some(p) = id_property {
+            query_params.push(("idProperty", p));
+        }
+
+        if let Some(p) = properties {
+            query_params.push(("properties", itertools::join(p, ",")));
+        }
+
+        if let Some(p) = properties_with_history {
+            query_params.push(("propertiesWithHistory", itertools::join(p, ",")));
+        }
+
+        let req = self
+            .request(http::Method::GET, object_id)
+            .query(&query_params);
+        self.transport.send(req).await
+    }
+
+    /// Create an object.
+    #[tracing::instrument]
+    pub async fn create(&self, body: &M::CreateInput) -> Result<M::Object, M::Error> {
+        let req = self.request(http::Method::POST, "").json(body);
+        self.transport.send(req).await
+    }
+
+    /// Update an object by its ID, or by a unique property when `id_property` is set.
+    #[tracing::instrument]
+    pub async fn update(
+        &self,
+        object_id: &str,
+        id_property: Option<String>,
+        body: &M::UpdateInput,
+    ) -> Result<M::Object, M::Error> {
+        let mut req = self.request(http::Method::PATCH, object_id);
+        if let Some(p) = id_property {
+            req = req.query(&[("idProperty", p)]);
+        }
+        req = req.json(body);
+        self.transport.send(req).await
+    }
+
+    /// Move an object to the recycling bin.
+    #[tracing::instrument]
+    pub async fn archive(&self, object_id: &str) -> Result<(), M::Error> {
+        let req = self.request(http::Method::DELETE, object_id);
+        self.transport.execute(req).await
+    }
+
+    /// Merge two objects of the same type.
+    #[tracing::instrument]
+    pub async fn merge(&self, body: &M::MergeInput) -> Result<M::Object, M::Error> {
+        let req = self.request(http::Method::POST, "merge").json(body);
+        self.transport.send(req).await
+    }
+
+    /// Read a batch of objects by ID or by a unique property.
+    #[tracing::instrument]
+    pub async fn batch_read(
+        &self,
+        archived: Option<bool>,
+        body: &M::BatchReadInput,
+    ) -> Result<M::BatchResponse, M::Error> {
+        let mut req = self.request(http::Method::POST, "batch/read");
+        if let Some(p) = archived {
+            req = req.query(&[("archived", format!("{}", p))]);
+        }
+        req = req.json(body);
+        self.transport.send(req).await
+    }
+
+    /// Create a batch of objects.
+    #[tracing::instrument]
+    pub async fn batch_create(
+        &self,
+        body: &M::BatchCreateInput,
+    ) -> Result<M::BatchResponse, M::Error> {
+        let req = self.request(http::Method::POST, "batch/create").json(body);
+        self.transport.send(req).await
+    }
+
+    /// Update a batch of objects by ID or by a unique property.
+    #[tracing::instrument]
+    pub async fn batch_update(
+        &self,
+        body: &M::BatchUpdateInput,
+    ) -> Result<M::BatchResponse, M::Error> {
+        let req = self.request(http::Method::POST, "batch/update").json(body);
+        self.transport.send(req).await
+    }
+
+    /// Create or update a batch of objects by a unique property.
+    #[tracing::instrument]
+    pub async fn batch_upsert(
+        &self,
+        body: &M::BatchUpsertInput,
+    ) -> Result<M::BatchUpsertResponse, M::Error> {
+        let req = self.request(http::Method::POST, "batch/upsert").json(body);
+        self.transport.send(req).await
+    }
+
+    /// Move a batch of objects to the recycling bin.
+    #[tracing::instrument]
+    pub async fn batch_archive(&self, body: &M::BatchArchiveInput) -> Result<(), M::Error> {
+        let req = self.request(http::Method::POST, "batch/archive").json(body);
+        self.transport.execute(req).await
+    }
+
+    /// Search objects by their property values.
+    #[tracing::instrument]
+    pub async fn search(&self, body: &M::SearchRequest) -> Result<M::SearchPage, M::Error> {
+        let req = self.request(http::Method::POST, "search").json(body);
+        self.transport.send(req).await
+    }
+}
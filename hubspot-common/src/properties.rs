@@ -0,0 +1,608 @@
+//! Property definitions and local validation of property values.
+//!
+//! HubSpot only rejects unknown property names and bad enumeration values when
+//! a request reaches it. A [`PropertyCache`] fetches the property definitions of
+//! an object type once, keeps them for every clone of the cache, and checks
+//! create, update and upsert inputs and search requests against them.
+//!
+//! Validation is opt-in: neither the generated methods nor
+//! [`Objects`](crate::objects::Objects) check properties, so call
+//! [`PropertyCache::validate`] or [`PropertyCache::validate_search`] before
+//! sending a request. The problems found are returned as a list of
+//! [`PropertyError`]s in [`ValidationError::InvalidProperties`].
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    marker::PhantomData,
+    sync::Arc,
+};
+
+use crate::{
+    objects::ObjectType,
+    transport::{ClientError, Transport},
+};
+
+/// An option of an enumeration property.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct PropertyOption {
+    pub label: String,
+    pub value: String,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(
+        rename = "displayOrder",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub display_order: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Which parts of a property can be changed.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct PropertyModificationMetadata {
+    #[serde(rename = "readOnlyValue")]
+    pub read_only_value: bool,
+    #[serde(rename = "readOnlyDefinition", default)]
+    pub read_only_definition: bool,
+    #[serde(default)]
+    pub archivable: bool,
+}
+
+/// The definition of a property.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct Property {
+    pub name: String,
+    pub label: String,
+    /// The data type: `string`, `number`, `date`, `datetime`, `enumeration`,
+    /// `bool` or `phone_number`.
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// How the property is shown in forms, e.g. `text`, `select` or `checkbox`.
+    #[serde(rename = "fieldType")]
+    pub field_type: String,
+    #[serde(rename = "groupName", default)]
+    pub group_name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub options: Vec<PropertyOption>,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub calculated: bool,
+    #[serde(rename = "hasUniqueValue", default)]
+    pub has_unique_value: bool,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(
+        rename = "modificationMetadata",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub modification_metadata: Option<PropertyModificationMetadata>,
+}
+
+impl Property {
+    /// Whether the value of the property can be written.
+    pub fn is_read_only(&self) -> bool {
+        self.calculated
+            || self
+                .modification_metadata
+                .as_ref()
+                .map(|m| m.read_only_value)
+                .unwrap_or(false)
+    }
+
+    /// Whether the property holds several `;` separated options.
+    pub fn is_multi_select(&self) -> bool {
+        self.type_ == "enumeration" && self.field_type == "checkbox"
+    }
+
+    /// Check a value against the type of the property.
+    pub fn check_value(&self, value: &str) -> Result<(), PropertyError> {
+        // An empty string clears the property.
+        if value.is_empty() {
+            return Ok(());
+        }
+
+        let invalid = |expected: &str| PropertyError::InvalidValue {
+            property: self.name.clone(),
+            value: value.to_string(),
+            expected: expected.to_string(),
+        };
+
+        match self.type_.as_str() {
+            "enumeration" => {
+                let values: Vec<&str> = if self.is_multi_select() {
+                    value.split(';').collect()
+                } else {
+                    vec![value]
+                };
+                for v in values {
+                    if !self.options.iter().any(|o| o.value == v) {
+                        return Err(PropertyError::InvalidOption {
+                            property: self.name.clone(),
+                            value: v.to_string(),
+                            allowed: self.options.iter().map(|o| o.value.clone()).collect(),
+                        });
+                    }
+                }
+                Ok(())
+            }
+            "number" => value
+                .parse::<f64>()
+                .map(|_| ())
+                .map_err(|_| invalid("a number")),
+            "bool" => match value {
+                "true" | "false" => Ok(()),
+                _ => Err(invalid("`true` or `false`")),
+            },
+            "date" => {
+                if value.parse::<i64>().is_ok()
+                    || chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok()
+                {
+                    Ok(())
+                } else {
+                    Err(invalid("a `YYYY-MM-DD` date or epoch milliseconds"))
+                }
+            }
+            "datetime" => {
+                if value.parse::<i64>().is_ok()
+                    || chrono::DateTime::parse_from_rfc3339(value).is_ok()
+                {
+                    Ok(())
+                } else {
+                    Err(invalid("an RFC 3339 timestamp or epoch milliseconds"))
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct CollectionResponseProperty {
+    pub results: Vec<Property>,
+}
+
+/// A problem with a property name or value found before sending a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PropertyError {
+    /// The object type has no property with that name.
+    UnknownProperty {
+        property: String,
+        /// The closest existing property name, if one is close enough.
+        suggestion: Option<String>,
+    },
+    /// The property is calculated or otherwise read-only.
+    ReadOnly { property: String },
+    /// The value is not one of the options of an enumeration property.
+    InvalidOption {
+        property: String,
+        value: String,
+        allowed: Vec<String>,
+    },
+    /// The value does not match the type of the property.
+    InvalidValue {
+        property: String,
+        value: String,
+        expected: String,
+    },
+}
+
+impl std::fmt::Display for PropertyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PropertyError::UnknownProperty {
+                property,
+                suggestion: Some(suggestion),
+            } => write!(
+                f,
+                "unknown property `{}`, did you mean `{}`?",
+                property, suggestion
+            ),
+            PropertyError::UnknownProperty {
+                property,
+                suggestion: None,
+            } => write!(f, "unknown property `{}`", property),
+            PropertyError::ReadOnly { property } => {
+                write!(f, "property `{}` is read-only", property)
+            }
+            PropertyError::InvalidOption {
+                property,
+                value,
+                allowed,
+            } => write!(
+                f,
+                "`{}` is not an option of property `{}`, expected one of: {}",
+                value,
+                property,
+                allowed.join(", ")
+            ),
+            PropertyError::InvalidValue {
+                property,
+                value,
+                expected,
+            } => write!(
+                f,
+                "`{}` is not a valid value for property `{}`, expected {}",
+                value, property, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PropertyError {}
+
+/// The property definitions of one object type, by name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertySchema {
+    pub object_type: ObjectType,
+    pub properties: BTreeMap<String, Property>,
+}
+
+impl PropertySchema {
+    /// Build a schema from the property definitions of an object type.
+    pub fn new(object_type: ObjectType, properties: Vec<Property>) -> Self {
+        PropertySchema {
+            object_type,
+            properties: properties
+                .into_iter()
+                .map(|p| (p.name.clone(), p))
+                .collect(),
+        }
+    }
+
+    /// Look a property up by name, or explain why it does not exist.
+    pub fn property(&self, name: &str) -> Result<&Property, PropertyError> {
+        self.properties
+            .get(name)
+            .ok_or_else(|| PropertyError::UnknownProperty {
+                property: name.to_string(),
+                suggestion: self.suggest(name),
+            })
+    }
+
+    /// Check the property values of an object to be written.
+    pub fn check_properties(&self, properties: &HashMap<String, String>) -> Vec<PropertyError> {
+        let mut errors = vec![];
+        let mut names: Vec<&String> = properties.keys().collect();
+        names.sort();
+        for name in names {
+            let property = match self.property(name) {
+                Ok(p) => p,
+                Err(e) => {
+                    errors.push(e);
+                    continue;
+                }
+            };
+            if property.is_read_only() {
+                errors.push(PropertyError::ReadOnly {
+                    property: name.clone(),
+                });
+                continue;
+            }
+            if let Err(e) = property.check_value(&properties[name]) {
+                errors.push(e);
+            }
+        }
+        errors
+    }
+
+    /// Check the property names, filter values and sorts of a search request.
+    pub fn check_search<S>(&self, request: &S) -> Vec<PropertyError>
+    where
+        S: SearchInputs + ?Sized,
+    {
+        let mut errors = vec![];
+        let mut push = |e: PropertyError| {
+            if !errors.contains(&e) {
+                errors.push(e);
+            }
+        };
+        for name in request.returned_properties() {
+            if let Err(e) = self.property(name) {
+                push(e);
+            }
+        }
+
+        for filter in request.filters() {
+            let property = match self.property(filter.property_name) {
+                Ok(p) => p,
+                Err(e) => {
+                    push(e);
+                    continue;
+                }
+            };
+            // Token and range filters match partial values, so only exact
+            // comparisons are checked against the enumeration options.
+            if !filter.exact {
+                continue;
+            }
+            for value in filter.values {
+                if let Err(e) = property.check_value(value) {
+                    push(e);
+                }
+            }
+        }
+
+        for sort in request.sorts() {
+            // A leading `-` sorts in descending order.
+            if let Err(e) = self.property(sort.strip_prefix('-').unwrap_or(sort)) {
+                push(e);
+            }
+        }
+        errors
+    }
+
+    fn suggest(&self, name: &str) -> Option<String> {
+        let lower = name.to_lowercase();
+        self.properties
+            .keys()
+            .map(|candidate| (edit_distance(&lower, candidate), candidate))
+            .filter(|(distance, _)| *distance <= 2.max(name.len() / 4))
+            .min()
+            .map(|(_, candidate)| candidate.clone())
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current.push((prev[j] + cost).min(prev[j + 1] + 1).min(current[j] + 1));
+        }
+        prev = current;
+    }
+    prev[b.len()]
+}
+
+/// Request bodies whose property values can be validated.
+pub trait PropertyInputs {
+    /// The property maps of every object in the request.
+    fn property_maps(&self) -> Vec<&HashMap<String, String>>;
+}
+
+/// A filter of a search request, as far as validation needs it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchFilter<'a> {
+    pub property_name: &'a str,
+    /// Whether the values must equal a property value, as for `EQ`, `NEQ`,
+    /// `IN` and `NOT_IN`.
+    pub exact: bool,
+    pub values: Vec<&'a str>,
+}
+
+/// Search requests whose property names, filters and sorts can be validated.
+pub trait SearchInputs {
+    /// The names of the properties to return.
+    fn returned_properties(&self) -> Vec<&str>;
+    /// The filters of every filter group.
+    fn filters(&self) -> Vec<SearchFilter<'_>>;
+    /// The properties to sort by.
+    fn sorts(&self) -> Vec<&str>;
+}
+
+/// Why a request could not be validated.
+#[derive(Debug)]
+pub enum ValidationError<E> {
+    /// The property definitions could not be fetched.
+    Request(E),
+    /// Properties that do not match their definitions.
+    InvalidProperties(Vec<PropertyError>),
+}
+
+impl<E> ValidationError<E> {
+    /// The problems found with the properties, if the definitions were fetched.
+    pub fn invalid_properties(&self) -> Option<&[PropertyError]> {
+        match self {
+            ValidationError::Request(_) => None,
+            ValidationError::InvalidProperties(errors) => Some(errors),
+        }
+    }
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for ValidationError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::Request(e) => write!(f, "fetching property definitions: {}", e),
+            ValidationError::InvalidProperties(errors) => {
+                write!(f, "{}", itertools::join(errors, "; "))
+            }
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for ValidationError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ValidationError::Request(e) => Some(e),
+            ValidationError::InvalidProperties(_) => None,
+        }
+    }
+}
+
+/// Property definitions.
+pub struct Properties<E> {
+    transport: Transport,
+    error: PhantomData<fn() -> E>,
+}
+
+impl<E> Clone for Properties<E> {
+    fn clone(&self) -> Self {
+        Properties {
+            transport: self.transport.clone(),
+            error: PhantomData,
+        }
+    }
+}
+
+impl<E> std::fmt::Debug for Properties<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Properties")
+            .field("transport", &self.transport)
+            .finish()
+    }
+}
+
+impl<E: ClientError> Properties<E> {
+    #[doc(hidden)]
+    pub fn new(transport: Transport) -> Self {
+        Properties {
+            transport,
+            error: PhantomData,
+        }
+    }
+
+    /// Read all properties of an object type.
+    #[tracing::instrument]
+    pub async fn get_all(
+        &self,
+        object_type: ObjectType,
+        archived: Option<bool>,
+    ) -> Result<CollectionResponseProperty, E> {
+        let mut req = self.transport.request(
+            http::Method::GET,
+            &format!("crm/v3/properties/{}", object_type),
+        );
+        if let Some(p) = archived {
+            req = req.query(&[("archived", format!("{}", p))]);
+        }
+        self.transport.send(req).await
+    }
+
+    /// Read a property of an object type by name.
+    #[tracing::instrument]
+    pub async fn get_by_name(
+        &self,
+        object_type: ObjectType,
+        property_name: &str,
+    ) -> Result<Property, E> {
+        let req = self.transport.request(
+            http::Method::GET,
+            &format!("crm/v3/properties/{}/{}", object_type, property_name),
+        );
+        self.transport.send(req).await
+    }
+}
+
+/// Property definitions fetched once per object type and shared by every clone
+/// of the cache.
+pub struct PropertyCache<E> {
+    properties: Properties<E>,
+    schemas: Arc<tokio::sync::RwLock<HashMap<ObjectType, Arc<PropertySchema>>>>,
+}
+
+impl<E> Clone for PropertyCache<E> {
+    fn clone(&self) -> Self {
+        PropertyCache {
+            properties: self.properties.clone(),
+            schemas: self.schemas.clone(),
+        }
+    }
+}
+
+impl<E> std::fmt::Debug for PropertyCache<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PropertyCache")
+            .field("properties", &self.properties)
+            .field("schemas", &self.schemas)
+            .finish()
+    }
+}
+
+impl<E: ClientError> PropertyCache<E> {
+    /// Create an empty cache that fetches definitions through `properties`.
+    pub fn new(properties: Properties<E>) -> Self {
+        PropertyCache {
+            properties,
+            schemas: Default::default(),
+        }
+    }
+
+    /// The property definitions of an object type, fetched on first use.
+    pub async fn schema(&self, object_type: &ObjectType) -> Result<Arc<PropertySchema>, E> {
+        if let Some(schema) = self.schemas.read().await.get(object_type) {
+            return Ok(schema.clone());
+        }
+
+        let properties = self
+            .properties
+            .get_all(object_type.clone(), None)
+            .await?
+            .results;
+        let schema = Arc::new(PropertySchema::new(object_type.clone(), properties));
+        self.schemas
+            .write()
+            .await
+            .insert(object_type.clone(), schema.clone());
+        Ok(schema)
+    }
+
+    /// Drop the cached definitions of an object type, e.g. after a property was
+    /// created or its options changed.
+    pub async fn invalidate(&self, object_type: &ObjectType) {
+        self.schemas.write().await.remove(object_type);
+    }
+
+    /// Check the property values of a create, update or upsert request.
+    pub async fn validate<I>(
+        &self,
+        object_type: &ObjectType,
+        input: &I,
+    ) -> Result<(), ValidationError<E>>
+    where
+        I: PropertyInputs + ?Sized,
+    {
+        let schema = self
+            .schema(object_type)
+            .await
+            .map_err(ValidationError::Request)?;
+        let mut errors = vec![];
+        for properties in input.property_maps() {
+            for e in schema.check_properties(properties) {
+                if !errors.contains(&e) {
+                    errors.push(e);
+                }
+            }
+        }
+        into_result(errors)
+    }
+
+    /// Check the property names, filter values and sorts of a search request.
+    pub async fn validate_search<S>(
+        &self,
+        object_type: &ObjectType,
+        request: &S,
+    ) -> Result<(), ValidationError<E>>
+    where
+        S: SearchInputs + ?Sized,
+    {
+        let schema = self
+            .schema(object_type)
+            .await
+            .map_err(ValidationError::Request)?;
+        into_result(schema.check_search(request))
+    }
+}
+
+fn into_result<E>(errors: Vec<PropertyError>) -> Result<(), ValidationError<E>> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError::InvalidProperties(errors))
+    }
+}
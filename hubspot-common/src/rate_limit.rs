@@ -0,0 +1,389 @@
+//! Rate limit aware request scheduling.
+//!
+//! HubSpot enforces a rolling per-app limit and a per-second limit, and reports
+//! usage on every response through the `X-HubSpot-RateLimit-*` headers. A
+//! [`RateLimiter`] keeps a token bucket for each of those limits, resyncs them
+//! from the headers, waits before a request would exceed either one, and honors
+//! `Retry-After` (or the reported policy) when HubSpot answers with
+//! `429 Too Many Requests`. Once it gives up, the request fails with a
+//! [`RateLimitExceeded`](crate::errors::RateLimitExceeded).
+//!
+//! A limiter is installed with the client's `with_rate_limiter`, inside its
+//! retry middleware so the retries never see a `429`. Every clone of the
+//! client shares it, including the handles returned by `basic()`, `batch()`
+//! and `search()`.
+//!
+//! ```rust,no_run
+//! use hubspot_common::rate_limit::{RateLimitConfig, RateLimiter};
+//!
+//! // Keep a fifth of each limit free for other processes using the same app.
+//! let limiter = RateLimiter::new(RateLimitConfig {
+//!     headroom: 0.2,
+//!     ..Default::default()
+//! });
+//! ```
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::errors::{RateLimitErrorBody, RateLimitExceeded};
+
+const HEADER_MAX: &str = "x-hubspot-ratelimit-max";
+const HEADER_REMAINING: &str = "x-hubspot-ratelimit-remaining";
+const HEADER_INTERVAL: &str = "x-hubspot-ratelimit-interval-milliseconds";
+const HEADER_SECONDLY: &str = "x-hubspot-ratelimit-secondly";
+const HEADER_SECONDLY_REMAINING: &str = "x-hubspot-ratelimit-secondly-remaining";
+const HEADER_DAILY: &str = "x-hubspot-ratelimit-daily";
+const HEADER_DAILY_REMAINING: &str = "x-hubspot-ratelimit-daily-remaining";
+
+/// Configuration for a [`RateLimiter`].
+///
+/// The limits are only used until HubSpot reports the real ones in the
+/// response headers.
+#[derive(Clone, Debug)]
+pub struct RateLimitConfig {
+    /// Requests allowed per rolling interval.
+    pub max_per_interval: u32,
+    /// Length of the rolling interval.
+    pub interval: Duration,
+    /// Requests allowed per second.
+    pub max_per_second: u32,
+    /// Fraction of each limit, between `0.0` and `1.0`, that is never used.
+    /// Leaves room for other processes sharing the same app. Values outside
+    /// the range are clamped, and at least one request is always allowed per
+    /// refill, so a headroom of `1.0` slows requests down rather than
+    /// stopping them.
+    pub headroom: f64,
+    /// How many times a request answered with `429` is sent again after waiting.
+    pub max_retries: u32,
+    /// Wait used when a `429` carries neither `Retry-After` nor a known policy name.
+    pub default_retry_after: Duration,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            max_per_interval: 100,
+            interval: Duration::from_secs(10),
+            max_per_second: 10,
+            headroom: 0.0,
+            max_retries: 3,
+            default_retry_after: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Usage most recently reported by HubSpot through the `X-HubSpot-RateLimit-*` headers.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RateLimitUsage {
+    /// Requests allowed per rolling interval.
+    pub max: Option<u32>,
+    /// Requests left in the current rolling interval.
+    pub remaining: Option<u32>,
+    /// Length of the rolling interval.
+    pub interval: Option<Duration>,
+    /// Requests allowed per second.
+    pub secondly: Option<u32>,
+    /// Requests left in the current second.
+    pub secondly_remaining: Option<u32>,
+    /// Requests allowed per day.
+    pub daily: Option<u32>,
+    /// Requests left today.
+    pub daily_remaining: Option<u32>,
+}
+
+impl RateLimitUsage {
+    /// Read the usage from the headers of a response.
+    pub fn from_headers(headers: &reqwest::header::HeaderMap) -> Self {
+        let get = |name: &str| -> Option<u64> {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse().ok())
+        };
+        let get_u32 = |name: &str| get(name).and_then(|v| u32::try_from(v).ok());
+
+        RateLimitUsage {
+            max: get_u32(HEADER_MAX),
+            remaining: get_u32(HEADER_REMAINING),
+            interval: get(HEADER_INTERVAL).map(Duration::from_millis),
+            secondly: get_u32(HEADER_SECONDLY),
+            secondly_remaining: get_u32(HEADER_SECONDLY_REMAINING),
+            daily: get_u32(HEADER_DAILY),
+            daily_remaining: get_u32(HEADER_DAILY_REMAINING),
+        }
+    }
+
+    fn merge(&mut self, other: RateLimitUsage) {
+        macro_rules! take {
+            ($($field:ident),*) => {
+                $(if other.$field.is_some() {
+                    self.$field = other.$field;
+                })*
+            };
+        }
+        take!(
+            max,
+            remaining,
+            interval,
+            secondly,
+            secondly_remaining,
+            daily,
+            daily_remaining
+        );
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Bucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(capacity: u32, interval: Duration, now: Instant) -> Self {
+        let capacity = f64::from(capacity.max(1));
+        Bucket {
+            capacity,
+            tokens: capacity,
+            refill_per_sec: capacity / interval.as_secs_f64().max(0.001),
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.updated = now;
+    }
+
+    fn resize(&mut self, capacity: u32, interval: Duration) {
+        self.capacity = f64::from(capacity.max(1));
+        self.refill_per_sec = self.capacity / interval.as_secs_f64().max(0.001);
+        self.tokens = self.tokens.min(self.capacity);
+    }
+
+    fn wait_time(&self, headroom: f64) -> Duration {
+        // A bucket never holds more than `capacity` tokens, so asking for more
+        // would wait forever.
+        let needed = (1.0 + self.capacity * headroom).min(self.capacity);
+        if self.tokens >= needed {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((needed - self.tokens) / self.refill_per_sec)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    interval: Bucket,
+    secondly: Bucket,
+    blocked_until: Option<Instant>,
+    usage: RateLimitUsage,
+}
+
+impl State {
+    /// Take a token from both buckets, or return how long to wait before trying again.
+    fn try_reserve(&mut self, now: Instant, headroom: f64) -> Option<Duration> {
+        if let Some(until) = self.blocked_until {
+            if until > now {
+                return Some(until - now);
+            }
+            self.blocked_until = None;
+        }
+
+        self.interval.refill(now);
+        self.secondly.refill(now);
+        let wait = self
+            .interval
+            .wait_time(headroom)
+            .max(self.secondly.wait_time(headroom));
+        if !wait.is_zero() {
+            return Some(wait);
+        }
+
+        self.interval.tokens -= 1.0;
+        self.secondly.tokens -= 1.0;
+        None
+    }
+
+    fn observe(&mut self, usage: RateLimitUsage, now: Instant) {
+        self.interval.refill(now);
+        self.secondly.refill(now);
+
+        if let Some(max) = usage.max {
+            let interval = usage.interval.unwrap_or_else(|| {
+                Duration::from_secs_f64(self.interval.capacity / self.interval.refill_per_sec)
+            });
+            self.interval.resize(max, interval);
+        }
+        if let Some(remaining) = usage.remaining {
+            self.interval.tokens = self.interval.tokens.min(f64::from(remaining));
+        }
+        if let Some(secondly) = usage.secondly {
+            self.secondly.resize(secondly, Duration::from_secs(1));
+        }
+        if let Some(remaining) = usage.secondly_remaining {
+            self.secondly.tokens = self.secondly.tokens.min(f64::from(remaining));
+        }
+
+        self.usage.merge(usage);
+    }
+
+    fn block_for(&mut self, wait: Duration, now: Instant) {
+        let until = now + wait;
+        if self.blocked_until.is_none_or(|current| current < until) {
+            self.blocked_until = Some(until);
+        }
+    }
+}
+
+/// A token-bucket scheduler for HubSpot requests.
+///
+/// Cloning a `RateLimiter` is cheap and the clones share their buckets, so one
+/// limiter can be installed on several clients that use the same app.
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    state: Arc<Mutex<State>>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimitConfig::default())
+    }
+}
+
+impl RateLimiter {
+    /// Create a new rate limiter.
+    pub fn new(config: RateLimitConfig) -> Self {
+        let now = Instant::now();
+        let state = State {
+            interval: Bucket::new(config.max_per_interval, config.interval, now),
+            secondly: Bucket::new(config.max_per_second, Duration::from_secs(1), now),
+            blocked_until: None,
+            usage: RateLimitUsage::default(),
+        };
+        RateLimiter {
+            config,
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// The usage most recently reported by HubSpot.
+    pub fn usage(&self) -> RateLimitUsage {
+        self.lock().usage.clone()
+    }
+
+    /// Wait until a request can be sent without exceeding either limit and
+    /// take a token for it.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = self
+                .lock()
+                .try_reserve(Instant::now(), self.config.headroom.clamp(0.0, 1.0));
+            match wait {
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => return,
+            }
+        }
+    }
+
+    /// Update the buckets from the headers of a response.
+    pub fn observe(&self, headers: &reqwest::header::HeaderMap) {
+        self.lock()
+            .observe(RateLimitUsage::from_headers(headers), Instant::now());
+    }
+
+    /// Record a `429` response and return how long to wait before sending again.
+    ///
+    /// Returns `None` when the daily limit was hit, since retrying before it
+    /// resets is pointless.
+    pub fn throttled(
+        &self,
+        headers: &reqwest::header::HeaderMap,
+        body: &RateLimitErrorBody,
+    ) -> Option<Duration> {
+        let retry_after = headers
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+
+        let now = Instant::now();
+        let mut state = self.lock();
+        state.observe(RateLimitUsage::from_headers(headers), now);
+
+        let wait = match body.policy_name.as_deref() {
+            Some("DAILY") => return None,
+            Some("SECONDLY") => {
+                state.secondly.tokens = 0.0;
+                retry_after.unwrap_or(Duration::from_secs(1))
+            }
+            Some("TEN_SECONDLY_ROLLING") => {
+                state.interval.tokens = 0.0;
+                retry_after.unwrap_or_else(|| {
+                    Duration::from_secs_f64(state.interval.capacity / state.interval.refill_per_sec)
+                })
+            }
+            _ => retry_after.unwrap_or(self.config.default_retry_after),
+        };
+        state.block_for(wait, now);
+        Some(wait)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        // The state is always left consistent, so a poisoned lock is still usable.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait::async_trait]
+impl reqwest_middleware::Middleware for RateLimiter {
+    async fn handle(
+        &self,
+        req: reqwest::Request,
+        extensions: &mut http::Extensions,
+        next: reqwest_middleware::Next<'_>,
+    ) -> reqwest_middleware::Result<reqwest::Response> {
+        let mut req = req;
+        let mut attempt = 0;
+        loop {
+            self.acquire().await;
+
+            let retry = if attempt < self.config.max_retries {
+                req.try_clone()
+            } else {
+                None
+            };
+            let resp = next.clone().run(req, extensions).await?;
+            if resp.status() != reqwest::StatusCode::TOO_MANY_REQUESTS {
+                self.observe(resp.headers());
+                return Ok(resp);
+            }
+
+            let headers = resp.headers().clone();
+            let body: RateLimitErrorBody = resp.json().await.unwrap_or_default();
+            let wait = self.throttled(&headers, &body);
+
+            match (wait, retry) {
+                (Some(wait), Some(retry)) => {
+                    tracing::debug!(?wait, policy = ?body.policy_name, "hubspot rate limit hit, waiting");
+                    attempt += 1;
+                    req = retry;
+                }
+                _ => {
+                    return Err(reqwest_middleware::Error::middleware(RateLimitExceeded {
+                        wait,
+                        body,
+                    }))
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! A local HTTP server answering requests in place of HubSpot, for the tests
+//! of the clients.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+/// A request received by a [`StandIn`].
+#[derive(Clone, Debug)]
+pub struct StandInRequest {
+    pub method: String,
+    /// The path and query.
+    pub path: String,
+    /// The request line and headers, lowercased.
+    pub head: String,
+    pub body: Vec<u8>,
+}
+
+impl StandInRequest {
+    /// The body parsed as JSON.
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    responses: VecDeque<(u16, String, String)>,
+    requests: Vec<StandInRequest>,
+}
+
+/// Answers every request with the next queued response, and `404` once they
+/// run out.
+#[derive(Clone, Debug, Default)]
+pub struct StandIn {
+    state: Arc<Mutex<State>>,
+}
+
+impl StandIn {
+    /// Create a stand-in answering with `responses`, each a status, extra
+    /// header lines (each ending in `\r\n`) and a body.
+    pub fn new<'a, I>(responses: I) -> Self
+    where
+        I: IntoIterator<Item = (u16, &'a str, &'a str)>,
+    {
+        let stand_in = StandIn::default();
+        for (status, headers, body) in responses {
+            stand_in.push(status, headers, body);
+        }
+        stand_in
+    }
+
+    /// Queue another response.
+    pub fn push(&self, status: u16, headers: &str, body: &str) {
+        self.lock()
+            .responses
+            .push_back((status, headers.to_string(), body.to_string()));
+    }
+
+    /// The requests received so far.
+    pub fn requests(&self) -> Vec<StandInRequest> {
+        self.lock().requests.clone()
+    }
+
+    /// The method and path of the requests received so far.
+    pub fn paths(&self) -> Vec<(String, String)> {
+        self.lock()
+            .requests
+            .iter()
+            .map(|r| (r.method.clone(), r.path.clone()))
+            .collect()
+    }
+
+    /// Serve on a local port and return the base URL.
+    pub async fn serve(&self) -> String {
+        use tokio::io::AsyncWriteExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let stand_in = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let stand_in = stand_in.clone();
+                tokio::spawn(async move {
+                    let mut buf = vec![];
+                    let header_end = loop {
+                        if read_more(&mut socket, &mut buf).await == 0 {
+                            return;
+                        }
+                        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                            break i + 4;
+                        }
+                    };
+                    let head = String::from_utf8_lossy(&buf[..header_end]).to_lowercase();
+                    let header = |name: &str| {
+                        head.lines().find_map(|l| {
+                            let (n, v) = l.split_once(':')?;
+                            (n.trim() == name).then(|| v.trim().to_string())
+                        })
+                    };
+
+                    let body = if header("transfer-encoding").as_deref() == Some("chunked") {
+                        let mut body = vec![];
+                        let mut pos = header_end;
+                        loop {
+                            let line_end = loop {
+                                if let Some(i) = buf[pos..].windows(2).position(|w| w == b"\r\n") {
+                                    break pos + i;
+                                }
+                                read_more(&mut socket, &mut buf).await;
+                            };
+                            let size = usize::from_str_radix(
+                                String::from_utf8_lossy(&buf[pos..line_end]).trim(),
+                                16,
+                            )
+                            .unwrap();
+                            let data_end = line_end + 2 + size;
+                            while buf.len() < data_end + 2 {
+                                read_more(&mut socket, &mut buf).await;
+                            }
+                            body.extend_from_slice(&buf[line_end + 2..data_end]);
+                            pos = data_end + 2;
+                            if size == 0 {
+                                break body;
+                            }
+                        }
+                    } else {
+                        let content_length: usize = header("content-length")
+                            .map(|v| v.parse().unwrap())
+                            .unwrap_or(0);
+                        while buf.len() < header_end + content_length {
+                            read_more(&mut socket, &mut buf).await;
+                        }
+                        buf[header_end..header_end + content_length].to_vec()
+                    };
+
+                    let original = String::from_utf8_lossy(&buf[..header_end]).to_string();
+                    let mut request_line = original.split_whitespace();
+                    let request = StandInRequest {
+                        method: request_line.next().unwrap().to_string(),
+                        path: request_line.next().unwrap().to_string(),
+                        head,
+                        body,
+                    };
+                    let (status, headers, body) = {
+                        let mut state = stand_in.lock();
+                        state.requests.push(request);
+                        state
+                            .responses
+                            .pop_front()
+                            .unwrap_or((404, String::new(), String::new()))
+                    };
+                    let response = format!(
+                        "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        headers,
+                        body
+                    );
+                    socket.write_all(response.as_bytes()).await.unwrap();
+                });
+            }
+        });
+        base_url
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Read more of a request into `buf`, returning how many bytes were read.
+async fn read_more(socket: &mut tokio::net::TcpStream, buf: &mut Vec<u8>) -> usize {
+    use tokio::io::AsyncReadExt;
+
+    let mut chunk = [0; 4096];
+    let n = socket.read(&mut chunk).await.unwrap();
+    buf.extend_from_slice(&chunk[..n]);
+    n
+}
@@ -0,0 +1,480 @@
+use pretty_assertions::assert_eq;
+
+#[test]
+fn test_rate_limit_usage_from_headers() {
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("X-HubSpot-RateLimit-Max", "190".parse().unwrap());
+    headers.insert("X-HubSpot-RateLimit-Remaining", "12".parse().unwrap());
+    headers.insert(
+        "X-HubSpot-RateLimit-Interval-Milliseconds",
+        "10000".parse().unwrap(),
+    );
+    headers.insert(
+        "X-HubSpot-RateLimit-Daily-Remaining",
+        "bogus".parse().unwrap(),
+    );
+
+    let usage = crate::rate_limit::RateLimitUsage::from_headers(&headers);
+    assert_eq!(
+        usage,
+        crate::rate_limit::RateLimitUsage {
+            max: Some(190),
+            remaining: Some(12),
+            interval: Some(std::time::Duration::from_secs(10)),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn test_rate_limit_throttled_honors_retry_after_and_policy() {
+    let limiter = crate::rate_limit::RateLimiter::default();
+
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(reqwest::header::RETRY_AFTER, "3".parse().unwrap());
+    let body: crate::errors::RateLimitErrorBody = serde_json::from_str(
+        r#"{"status":"error","message":"You have reached your secondly limit.","errorType":"RATE_LIMIT","policyName":"SECONDLY"}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        limiter.throttled(&headers, &body),
+        Some(std::time::Duration::from_secs(3))
+    );
+
+    let daily = crate::errors::RateLimitErrorBody {
+        policy_name: Some("DAILY".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(limiter.throttled(&headers, &daily), None);
+}
+
+#[tokio::test]
+async fn test_rate_limit_acquire_waits_for_refill() {
+    let limiter = crate::rate_limit::RateLimiter::new(crate::rate_limit::RateLimitConfig {
+        max_per_interval: 2,
+        interval: std::time::Duration::from_millis(200),
+        ..Default::default()
+    });
+
+    let start = std::time::Instant::now();
+    for _ in 0..3 {
+        limiter.acquire().await;
+    }
+    assert!(start.elapsed() >= std::time::Duration::from_millis(90));
+}
+
+#[tokio::test]
+async fn test_rate_limit_full_headroom_still_allows_requests() {
+    let limiter = crate::rate_limit::RateLimiter::new(crate::rate_limit::RateLimitConfig {
+        max_per_interval: 2,
+        interval: std::time::Duration::from_millis(100),
+        max_per_second: 100,
+        headroom: 5.0,
+        ..Default::default()
+    });
+
+    tokio::time::timeout(std::time::Duration::from_secs(2), async {
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+    })
+    .await
+    .expect("acquire never returned");
+}
+
+#[tokio::test]
+async fn test_rate_limit_middleware_retries_429_and_gives_up_with_error() {
+    use crate::{
+        errors::RateLimitExceeded,
+        rate_limit::{RateLimitConfig, RateLimiter},
+        test_util::StandIn,
+    };
+
+    let too_many = |policy: &str| {
+        format!(
+            r#"{{"status":"error","message":"You have reached your limit.","errorType":"RATE_LIMIT","policyName":"{}"}}"#,
+            policy
+        )
+    };
+    let secondly = too_many("SECONDLY");
+    let daily = too_many("DAILY");
+    let stand_in = StandIn::new([
+        (429, "Retry-After: 0\r\n", secondly.as_str()),
+        (200, "X-HubSpot-RateLimit-Max: 190\r\n", "{}"),
+        (429, "", daily.as_str()),
+    ]);
+    let base_url = stand_in.serve().await;
+
+    let limiter = RateLimiter::new(RateLimitConfig {
+        max_retries: 1,
+        ..Default::default()
+    });
+    let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+        .with(limiter.clone())
+        .build();
+
+    let resp = client.get(&base_url).send().await.unwrap();
+    assert_eq!(resp.status(), reqwest::StatusCode::OK);
+    assert_eq!(limiter.usage().max, Some(190));
+    assert_eq!(stand_in.requests().len(), 2);
+
+    // The daily limit is not retried.
+    let err = client.get(&base_url).send().await.unwrap_err();
+    let rate_limited = RateLimitExceeded::from_middleware_error(&err).unwrap();
+    assert_eq!(rate_limited.wait, None);
+    assert_eq!(rate_limited.body.policy_name.as_deref(), Some("DAILY"));
+    assert_eq!(stand_in.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn test_appended_middleware_runs_inside_retries() {
+    use crate::{
+        errors::RateLimitExceeded, rate_limit::RateLimiter, test_util::StandIn,
+        transport::append_middleware,
+    };
+
+    let daily = r#"{"status":"error","message":"You have reached your daily limit.","errorType":"RATE_LIMIT","policyName":"DAILY"}"#;
+    let stand_in = StandIn::new([(429, "", daily), (429, "", daily)]);
+    let base_url = stand_in.serve().await;
+
+    // The stack the generated constructors build, retrying 429s on their own.
+    let retry_policy =
+        reqwest_retry::policies::ExponentialBackoff::builder().build_with_max_retries(3);
+    let generated = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+        .with(reqwest_retry::RetryTransientMiddleware::new_with_policy(
+            retry_policy,
+        ))
+        .build();
+    let client = append_middleware(generated, RateLimiter::default());
+
+    let err = client.get(&base_url).send().await.unwrap_err();
+    let rate_limited = RateLimitExceeded::from_middleware_error(&err).unwrap();
+    assert_eq!(rate_limited.body.policy_name.as_deref(), Some("DAILY"));
+    // The retry middleware does not send the request again.
+    assert_eq!(stand_in.requests().len(), 1);
+}
+
+/// A session whose access token expired, for an app whose token endpoint is
+/// served at `base_url`.
+fn expired_session(base_url: &str) -> crate::oauth::Session {
+    use crate::oauth::{AccessToken, OAuthApp, Session};
+
+    let mut app = OAuthApp::new("client-id", "secret", "https://example.com/callback");
+    app.set_base_url(base_url);
+    Session::new(
+        app,
+        &AccessToken {
+            access_token: "old-token".to_string(),
+            refresh_token: "refresh-1".to_string(),
+            expires_in: 0,
+            ..Default::default()
+        },
+    )
+}
+
+#[tokio::test]
+async fn test_session_refreshes_once_for_concurrent_requests() {
+    use crate::test_util::StandIn;
+
+    let stand_in = StandIn::new([
+        (
+            200,
+            "",
+            r#"{"token_type":"bearer","access_token":"new-token","refresh_token":"refresh-2","expires_in":1800}"#,
+        ),
+        (200, "", "{}"),
+        (200, "", "{}"),
+    ]);
+    let base_url = stand_in.serve().await;
+    let session = expired_session(&base_url);
+    assert_eq!(session.is_expired(), Some(true));
+
+    let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+        .with(session.clone())
+        .build();
+    let url = format!("{}/crm/v3/objects/contacts", base_url);
+    let (a, b) = tokio::join!(
+        client.get(&url).bearer_auth("static-token").send(),
+        client.get(&url).bearer_auth("static-token").send(),
+    );
+    assert!(a.unwrap().status().is_success());
+    assert!(b.unwrap().status().is_success());
+
+    let requests = stand_in.requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(
+        (requests[0].method.as_str(), requests[0].path.as_str()),
+        ("POST", "/oauth/v1/token")
+    );
+    let form = String::from_utf8(requests[0].body.clone()).unwrap();
+    assert!(form.contains("grant_type=refresh_token"));
+    assert!(form.contains("refresh_token=refresh-1"));
+    for request in &requests[1..] {
+        assert!(request.head.contains("authorization: bearer new-token"));
+    }
+
+    assert_eq!(session.refresh_token(), "refresh-2");
+    assert_eq!(session.is_expired(), Some(false));
+}
+
+#[tokio::test]
+async fn test_session_expiry() {
+    use crate::test_util::StandIn;
+
+    let stand_in = StandIn::new([(200, "", "{}")]);
+    let base_url = stand_in.serve().await;
+    let session = expired_session(&base_url);
+
+    // The tokens and the client secret never show up in logs.
+    let debug = format!("{:?}", session);
+    assert!(debug.contains("client-id"));
+    for secret in ["secret", "old-token", "refresh-1"] {
+        assert!(!debug.contains(secret), "{} leaked in {}", secret, debug);
+    }
+
+    // A minute of leeway is taken off the lifetime of the token.
+    session.set_expires_in(30);
+    assert_eq!(session.is_expired(), Some(true));
+    session.set_expires_in(3600);
+    assert_eq!(session.is_expired(), Some(false));
+    let expires_in = session.expires_in().unwrap();
+    assert!(expires_in > std::time::Duration::from_secs(3500));
+    assert!(expires_in <= std::time::Duration::from_secs(3540));
+    session.set_expires_at(None);
+    assert_eq!(session.is_expired(), None);
+
+    // Without automatic refreshing, an expired token is sent as it is.
+    session.set_expires_in(0);
+    session.set_auto_refresh(false);
+    let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+        .with(session.clone())
+        .build();
+    client.get(&base_url).send().await.unwrap();
+    let requests = stand_in.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].head.contains("authorization: bearer old-token"));
+}
+
+/// A client built like the generated ones, for [`Portals`](crate::oauth::Portals).
+#[derive(Clone, Debug)]
+struct PortalClient(reqwest_middleware::ClientWithMiddleware);
+
+impl crate::oauth::OAuthClient for PortalClient {
+    fn from_session(session: &crate::oauth::Session) -> Self {
+        let retry_policy =
+            reqwest_retry::policies::ExponentialBackoff::builder().build_with_max_retries(3);
+        let generated = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+            .with(reqwest_retry::RetryTransientMiddleware::new_with_policy(
+                retry_policy,
+            ))
+            .build();
+        PortalClient(crate::transport::append_middleware(
+            generated,
+            session.clone(),
+        ))
+    }
+}
+
+#[tokio::test]
+async fn test_portals_refresh_through_the_session() {
+    use crate::{
+        oauth::{AccessToken, OAuthApp, Portals},
+        test_util::StandIn,
+    };
+
+    let stand_in = StandIn::new([
+        (
+            200,
+            "",
+            r#"{"token_type":"bearer","access_token":"new-token","refresh_token":"refresh-2","expires_in":1800}"#,
+        ),
+        (200, "", "{}"),
+    ]);
+    let base_url = stand_in.serve().await;
+    let mut app = OAuthApp::new("client-id", "secret", "https://example.com/callback");
+    app.set_base_url(&base_url);
+    let portals = Portals::<PortalClient>::new(app);
+    portals
+        .insert(
+            7,
+            &AccessToken {
+                access_token: "old-token".to_string(),
+                refresh_token: "refresh-1".to_string(),
+                expires_in: 0,
+                ..Default::default()
+            },
+        )
+        .await;
+    assert_eq!(portals.hub_ids().await, vec![7]);
+    assert!(portals.get(8).await.unwrap().is_none());
+
+    let client = portals.get(7).await.unwrap().unwrap();
+    let session = portals.session(7).await.unwrap();
+    assert_eq!(session.refresh_token(), "refresh-2");
+    assert_eq!(session.is_expired(), Some(false));
+
+    client
+        .0
+        .get(format!("{}/crm/v3/objects/contacts", base_url))
+        .bearer_auth("old-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        stand_in.paths(),
+        vec![
+            ("POST".to_string(), "/oauth/v1/token".to_string()),
+            ("GET".to_string(), "/crm/v3/objects/contacts".to_string()),
+        ]
+    );
+    assert!(stand_in.requests()[1]
+        .head
+        .contains("authorization: bearer new-token"));
+
+    assert!(portals.remove(7).await.is_some());
+    assert!(portals.session(7).await.is_none());
+}
+
+#[test]
+fn test_association_spec_constants() {
+    use crate::associations::{AssociationCategory, AssociationSpec};
+
+    assert_eq!(
+        serde_json::to_value([
+            AssociationSpec::TICKET_TO_CONTACT,
+            AssociationSpec::CONTACT_TO_COMPANY_PRIMARY,
+            AssociationSpec::user_defined(42),
+            AssociationSpec::integrator_defined(7),
+        ])
+        .unwrap(),
+        serde_json::json!([
+            {"associationCategory": "HUBSPOT_DEFINED", "associationTypeId": 16},
+            {"associationCategory": "HUBSPOT_DEFINED", "associationTypeId": 1},
+            {"associationCategory": "USER_DEFINED", "associationTypeId": 42},
+            {"associationCategory": "INTEGRATOR_DEFINED", "associationTypeId": 7},
+        ])
+    );
+    assert_eq!(
+        AssociationSpec::COMPANY_TO_TICKET.association_category,
+        AssociationCategory::HubspotDefined
+    );
+    assert_eq!(AssociationSpec::COMPANY_TO_TICKET.association_type_id, 340);
+    assert_eq!(AssociationSpec::CONTACT_TO_CONTACT.association_type_id, 449);
+}
+
+#[test]
+fn test_association_label_and_batch_shapes() {
+    use crate::associations::{
+        AssociationInput, AssociationLabelCreate, AssociationLabelUpdate, AssociationSpec,
+        BatchInput, BatchResponse, CollectionResponseAssociationLabel,
+        CollectionResponseMultiAssociatedObjectWithLabel, FetchAssociationsInput,
+        LabelsBetweenObjectPair, MultiAssociatedObjects, PublicObjectId, Status,
+    };
+
+    assert_eq!(
+        serde_json::to_value(AssociationLabelCreate {
+            name: "manager".to_string(),
+            label: "Manager".to_string(),
+            inverse_label: Some("Report".to_string()),
+        })
+        .unwrap(),
+        serde_json::json!({"name": "manager", "label": "Manager", "inverseLabel": "Report"})
+    );
+    assert_eq!(
+        serde_json::to_value(AssociationLabelUpdate {
+            association_type_id: 12,
+            label: "Boss".to_string(),
+            inverse_label: None,
+        })
+        .unwrap(),
+        serde_json::json!({"associationTypeId": 12, "label": "Boss"})
+    );
+
+    let labels: CollectionResponseAssociationLabel = serde_json::from_str(
+        r#"{"results": [
+            {"category": "HUBSPOT_DEFINED", "typeId": 1, "label": "Primary"},
+            {"category": "USER_DEFINED", "typeId": 12}
+        ]}"#,
+    )
+    .unwrap();
+    assert_eq!(labels.results[0].label.as_deref(), Some("Primary"));
+    assert_eq!(labels.results[1].label, None);
+
+    assert_eq!(
+        serde_json::to_value(BatchInput {
+            inputs: vec![AssociationInput {
+                from: PublicObjectId {
+                    id: "1".to_string(),
+                },
+                to: PublicObjectId {
+                    id: "2".to_string(),
+                },
+                types: vec![AssociationSpec::user_defined(12)],
+            }],
+        })
+        .unwrap(),
+        serde_json::json!({"inputs": [{
+            "from": {"id": "1"},
+            "to": {"id": "2"},
+            "types": [{"associationCategory": "USER_DEFINED", "associationTypeId": 12}]
+        }]})
+    );
+    assert_eq!(
+        serde_json::to_value(BatchInput {
+            inputs: vec![FetchAssociationsInput {
+                id: "1".to_string(),
+                after: None,
+            }],
+        })
+        .unwrap(),
+        serde_json::json!({"inputs": [{"id": "1"}]})
+    );
+
+    let created: BatchResponse<LabelsBetweenObjectPair> = serde_json::from_str(
+        r#"{"status": "COMPLETE",
+            "results": [{"fromObjectTypeId": "0-5", "fromObjectId": 1,
+                         "toObjectTypeId": "0-1", "toObjectId": 2, "labels": ["Manager"]}],
+            "errors": [{"status": "error", "category": "VALIDATION_ERROR",
+                        "message": "Object 3 does not exist"}],
+            "startedAt": "2024-01-01T00:00:00Z", "completedAt": "2024-01-01T00:00:01Z"}"#,
+    )
+    .unwrap();
+    assert_eq!(created.status, Status::Complete);
+    assert_eq!(created.results[0].labels, vec!["Manager".to_string()]);
+    assert_eq!(
+        created.errors.unwrap()[0].message,
+        "Object 3 does not exist"
+    );
+
+    let read: BatchResponse<MultiAssociatedObjects> = serde_json::from_str(
+        r#"{"status": "COMPLETE",
+            "results": [{"from": {"id": "1"},
+                         "to": [{"toObjectId": 2, "associationTypes": [
+                             {"category": "HUBSPOT_DEFINED", "typeId": 16, "label": null}]}],
+                         "paging": {"next": {"after": "abc"}}}],
+            "startedAt": "2024-01-01T00:00:00Z", "completedAt": "2024-01-01T00:00:01Z"}"#,
+    )
+    .unwrap();
+    let to = &read.results[0].to[0];
+    assert_eq!(
+        AssociationSpec::from(&to.association_types[0]),
+        AssociationSpec::TICKET_TO_CONTACT
+    );
+    assert_eq!(
+        read.results[0]
+            .paging
+            .as_ref()
+            .unwrap()
+            .next
+            .as_ref()
+            .unwrap()
+            .after,
+        "abc"
+    );
+
+    let page: CollectionResponseMultiAssociatedObjectWithLabel =
+        serde_json::from_str(r#"{"results": [{"toObjectId": 2, "associationTypes": []}]}"#)
+            .unwrap();
+    assert_eq!(page.results[0].to_object_id, 2);
+    assert_eq!(page.paging, None);
+}
@@ -0,0 +1,125 @@
+//! How the shared modules send requests through a client.
+//!
+//! A client hands the shared modules a [`Transport`]: its base URL, token and
+//! middleware stack. Requests sent through it are rate limited and have their
+//! OAuth token refreshed like those of the generated methods. Errors are
+//! returned as the client's own generated `Error`, which implements
+//! [`ClientError`].
+
+/// The error type of a client, as the shared modules create it.
+pub trait ClientError: Sized {
+    /// The request could not be sent, or a middleware failed.
+    fn communication(e: reqwest_middleware::Error) -> Self;
+    /// The response has an unsuccessful status.
+    fn server(body: String, status: reqwest::StatusCode) -> Self;
+    /// The body of a successful response could not be deserialized.
+    fn deserialize(body: String, e: serde_json::Error, status: reqwest::StatusCode) -> Self;
+}
+
+/// The base URL, token and middleware stack of a client.
+#[derive(Clone)]
+pub struct Transport {
+    base_url: String,
+    token: String,
+    client: reqwest_middleware::ClientWithMiddleware,
+}
+
+impl std::fmt::Debug for Transport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Transport")
+            .field("base_url", &self.base_url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Transport {
+    /// Create a transport sending requests to `base_url` through `client`.
+    pub fn new<C>(base_url: &str, token: &str, client: C) -> Self
+    where
+        C: Into<reqwest_middleware::ClientWithMiddleware>,
+    {
+        Transport {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            client: client.into(),
+        }
+    }
+
+    /// The base URL, without a trailing `/`.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Start a request to `path`, relative to the base URL.
+    pub fn request(&self, method: http::Method, path: &str) -> reqwest_middleware::RequestBuilder {
+        self.client
+            .request(
+                method,
+                format!("{}/{}", self.base_url, path.trim_start_matches('/')),
+            )
+            .bearer_auth(&self.token)
+    }
+
+    /// Send a request and deserialize the response.
+    pub async fn send<T, E>(&self, req: reqwest_middleware::RequestBuilder) -> Result<T, E>
+    where
+        T: serde::de::DeserializeOwned,
+        E: ClientError,
+    {
+        parse_response(req.send().await.map_err(E::communication)?).await
+    }
+
+    /// Send a request whose response has no body.
+    pub async fn execute<E>(&self, req: reqwest_middleware::RequestBuilder) -> Result<(), E>
+    where
+        E: ClientError,
+    {
+        check_response(req.send().await.map_err(E::communication)?).await
+    }
+}
+
+/// Append `middleware` to the stack of a client, after the middleware it
+/// already has.
+///
+/// The clients install their rate limiter and OAuth session this way, after
+/// the tracing and retries of the generated constructors, so those run once
+/// for every attempt.
+pub fn append_middleware<M>(
+    client: reqwest_middleware::ClientWithMiddleware,
+    middleware: M,
+) -> reqwest_middleware::ClientWithMiddleware
+where
+    M: reqwest_middleware::Middleware,
+{
+    reqwest_middleware::ClientBuilder::from_client(client)
+        .with(middleware)
+        .build()
+}
+
+/// Deserialize a successful response, or turn a failed one into a server error.
+pub async fn parse_response<T, E>(resp: reqwest::Response) -> Result<T, E>
+where
+    T: serde::de::DeserializeOwned,
+    E: ClientError,
+{
+    let status = resp.status();
+    let text = resp.text().await.unwrap_or_default();
+    if status.is_success() {
+        serde_json::from_str(&text).map_err(|err| E::deserialize(text, err, status))
+    } else {
+        Err(E::server(text, status))
+    }
+}
+
+/// Check that a response without a body succeeded.
+pub async fn check_response<E>(resp: reqwest::Response) -> Result<(), E>
+where
+    E: ClientError,
+{
+    let status = resp.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(E::server(resp.text().await.unwrap_or_default(), status))
+    }
+}
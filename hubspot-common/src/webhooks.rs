@@ -0,0 +1,438 @@
+//! Verification and parsing of webhook requests.
+//!
+//! HubSpot signs webhook requests with the `X-HubSpot-Signature-v3` header: a
+//! base64 HMAC-SHA256, keyed with the app's client secret, over the request
+//! method, URI, body and the `X-HubSpot-Request-Timestamp` header. Requests
+//! older than five minutes are rejected to prevent replays.
+//!
+//! One app receives the events of every object type it subscribed to, so the
+//! same [`WebhookVerifier`] and [`WebhookEvent`] serve all clients.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// The header holding the request signature.
+pub const SIGNATURE_HEADER: &str = "X-HubSpot-Signature-v3";
+/// The header holding the time the request was sent, in epoch milliseconds.
+pub const TIMESTAMP_HEADER: &str = "X-HubSpot-Request-Timestamp";
+/// How old a request may be before it is rejected.
+pub const MAX_REQUEST_AGE: Duration = Duration::from_secs(5 * 60);
+
+/// Why a webhook request was rejected.
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookError {
+    /// A required header is missing or not valid UTF-8.
+    #[error("missing header `{0}`")]
+    MissingHeader(&'static str),
+    /// The timestamp header is not a number.
+    #[error("invalid timestamp `{0}`")]
+    InvalidTimestamp(String),
+    /// The request was sent longer ago than the replay window allows.
+    #[error("request timestamp is {age:?} old, older than the allowed {max:?}")]
+    Expired { age: Duration, max: Duration },
+    /// The signature does not match the request.
+    #[error("invalid signature")]
+    InvalidSignature,
+    /// The body is not a batch of webhook events.
+    #[error("invalid webhook payload: {0}")]
+    InvalidPayload(#[from] serde_json::Error),
+}
+
+/// Checks the signatures of webhook requests with the app's client secret.
+#[derive(Clone)]
+pub struct WebhookVerifier {
+    client_secret: String,
+    max_age: Duration,
+}
+
+impl std::fmt::Debug for WebhookVerifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookVerifier")
+            .field("max_age", &self.max_age)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WebhookVerifier {
+    /// Create a verifier for the app with the given client secret.
+    pub fn new<S: ToString>(client_secret: S) -> Self {
+        WebhookVerifier {
+            client_secret: client_secret.to_string(),
+            max_age: MAX_REQUEST_AGE,
+        }
+    }
+
+    /// Create a verifier with the client secret in the `HUBSPOT_CLIENT_SECRET`
+    /// environment variable, as read by `OAuthApp::new_from_env`.
+    pub fn new_from_env() -> Self {
+        let client_secret =
+            std::env::var("HUBSPOT_CLIENT_SECRET").expect("must set HUBSPOT_CLIENT_SECRET");
+        WebhookVerifier::new(client_secret)
+    }
+
+    /// Change how old a request may be before it is rejected.
+    pub fn set_max_age(&mut self, max_age: Duration) -> &mut Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Compute the signature HubSpot sends for a request.
+    pub fn sign(&self, method: &str, uri: &str, body: &[u8], timestamp: &str) -> String {
+        let mac = self.mac(method, uri, body, timestamp);
+        base64::engine::general_purpose::STANDARD.encode(mac.finalize().into_bytes())
+    }
+
+    fn mac(&self, method: &str, uri: &str, body: &[u8], timestamp: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.client_secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(method.as_bytes());
+        mac.update(decode_uri(uri).as_bytes());
+        mac.update(body);
+        mac.update(timestamp.as_bytes());
+        mac
+    }
+
+    /// Check the signature and timestamp of a request.
+    ///
+    /// `uri` is the full URL the request was sent to, including the scheme,
+    /// host and query string.
+    pub fn verify(
+        &self,
+        method: &str,
+        uri: &str,
+        body: &[u8],
+        timestamp: &str,
+        signature: &str,
+    ) -> Result<(), WebhookError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        self.verify_at(method, uri, body, timestamp, signature, now)
+    }
+
+    /// Check a request as [`WebhookVerifier::verify`] does, at the given time
+    /// since the Unix epoch.
+    pub fn verify_at(
+        &self,
+        method: &str,
+        uri: &str,
+        body: &[u8],
+        timestamp: &str,
+        signature: &str,
+        now: Duration,
+    ) -> Result<(), WebhookError> {
+        let sent = timestamp
+            .trim()
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|_| WebhookError::InvalidTimestamp(timestamp.to_string()))?;
+        let age = now.saturating_sub(sent);
+        if age > self.max_age {
+            return Err(WebhookError::Expired {
+                age,
+                max: self.max_age,
+            });
+        }
+
+        let signature = base64::engine::general_purpose::STANDARD
+            .decode(signature.trim())
+            .map_err(|_| WebhookError::InvalidSignature)?;
+        self.mac(method, uri, body, timestamp)
+            .verify_slice(&signature)
+            .map_err(|_| WebhookError::InvalidSignature)
+    }
+
+    /// Check a request using its signature and timestamp headers.
+    #[cfg(feature = "requests")]
+    pub fn verify_headers(
+        &self,
+        method: &str,
+        uri: &str,
+        headers: &http::HeaderMap,
+        body: &[u8],
+    ) -> Result<(), WebhookError> {
+        let header = |name: &'static str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .ok_or(WebhookError::MissingHeader(name))
+        };
+        self.verify(
+            method,
+            uri,
+            body,
+            header(TIMESTAMP_HEADER)?,
+            header(SIGNATURE_HEADER)?,
+        )
+    }
+
+    /// Check a request using its headers and parse the events in its body.
+    #[cfg(feature = "requests")]
+    pub fn verify_and_parse(
+        &self,
+        method: &str,
+        uri: &str,
+        headers: &http::HeaderMap,
+        body: &[u8],
+    ) -> Result<Vec<WebhookEvent>, WebhookError> {
+        self.verify_headers(method, uri, headers, body)?;
+        parse_events(body)
+    }
+}
+
+/// HubSpot signs the URI with these characters decoded.
+fn decode_uri(uri: &str) -> String {
+    const DECODED: [(&str, &str); 12] = [
+        ("%3A", ":"),
+        ("%2F", "/"),
+        ("%3F", "?"),
+        ("%40", "@"),
+        ("%21", "!"),
+        ("%24", "$"),
+        ("%27", "'"),
+        ("%28", "("),
+        ("%29", ")"),
+        ("%2A", "*"),
+        ("%2C", ","),
+        ("%3B", ";"),
+    ];
+    let mut decoded = String::with_capacity(uri.len());
+    let mut rest = uri;
+    while let Some(i) = rest.find('%') {
+        decoded.push_str(&rest[..i]);
+        rest = &rest[i..];
+        match DECODED.iter().find(|(encoded, _)| {
+            rest.get(..3)
+                .is_some_and(|p| p.eq_ignore_ascii_case(encoded))
+        }) {
+            Some((encoded, plain)) => {
+                decoded.push_str(plain);
+                rest = &rest[encoded.len()..];
+            }
+            None => {
+                decoded.push('%');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Parse the batch of events in the body of a webhook request.
+pub fn parse_events(body: &[u8]) -> Result<Vec<WebhookEvent>, WebhookError> {
+    Ok(serde_json::from_slice(body)?)
+}
+
+/// Fields sent with every event.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct EventMetadata {
+    #[serde(rename = "eventId")]
+    pub event_id: i64,
+    #[serde(rename = "subscriptionId")]
+    pub subscription_id: i64,
+    #[serde(rename = "portalId")]
+    pub portal_id: i64,
+    #[serde(rename = "appId")]
+    pub app_id: i64,
+    /// When the event happened, in epoch milliseconds.
+    #[serde(rename = "occurredAt")]
+    pub occurred_at: i64,
+    /// Starts at 0 and increases with every retry of the delivery.
+    #[serde(rename = "attemptNumber", default)]
+    pub attempt_number: i32,
+    #[serde(
+        rename = "changeSource",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub change_source: Option<String>,
+    #[serde(rename = "sourceId", default, skip_serializing_if = "Option::is_none")]
+    pub source_id: Option<String>,
+}
+
+/// An object was created, deleted or restored.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ObjectEvent {
+    #[serde(flatten)]
+    pub metadata: EventMetadata,
+    #[serde(rename = "objectId")]
+    pub object_id: i64,
+    #[serde(
+        rename = "changeFlag",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub change_flag: Option<String>,
+}
+
+/// A property of an object changed.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct PropertyChangeEvent {
+    #[serde(flatten)]
+    pub metadata: EventMetadata,
+    #[serde(rename = "objectId")]
+    pub object_id: i64,
+    #[serde(rename = "propertyName")]
+    pub property_name: String,
+    /// The new value. Empty when the property was cleared.
+    #[serde(rename = "propertyValue", default)]
+    pub property_value: String,
+}
+
+/// Objects were merged.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct MergeEvent {
+    #[serde(flatten)]
+    pub metadata: EventMetadata,
+    /// The ID of the object that remains after the merge.
+    #[serde(rename = "objectId")]
+    pub object_id: i64,
+    #[serde(rename = "primaryObjectId")]
+    pub primary_object_id: i64,
+    #[serde(rename = "mergedObjectIds", default)]
+    pub merged_object_ids: Vec<i64>,
+    #[serde(rename = "newObjectId")]
+    pub new_object_id: i64,
+    #[serde(rename = "numberOfPropertiesMoved", default)]
+    pub number_of_properties_moved: i64,
+}
+
+/// An association of an object was added or removed.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct AssociationChangeEvent {
+    #[serde(flatten)]
+    pub metadata: EventMetadata,
+    /// E.g. `CONTACT_TO_TICKET`.
+    #[serde(rename = "associationType")]
+    pub association_type: String,
+    #[serde(rename = "fromObjectId")]
+    pub from_object_id: i64,
+    #[serde(rename = "toObjectId")]
+    pub to_object_id: i64,
+    #[serde(rename = "associationRemoved")]
+    pub association_removed: bool,
+    #[serde(rename = "isPrimaryAssociation", default)]
+    pub is_primary_association: bool,
+}
+
+/// An event delivered to a webhook, by `subscriptionType`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebhookEvent {
+    ContactCreation(ObjectEvent),
+    ContactDeletion(ObjectEvent),
+    ContactPrivacyDeletion(ObjectEvent),
+    ContactRestore(ObjectEvent),
+    ContactPropertyChange(PropertyChangeEvent),
+    ContactMerge(MergeEvent),
+    ContactAssociationChange(AssociationChangeEvent),
+    TicketCreation(ObjectEvent),
+    TicketDeletion(ObjectEvent),
+    TicketRestore(ObjectEvent),
+    TicketPropertyChange(PropertyChangeEvent),
+    TicketMerge(MergeEvent),
+    TicketAssociationChange(AssociationChangeEvent),
+    /// An event of a subscription type without a variant.
+    Other(serde_json::Value),
+}
+
+impl WebhookEvent {
+    /// The `subscriptionType` of the event.
+    pub fn subscription_type(&self) -> &str {
+        match self {
+            WebhookEvent::ContactCreation(_) => "contact.creation",
+            WebhookEvent::ContactDeletion(_) => "contact.deletion",
+            WebhookEvent::ContactPrivacyDeletion(_) => "contact.privacyDeletion",
+            WebhookEvent::ContactRestore(_) => "contact.restore",
+            WebhookEvent::ContactPropertyChange(_) => "contact.propertyChange",
+            WebhookEvent::ContactMerge(_) => "contact.merge",
+            WebhookEvent::ContactAssociationChange(_) => "contact.associationChange",
+            WebhookEvent::TicketCreation(_) => "ticket.creation",
+            WebhookEvent::TicketDeletion(_) => "ticket.deletion",
+            WebhookEvent::TicketRestore(_) => "ticket.restore",
+            WebhookEvent::TicketPropertyChange(_) => "ticket.propertyChange",
+            WebhookEvent::TicketMerge(_) => "ticket.merge",
+            WebhookEvent::TicketAssociationChange(_) => "ticket.associationChange",
+            WebhookEvent::Other(value) => value
+                .get("subscriptionType")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The ID of the object the event is about, as taken by the `get_by_id`
+    /// operations. Association changes return the `from` object.
+    pub fn object_id(&self) -> Option<String> {
+        let id = match self {
+            WebhookEvent::ContactCreation(e)
+            | WebhookEvent::ContactDeletion(e)
+            | WebhookEvent::ContactPrivacyDeletion(e)
+            | WebhookEvent::ContactRestore(e)
+            | WebhookEvent::TicketCreation(e)
+            | WebhookEvent::TicketDeletion(e)
+            | WebhookEvent::TicketRestore(e) => e.object_id,
+            WebhookEvent::ContactPropertyChange(e) | WebhookEvent::TicketPropertyChange(e) => {
+                e.object_id
+            }
+            WebhookEvent::ContactMerge(e) | WebhookEvent::TicketMerge(e) => e.object_id,
+            WebhookEvent::ContactAssociationChange(e)
+            | WebhookEvent::TicketAssociationChange(e) => e.from_object_id,
+            WebhookEvent::Other(value) => value.get("objectId")?.as_i64()?,
+        };
+        Some(id.to_string())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for WebhookEvent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let subscription_type = value
+            .get("subscriptionType")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| D::Error::missing_field("subscriptionType"))?
+            .to_string();
+
+        fn typed<T, E>(value: serde_json::Value) -> Result<T, E>
+        where
+            T: serde::de::DeserializeOwned,
+            E: serde::de::Error,
+        {
+            serde_json::from_value(value).map_err(E::custom)
+        }
+
+        Ok(match subscription_type.as_str() {
+            "contact.creation" => WebhookEvent::ContactCreation(typed(value)?),
+            "contact.deletion" => WebhookEvent::ContactDeletion(typed(value)?),
+            "contact.privacyDeletion" => WebhookEvent::ContactPrivacyDeletion(typed(value)?),
+            "contact.restore" => WebhookEvent::ContactRestore(typed(value)?),
+            "contact.propertyChange" => WebhookEvent::ContactPropertyChange(typed(value)?),
+            "contact.merge" => WebhookEvent::ContactMerge(typed(value)?),
+            "contact.associationChange" => WebhookEvent::ContactAssociationChange(typed(value)?),
+            "ticket.creation" => WebhookEvent::TicketCreation(typed(value)?),
+            "ticket.deletion" => WebhookEvent::TicketDeletion(typed(value)?),
+            "ticket.restore" => WebhookEvent::TicketRestore(typed(value)?),
+            "ticket.propertyChange" => WebhookEvent::TicketPropertyChange(typed(value)?),
+            "ticket.merge" => WebhookEvent::TicketMerge(typed(value)?),
+            "ticket.associationChange" => WebhookEvent::TicketAssociationChange(typed(value)?),
+            _ => WebhookEvent::Other(value),
+        })
+    }
+}
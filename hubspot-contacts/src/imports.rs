@@ -0,0 +1,832 @@
+//! Imports of contacts from CSV files.
+//!
+//! The imports API takes a CSV file and a mapping of its columns to contact
+//! properties, and processes the rows in the background. It handles loads
+//! beyond what the batch operations are meant for; a file may hold up to
+//! 1,048,576 rows and 512 MB, so files are streamed from disk while they are
+//! uploaded rather than read into memory. That rules out the
+//! [`Attachment`](crate::types::multipart::Attachment) the generated
+//! multipart methods take, which holds the whole file in its `data`, so
+//! [`Imports::start`] builds the form from a [`CsvFile`] instead.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use hubspot_contacts::{
+//!     imports::{ColumnMapping, CsvFile, IdColumnType, ImportOperation, ImportRequest},
+//!     objects::ObjectType,
+//!     properties::PropertyCache,
+//! };
+//!
+//! async fn example_imports() -> anyhow::Result<()> {
+//!     let client = hubspot_contacts::Client::new_from_env();
+//!     let file = CsvFile::open("contacts.csv").await?;
+//!
+//!     let request = ImportRequest::csv(
+//!         "Quarterly list",
+//!         "contacts.csv",
+//!         ImportOperation::Upsert,
+//!         vec![
+//!             ColumnMapping::id("Email", "email", IdColumnType::HubspotAlternateId),
+//!             ColumnMapping::property("First Name", "firstname"),
+//!             ColumnMapping::property("Stage", "lifecyclestage"),
+//!         ],
+//!     );
+//!
+//!     let imports = client.imports();
+//!     let schema = PropertyCache::new(client.properties())
+//!         .schema(&ObjectType::Contacts)
+//!         .await?;
+//!     if let Err(problems) = imports.validate(&request, &schema, &file) {
+//!         for problem in &problems {
+//!             eprintln!("{}", problem);
+//!         }
+//!         anyhow::bail!("{} problems with the import", problems.len());
+//!     }
+//!
+//!     let import = imports.start(&request, file).await?;
+//!     let import = imports
+//!         .wait(
+//!             &import.id,
+//!             Duration::from_secs(30),
+//!             Duration::from_secs(60 * 60),
+//!         )
+//!         .await?;
+//!     println!("{:?} {:?}", import.state, import.counters());
+//!     std::fs::write("errors.csv", imports.error_report(&import.id).await?)?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use hubspot_common::transport::Transport;
+
+use crate::properties::{PropertyError, PropertySchema};
+
+/// The object type ID of contacts.
+const CONTACT_OBJECT_TYPE_ID: &str = "0-1";
+
+/// What an import does with the rows of a file.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Debug,
+    Clone,
+    Copy,
+    schemars :: JsonSchema,
+    parse_display :: FromStr,
+    parse_display :: Display,
+)]
+pub enum ImportOperation {
+    /// Only create new records.
+    #[serde(rename = "CREATE")]
+    #[display("CREATE")]
+    Create,
+    /// Only update existing records.
+    #[serde(rename = "UPDATE")]
+    #[display("UPDATE")]
+    Update,
+    /// Create new records and update existing ones.
+    #[serde(rename = "UPSERT")]
+    #[display("UPSERT")]
+    Upsert,
+}
+
+/// How a column identifies existing records.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Debug,
+    Clone,
+    Copy,
+    schemars :: JsonSchema,
+    parse_display :: FromStr,
+    parse_display :: Display,
+)]
+pub enum IdColumnType {
+    /// The column holds record IDs.
+    #[serde(rename = "HUBSPOT_OBJECT_ID")]
+    #[display("HUBSPOT_OBJECT_ID")]
+    HubspotObjectId,
+    /// The column holds a unique property, e.g. `email`.
+    #[serde(rename = "HUBSPOT_ALTERNATE_ID")]
+    #[display("HUBSPOT_ALTERNATE_ID")]
+    HubspotAlternateId,
+}
+
+/// The mapping of one CSV column to a property.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ColumnMapping {
+    #[serde(rename = "columnObjectTypeId")]
+    pub column_object_type_id: String,
+    /// The column name as it appears in the header row.
+    #[serde(rename = "columnName")]
+    pub column_name: String,
+    /// The property the column is imported into. Columns without a property
+    /// are skipped.
+    #[serde(
+        rename = "propertyName",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub property_name: Option<String>,
+    #[serde(
+        rename = "idColumnType",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub id_column_type: Option<IdColumnType>,
+}
+
+impl ColumnMapping {
+    /// Import a column into a contact property.
+    pub fn property<C: ToString, P: ToString>(column: C, property: P) -> Self {
+        ColumnMapping {
+            column_object_type_id: CONTACT_OBJECT_TYPE_ID.to_string(),
+            column_name: column.to_string(),
+            property_name: Some(property.to_string()),
+            id_column_type: None,
+        }
+    }
+
+    /// Import a column into a contact property and match existing contacts on
+    /// it.
+    pub fn id<C: ToString, P: ToString>(column: C, property: P, id_type: IdColumnType) -> Self {
+        ColumnMapping {
+            id_column_type: Some(id_type),
+            ..ColumnMapping::property(column, property)
+        }
+    }
+
+    /// Skip a column.
+    pub fn skip<C: ToString>(column: C) -> Self {
+        ColumnMapping {
+            column_object_type_id: CONTACT_OBJECT_TYPE_ID.to_string(),
+            column_name: column.to_string(),
+            property_name: None,
+            id_column_type: None,
+        }
+    }
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct FileImportPage {
+    #[serde(rename = "hasHeader")]
+    pub has_header: bool,
+    #[serde(rename = "columnMappings")]
+    pub column_mappings: Vec<ColumnMapping>,
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ImportFile {
+    /// Must match the file name of the uploaded file.
+    #[serde(rename = "fileName")]
+    pub file_name: String,
+    #[serde(rename = "fileFormat")]
+    pub file_format: String,
+    #[serde(rename = "fileImportPage")]
+    pub file_import_page: FileImportPage,
+}
+
+/// The description of an import, sent with the file.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ImportRequest {
+    pub name: String,
+    /// The operation by object type ID.
+    #[serde(rename = "importOperations")]
+    pub import_operations: BTreeMap<String, ImportOperation>,
+    /// The order of date parts, e.g. `DAY_MONTH_YEAR`.
+    #[serde(
+        rename = "dateFormat",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub date_format: Option<String>,
+    #[serde(
+        rename = "marketableContactImport",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub marketable_contact_import: Option<bool>,
+    #[serde(
+        rename = "createContactListFromImport",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub create_contact_list_from_import: Option<bool>,
+    pub files: Vec<ImportFile>,
+}
+
+impl ImportRequest {
+    /// Describe the import of one CSV file with a header row into contacts.
+    pub fn csv<N: ToString, F: ToString>(
+        name: N,
+        file_name: F,
+        operation: ImportOperation,
+        column_mappings: Vec<ColumnMapping>,
+    ) -> Self {
+        ImportRequest {
+            name: name.to_string(),
+            import_operations: BTreeMap::from([(CONTACT_OBJECT_TYPE_ID.to_string(), operation)]),
+            date_format: None,
+            marketable_contact_import: None,
+            create_contact_list_from_import: None,
+            files: vec![ImportFile {
+                file_name: file_name.to_string(),
+                file_format: "CSV".to_string(),
+                file_import_page: FileImportPage {
+                    has_header: true,
+                    column_mappings,
+                },
+            }],
+        }
+    }
+
+    /// Check the mapped properties against the property definitions of
+    /// contacts.
+    pub fn check_mappings(&self, schema: &PropertySchema) -> Vec<PropertyError> {
+        let mut errors = vec![];
+        let mappings = self
+            .files
+            .iter()
+            .flat_map(|f| f.file_import_page.column_mappings.iter());
+        for mapping in mappings {
+            let Some(name) = &mapping.property_name else {
+                continue;
+            };
+            match schema.property(name) {
+                Ok(property) if property.is_read_only() && mapping.id_column_type.is_none() => {
+                    errors.push(PropertyError::ReadOnly {
+                        property: name.clone(),
+                    })
+                }
+                Ok(_) => {}
+                Err(e) => errors.push(e),
+            }
+        }
+        errors
+    }
+}
+
+/// The processing state of an import.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Debug,
+    Clone,
+    Copy,
+    schemars :: JsonSchema,
+    parse_display :: FromStr,
+    parse_display :: Display,
+)]
+pub enum ImportState {
+    #[serde(rename = "STARTED")]
+    #[display("STARTED")]
+    Started,
+    #[serde(rename = "PROCESSING")]
+    #[display("PROCESSING")]
+    Processing,
+    #[serde(rename = "DEFERRED")]
+    #[display("DEFERRED")]
+    Deferred,
+    #[serde(rename = "DONE")]
+    #[display("DONE")]
+    Done,
+    #[serde(rename = "FAILED")]
+    #[display("FAILED")]
+    Failed,
+    #[serde(rename = "CANCELED")]
+    #[display("CANCELED")]
+    Canceled,
+    #[serde(rename = "REVERTED")]
+    #[display("REVERTED")]
+    Reverted,
+}
+
+impl ImportState {
+    /// Whether the import has stopped processing.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            ImportState::Done | ImportState::Failed | ImportState::Canceled | ImportState::Reverted
+        )
+    }
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ImportMetadata {
+    /// Row counts by name, e.g. `TOTAL_ROWS`, `CREATED_OBJECTS` or `ERRORS`.
+    #[serde(default)]
+    pub counters: HashMap<String, i64>,
+    #[serde(rename = "fileIds", default)]
+    pub file_ids: Vec<String>,
+    #[serde(rename = "objectLists", default)]
+    pub object_lists: Vec<serde_json::Value>,
+}
+
+/// An import and its progress.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct Import {
+    pub id: String,
+    pub state: ImportState,
+    #[serde(
+        rename = "importName",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub import_name: Option<String>,
+    #[serde(
+        rename = "importSource",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub import_source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ImportMetadata>,
+    #[serde(
+        rename = "optOutImport",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub opt_out_import: Option<bool>,
+    #[serde(rename = "createdAt")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Import {
+    /// The row counts of the import.
+    pub fn counters(&self) -> HashMap<String, i64> {
+        self.metadata
+            .as_ref()
+            .map(|m| m.counters.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct CollectionResponseImport {
+    pub results: Vec<Import>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paging: Option<crate::types::ForwardPaging>,
+}
+
+/// The row an import error happened in.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ImportRowData {
+    #[serde(rename = "lineNumber")]
+    pub line_number: i64,
+    #[serde(rename = "rowData", default)]
+    pub row_data: Vec<String>,
+    #[serde(rename = "fileId", default, skip_serializing_if = "Option::is_none")]
+    pub file_id: Option<i64>,
+    #[serde(rename = "pageName", default, skip_serializing_if = "Option::is_none")]
+    pub page_name: Option<String>,
+}
+
+/// A row that could not be imported.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct ImportError {
+    pub id: String,
+    #[serde(rename = "errorType")]
+    pub error_type: String,
+    #[serde(
+        rename = "errorMessage",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub error_message: Option<String>,
+    #[serde(
+        rename = "invalidValue",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub invalid_value: Option<String>,
+    #[serde(
+        rename = "knownColumnNumber",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub known_column_number: Option<i64>,
+    #[serde(
+        rename = "objectType",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub object_type: Option<String>,
+    #[serde(
+        rename = "sourceData",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub source_data: Option<ImportRowData>,
+    #[serde(rename = "createdAt")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct CollectionResponseImportError {
+    pub results: Vec<ImportError>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paging: Option<crate::types::ForwardPaging>,
+}
+
+/// Render import errors as a CSV report with one line per failed row.
+///
+/// The `row` column holds the failed row itself as a line of CSV.
+pub fn error_report_csv(errors: &[ImportError]) -> String {
+    let mut report = String::from("line,error_type,error_message,invalid_value,row\n");
+    for error in errors {
+        let source = error.source_data.as_ref();
+        let fields = [
+            source
+                .map(|s| s.line_number.to_string())
+                .unwrap_or_default(),
+            error.error_type.clone(),
+            error.error_message.clone().unwrap_or_default(),
+            error.invalid_value.clone().unwrap_or_default(),
+            source
+                .map(|s| itertools::join(s.row_data.iter().map(|f| csv_field(f)), ","))
+                .unwrap_or_default(),
+        ];
+        report.push_str(&itertools::join(fields.iter().map(|f| csv_field(f)), ","));
+        report.push('\n');
+    }
+    report
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// The column names in the header row of a CSV file.
+fn csv_header(data: &[u8]) -> Vec<String> {
+    let data = data.strip_prefix(b"\xef\xbb\xbf").unwrap_or(data);
+    let end = data.iter().position(|b| *b == b'\n').unwrap_or(data.len());
+    let line = String::from_utf8_lossy(&data[..end]);
+
+    let mut columns = vec![];
+    let mut column = String::new();
+    let mut quoted = false;
+    let mut chars = line.trim_end_matches('\r').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                column.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => columns.push(std::mem::take(&mut column)),
+            c => column.push(c),
+        }
+    }
+    columns.push(column);
+    columns.into_iter().map(|c| c.trim().to_string()).collect()
+}
+
+/// A CSV file to import, uploaded as a stream.
+///
+/// Only the header row is read up front, to check the column mappings.
+pub struct CsvFile {
+    header: Vec<String>,
+    body: reqwest::Body,
+    length: Option<u64>,
+}
+
+impl std::fmt::Debug for CsvFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CsvFile")
+            .field("header", &self.header)
+            .field("length", &self.length)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CsvFile {
+    /// Upload `body`, whose first line is `header_line`, e.g. a stream made
+    /// with [`reqwest::Body::wrap_stream`].
+    pub fn new<B: Into<reqwest::Body>>(header_line: &[u8], body: B) -> Self {
+        CsvFile {
+            header: csv_header(header_line),
+            body: body.into(),
+            length: None,
+        }
+    }
+
+    /// Set the size of the body in bytes, if it is known.
+    pub fn set_length(&mut self, length: u64) -> &mut Self {
+        self.length = Some(length);
+        self
+    }
+
+    /// Read the header row of a file and stream the file from disk when it is
+    /// uploaded.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self, std::io::Error> {
+        use tokio::io::AsyncBufReadExt;
+
+        let mut header_line = vec![];
+        tokio::io::BufReader::new(tokio::fs::File::open(&path).await?)
+            .read_until(b'\n', &mut header_line)
+            .await?;
+
+        let file = tokio::fs::File::open(&path).await?;
+        let length = file.metadata().await?.len();
+        let mut csv = CsvFile::new(&header_line, file);
+        csv.set_length(length);
+        Ok(csv)
+    }
+
+    /// The column names in the header row.
+    pub fn header(&self) -> &[String] {
+        &self.header
+    }
+}
+
+/// A problem with an import found before starting it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportProblem {
+    /// A mapped property does not exist or cannot be written.
+    Property(PropertyError),
+    /// A mapped column is not in the header row of the file.
+    MissingColumn { column: String, file_name: String },
+}
+
+impl std::fmt::Display for ImportProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportProblem::Property(e) => write!(f, "{}", e),
+            ImportProblem::MissingColumn { column, file_name } => write!(
+                f,
+                "column `{}` is not in the header row of `{}`",
+                column, file_name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ImportProblem {}
+
+/// Why [`Imports::wait`] stopped before the import finished.
+#[derive(Debug)]
+pub enum WaitError {
+    /// Reading the import failed.
+    Request(crate::types::error::Error),
+    /// The import was still processing at the deadline.
+    TimedOut(Box<Import>),
+}
+
+impl From<crate::types::error::Error> for WaitError {
+    fn from(e: crate::types::error::Error) -> Self {
+        WaitError::Request(e)
+    }
+}
+
+impl std::fmt::Display for WaitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WaitError::Request(e) => write!(f, "reading the import: {}", e),
+            WaitError::TimedOut(import) => write!(
+                f,
+                "import {} is still {} at the deadline",
+                import.id, import.state
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WaitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WaitError::Request(e) => Some(e),
+            WaitError::TimedOut(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Imports {
+    transport: Transport,
+}
+
+impl Imports {
+    #[doc(hidden)]
+    pub fn new(transport: Transport) -> Self {
+        Self { transport }
+    }
+
+    /// Check an import before starting it: every mapped property must exist
+    /// and be writable, and every mapped column must be in the header row of
+    /// the file.
+    pub fn validate(
+        &self,
+        request: &ImportRequest,
+        schema: &PropertySchema,
+        file: &CsvFile,
+    ) -> Result<(), Vec<ImportProblem>> {
+        let mut problems: Vec<ImportProblem> = request
+            .check_mappings(schema)
+            .into_iter()
+            .map(ImportProblem::Property)
+            .collect();
+
+        for f in &request.files {
+            if !f.file_import_page.has_header {
+                continue;
+            }
+            for mapping in &f.file_import_page.column_mappings {
+                if !file.header().contains(&mapping.column_name) {
+                    problems.push(ImportProblem::MissingColumn {
+                        column: mapping.column_name.clone(),
+                        file_name: f.file_name.clone(),
+                    });
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// Upload a CSV file and start importing it.
+    ///
+    /// The file is sent under the name of the first file in the request, which
+    /// HubSpot matches against `fileName`.
+    #[tracing::instrument(skip(file))]
+    pub async fn start(
+        &self,
+        request: &ImportRequest,
+        file: CsvFile,
+    ) -> Result<Import, crate::types::error::Error> {
+        let mut req = self.transport.request(http::Method::POST, "crm/v3/imports");
+
+        let mut part = match file.length {
+            Some(length) => reqwest::multipart::Part::stream_with_length(file.body, length),
+            None => reqwest::multipart::Part::stream(file.body),
+        };
+        if let Some(f) = request.files.first() {
+            part = part.file_name(f.file_name.clone());
+        }
+        let form = reqwest::multipart::Form::new()
+            .text("importRequest", serde_json::to_string(request)?)
+            .part("files", part.mime_str("text/csv")?);
+        req = req.multipart(form);
+        self.transport.send(req).await
+    }
+
+    /// Read an import by ID.
+    #[tracing::instrument]
+    pub async fn get(&self, import_id: &str) -> Result<Import, crate::types::error::Error> {
+        let req = self
+            .transport
+            .request(http::Method::GET, &format!("crm/v3/imports/{}", import_id));
+        self.transport.send(req).await
+    }
+
+    /// Read a page of imports. Continue with `paging.next.after` from the
+    /// response.
+    #[tracing::instrument]
+    pub async fn list(
+        &self,
+        after: Option<String>,
+        limit: Option<i32>,
+    ) -> Result<CollectionResponseImport, crate::types::error::Error> {
+        let mut req = self.transport.request(http::Method::GET, "crm/v3/imports");
+        let mut query_params = vec![];
+        if let Some(p) = after {
+            query_params.push(("after", p));
+        }
+
+        if let Some(p) = limit {
+            query_params.push(("limit", format!("{}", p)));
+        }
+
+        req = req.query(&query_params);
+        self.transport.send(req).await
+    }
+
+    /// Cancel an import that is still processing.
+    #[tracing::instrument]
+    pub async fn cancel(&self, import_id: &str) -> Result<(), crate::types::error::Error> {
+        let req = self.transport.request(
+            http::Method::POST,
+            &format!("crm/v3/imports/{}/cancel", import_id),
+        );
+        self.transport.execute(req).await
+    }
+
+    /// Read a page of the errors of an import, with their messages and row
+    /// data. Continue with `paging.next.after` from the response.
+    #[tracing::instrument]
+    pub async fn errors(
+        &self,
+        import_id: &str,
+        after: Option<String>,
+        limit: Option<i32>,
+    ) -> Result<CollectionResponseImportError, crate::types::error::Error> {
+        let mut req = self.transport.request(
+            http::Method::GET,
+            &format!("crm/v3/imports/{}/errors", import_id),
+        );
+        let mut query_params = vec![
+            ("includeErrorMessage", "true".to_string()),
+            ("includeRowData", "true".to_string()),
+        ];
+        if let Some(p) = after {
+            query_params.push(("after", p));
+        }
+
+        if let Some(p) = limit {
+            query_params.push(("limit", format!("{}", p)));
+        }
+
+        req = req.query(&query_params);
+        self.transport.send(req).await
+    }
+
+    /// Read all errors of an import.
+    pub async fn all_errors(
+        &self,
+        import_id: &str,
+    ) -> Result<Vec<ImportError>, crate::types::error::Error> {
+        let mut errors = vec![];
+        let mut after = None;
+        loop {
+            let page = self.errors(import_id, after, Some(500)).await?;
+            errors.extend(page.results);
+            after = page.paging.and_then(|p| p.next).map(|n| n.after);
+            if after.is_none() {
+                return Ok(errors);
+            }
+        }
+    }
+
+    /// Download the errors of an import as a CSV report.
+    pub async fn error_report(
+        &self,
+        import_id: &str,
+    ) -> Result<String, crate::types::error::Error> {
+        Ok(error_report_csv(&self.all_errors(import_id).await?))
+    }
+
+    /// Poll an import until it has finished processing, for at most
+    /// `timeout`. Imports of large files take minutes, so poll no more often
+    /// than every few seconds.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn wait(
+        &self,
+        import_id: &str,
+        interval: std::time::Duration,
+        timeout: std::time::Duration,
+    ) -> Result<Import, WaitError> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let import = self.get(import_id).await?;
+            if import.state.is_finished() {
+                return Ok(import);
+            }
+            if tokio::time::Instant::now() + interval > deadline {
+                return Err(WaitError::TimedOut(Box::new(import)));
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
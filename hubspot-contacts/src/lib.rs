@@ -57,16 +57,33 @@
 #![allow(clippy::too_many_arguments)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+#[cfg(feature = "requests")]
+pub mod associations;
 #[cfg(feature = "requests")]
 pub mod basic;
 #[cfg(feature = "requests")]
 pub mod batch;
+#[cfg(feature = "requests")]
+pub mod errors;
+#[cfg(feature = "requests")]
+pub mod imports;
 mod methods;
 #[cfg(feature = "requests")]
+pub mod oauth;
+#[cfg(feature = "requests")]
+pub mod objects;
+#[cfg(feature = "requests")]
+pub mod properties;
+#[cfg(feature = "requests")]
+#[cfg(feature = "retry")]
+#[cfg(not(target_arch = "wasm32"))]
+pub use hubspot_common::rate_limit;
+#[cfg(feature = "requests")]
 pub mod search;
 #[cfg(test)]
 mod tests;
 pub mod types;
+pub mod webhooks;
 
 #[cfg(feature = "requests")]
 use std::env;
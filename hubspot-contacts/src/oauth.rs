@@ -0,0 +1,53 @@
+//! OAuth authentication for public (marketplace) apps.
+//!
+//! An [`OAuthApp`] holds the app credentials and drives the authorization code
+//! flow: it builds the install URL, exchanges the returned code for tokens and
+//! refreshes them. Each portal (HubSpot account) that installs the app gets its
+//! own token set; [`Portals`] keeps one [`Client`](crate::Client) per portal
+//! so a single process can serve many of them. A single portal only needs a
+//! [`Session`] and [`Client::new_from_session`](crate::Client::new_from_session).
+//! See [`hubspot_common::oauth`] for how the tokens are kept and refreshed.
+//!
+//! ```rust,no_run
+//! use hubspot_contacts::oauth::{OAuthApp, Portals, Scope};
+//!
+//! async fn example_oauth() -> anyhow::Result<()> {
+//!     let app = OAuthApp::new_from_env();
+//!     let url = app.user_consent_url(
+//!         &[Scope::Oauth, Scope::CrmObjectsContactsRead],
+//!         &[Scope::CrmObjectsContactsWrite],
+//!         "some-state",
+//!     );
+//!     println!("install the app at {}", url);
+//!
+//!     // Later, in the redirect handler.
+//!     let portals = Portals::new(app);
+//!     let (hub_id, _client) = portals.install("code-from-redirect").await?;
+//!
+//!     // Any time after that, with the access token refreshed when needed.
+//!     if let Some(client) = portals.get(hub_id).await? {
+//!         client
+//!             .basic()
+//!             .get_crm_v_3_objects_contacts_contact_id_get_by_id(None, None, "1", None, None)
+//!             .await?;
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+pub use hubspot_common::oauth::{
+    AccessToken, AccessTokenInfo, OAuthApp, OAuthClient, Scope, Session,
+};
+
+/// One client per portal that installed the app, keyed by hub ID.
+#[cfg(feature = "retry")]
+#[cfg(not(target_arch = "wasm32"))]
+pub type Portals = hubspot_common::oauth::Portals<crate::Client>;
+
+#[cfg(feature = "retry")]
+#[cfg(not(target_arch = "wasm32"))]
+impl OAuthClient for crate::Client {
+    fn from_session(session: &Session) -> Self {
+        crate::Client::new_from_session(session)
+    }
+}
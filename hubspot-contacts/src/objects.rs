@@ -0,0 +1,56 @@
+//! Basic, batch and search operations for any CRM object type.
+//!
+//! The `basic()`, `batch()` and `search()` modules only cover contacts. The
+//! [`Objects`] handle exposes the same operations for every object type that
+//! lives under `/crm/v3/objects`, including companies, deals and custom objects
+//! addressed by their `objectTypeId`.
+//!
+//! ```rust,no_run
+//! use hubspot_contacts::objects::{GetPageParams, ObjectType};
+//!
+//! async fn example_objects() -> anyhow::Result<()> {
+//!     let client = hubspot_contacts::Client::new_from_env();
+//!     let deals = client.objects(ObjectType::Deals);
+//!     let page = deals
+//!         .get_page(GetPageParams {
+//!             limit: Some(10),
+//!             properties: Some(vec!["dealname".to_string(), "amount".to_string()]),
+//!             ..Default::default()
+//!         })
+//!         .await?;
+//!     println!("{:?}", page.results);
+//!
+//!     let pets = client.objects("2-1234567".parse()?);
+//!     let pet = pets.get_by_id("42", Default::default()).await?;
+//!     println!("{:?}", pet.properties);
+//!     Ok(())
+//! }
+//! ```
+
+pub use hubspot_common::objects::{GetByIdParams, GetPageParams, ObjectModels, ObjectType};
+
+/// Basic, batch and search operations for one object type.
+pub type Objects = hubspot_common::objects::Objects<Models>;
+
+/// The models of this crate that [`Objects`] reads and writes.
+#[derive(Clone, Copy, Debug)]
+pub struct Models;
+
+impl ObjectModels for Models {
+    type Page = crate::types::CollectionResponseSimplePublicObjectWithAssociationsForwardPaging;
+    type ObjectWithAssociations = crate::types::SimplePublicObjectWithAssociations;
+    type Object = crate::types::SimplePublicObject;
+    type CreateInput = crate::types::SimplePublicObjectInputForCreate;
+    type UpdateInput = crate::types::SimplePublicObjectInput;
+    type MergeInput = crate::types::PublicMergeInput;
+    type BatchReadInput = crate::types::BatchReadInputSimplePublicObjectId;
+    type BatchCreateInput = crate::types::BatchInputSimplePublicObjectBatchInputForCreate;
+    type BatchUpdateInput = crate::types::BatchInputSimplePublicObjectBatchInput;
+    type BatchUpsertInput = crate::types::BatchInputSimplePublicObjectBatchInputUpsert;
+    type BatchArchiveInput = crate::types::BatchInputSimplePublicObjectId;
+    type BatchResponse = crate::types::BatchResponseSimplePublicObject;
+    type BatchUpsertResponse = crate::types::BatchResponseSimplePublicUpsertObject;
+    type SearchRequest = crate::types::PublicObjectSearchRequest;
+    type SearchPage = crate::types::CollectionResponseWithTotalSimplePublicObjectForwardPaging;
+    type Error = crate::types::error::Error;
+}
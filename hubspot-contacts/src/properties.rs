@@ -0,0 +1,113 @@
+//! Property definitions and local validation of property values.
+//!
+//! HubSpot only rejects unknown property names and bad enumeration values when
+//! a request reaches it. A [`PropertyCache`] fetches the property definitions of
+//! an object type once, keeps them for every clone of the cache, and checks
+//! create, update and upsert inputs and search requests against them.
+//!
+//! Validation is opt-in: call [`PropertyCache::validate`] before sending a
+//! request. Failures list every [`PropertyError`] found:
+//!
+//! ```rust,no_run
+//! use hubspot_contacts::{objects::ObjectType, properties::PropertyCache};
+//!
+//! async fn example_properties() -> anyhow::Result<()> {
+//!     let client = hubspot_contacts::Client::new_from_env();
+//!     let cache = PropertyCache::new(client.properties());
+//!
+//!     let body = hubspot_contacts::types::SimplePublicObjectInputForCreate {
+//!         associations: None,
+//!         properties: std::collections::HashMap::from([
+//!             ("email".to_string(), "bob@example.com".to_string()),
+//!             ("lifecyclestage".to_string(), "lead".to_string()),
+//!         ]),
+//!     };
+//!     cache.validate(&ObjectType::Contacts, &body).await?;
+//!     client.basic().post_crm_v_3_objects_contacts_create(&body).await?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+pub use hubspot_common::properties::{
+    CollectionResponseProperty, Property, PropertyError, PropertyInputs,
+    PropertyModificationMetadata, PropertyOption, PropertySchema, SearchFilter, SearchInputs,
+};
+
+use crate::types::Operator;
+
+/// Property definitions.
+pub type Properties = hubspot_common::properties::Properties<crate::types::error::Error>;
+
+/// Property definitions fetched once per object type and shared by every clone
+/// of the cache.
+pub type PropertyCache = hubspot_common::properties::PropertyCache<crate::types::error::Error>;
+
+/// Why a request could not be validated.
+pub type ValidationError = hubspot_common::properties::ValidationError<crate::types::error::Error>;
+
+impl PropertyInputs for crate::types::SimplePublicObjectInput {
+    fn property_maps(&self) -> Vec<&HashMap<String, String>> {
+        vec![&self.properties]
+    }
+}
+
+impl PropertyInputs for crate::types::SimplePublicObjectInputForCreate {
+    fn property_maps(&self) -> Vec<&HashMap<String, String>> {
+        vec![&self.properties]
+    }
+}
+
+impl PropertyInputs for crate::types::BatchInputSimplePublicObjectBatchInputForCreate {
+    fn property_maps(&self) -> Vec<&HashMap<String, String>> {
+        self.inputs.iter().map(|i| &i.properties).collect()
+    }
+}
+
+impl PropertyInputs for crate::types::BatchInputSimplePublicObjectBatchInput {
+    fn property_maps(&self) -> Vec<&HashMap<String, String>> {
+        self.inputs.iter().map(|i| &i.properties).collect()
+    }
+}
+
+impl PropertyInputs for crate::types::BatchInputSimplePublicObjectBatchInputUpsert {
+    fn property_maps(&self) -> Vec<&HashMap<String, String>> {
+        self.inputs.iter().map(|i| &i.properties).collect()
+    }
+}
+
+impl SearchInputs for crate::types::PublicObjectSearchRequest {
+    fn returned_properties(&self) -> Vec<&str> {
+        self.properties
+            .iter()
+            .flatten()
+            .map(|p| p.as_str())
+            .collect()
+    }
+
+    fn filters(&self) -> Vec<SearchFilter<'_>> {
+        self.filter_groups
+            .iter()
+            .flatten()
+            .flat_map(|g| g.filters.iter())
+            .map(|filter| SearchFilter {
+                property_name: &filter.property_name,
+                exact: matches!(
+                    filter.operator,
+                    Operator::Eq | Operator::Neq | Operator::In | Operator::NotIn
+                ),
+                values: filter
+                    .value
+                    .iter()
+                    .chain(filter.values.iter().flatten())
+                    .map(|v| v.as_str())
+                    .collect(),
+            })
+            .collect()
+    }
+
+    fn sorts(&self) -> Vec<&str> {
+        self.sorts.iter().flatten().map(|s| s.as_str()).collect()
+    }
+}
@@ -1 +1,538 @@
+use pretty_assertions::assert_eq;
 
+#[test]
+fn test_oauth_user_consent_url() {
+    use crate::oauth::{OAuthApp, Scope};
+
+    let app = OAuthApp::new("client-id", "secret", "https://example.com/callback");
+    let url = app.user_consent_url(
+        &[Scope::Oauth, Scope::CrmObjectsContactsRead],
+        &[Scope::Tickets],
+        "portal-state",
+    );
+    assert_eq!(
+        url,
+        "https://app.hubspot.com/oauth/authorize?client_id=client-id&redirect_uri=https%3A%2F%2Fexample.com%2Fcallback&scope=oauth+crm.objects.contacts.read&optional_scope=tickets&state=portal-state"
+    );
+    assert_eq!(
+        "crm.objects.contacts.write".parse::<Scope>().unwrap(),
+        Scope::CrmObjectsContactsWrite
+    );
+}
+
+#[test]
+fn test_object_type_from_str() {
+    use crate::objects::ObjectType;
+
+    assert_eq!(ObjectType::from("deals"), ObjectType::Deals);
+    assert_eq!(ObjectType::from("0-5"), ObjectType::Tickets);
+    assert_eq!(
+        ObjectType::from("2-1234567"),
+        ObjectType::Custom("2-1234567".to_string())
+    );
+    assert_eq!(ObjectType::LineItems.to_string(), "line_items");
+}
+
+#[test]
+fn test_property_schema_check_properties() {
+    use crate::{
+        objects::ObjectType,
+        properties::{Property, PropertyError, PropertySchema},
+    };
+
+    let properties: Vec<Property> = serde_json::from_str(
+        r#"[
+            {"name": "email", "label": "Email", "type": "string", "fieldType": "text"},
+            {"name": "lifecyclestage", "label": "Lifecycle stage", "type": "enumeration",
+             "fieldType": "radio", "options": [
+                {"label": "Lead", "value": "lead"},
+                {"label": "Customer", "value": "customer"}
+             ]},
+            {"name": "hs_object_id", "label": "Record ID", "type": "number", "fieldType": "number",
+             "modificationMetadata": {"readOnlyValue": true}}
+        ]"#,
+    )
+    .unwrap();
+    let schema = PropertySchema::new(ObjectType::Contacts, properties);
+
+    let errors = schema.check_properties(&std::collections::HashMap::from([
+        ("emial".to_string(), "bob@example.com".to_string()),
+        ("lifecyclestage".to_string(), "prospect".to_string()),
+        ("hs_object_id".to_string(), "1".to_string()),
+    ]));
+    assert_eq!(
+        errors,
+        vec![
+            PropertyError::UnknownProperty {
+                property: "emial".to_string(),
+                suggestion: Some("email".to_string()),
+            },
+            PropertyError::ReadOnly {
+                property: "hs_object_id".to_string(),
+            },
+            PropertyError::InvalidOption {
+                property: "lifecyclestage".to_string(),
+                value: "prospect".to_string(),
+                allowed: vec!["lead".to_string(), "customer".to_string()],
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_webhook_signature_and_events() {
+    use std::time::Duration;
+
+    use crate::webhooks::{WebhookError, WebhookEvent, WebhookVerifier};
+
+    let verifier = WebhookVerifier::new("app-secret");
+    let uri = "https://example.com/webhooks?portal%3Aid=1";
+    let body = br#"[
+        {"eventId": 1, "subscriptionId": 2, "portalId": 3, "appId": 4, "occurredAt": 1700000000000,
+         "subscriptionType": "contact.propertyChange", "attemptNumber": 0, "objectId": 123,
+         "propertyName": "email", "propertyValue": "bob@example.com", "changeSource": "CRM"},
+        {"eventId": 5, "subscriptionId": 6, "portalId": 3, "appId": 4, "occurredAt": 1700000000000,
+         "subscriptionType": "ticket.deletion", "attemptNumber": 1, "objectId": 456},
+        {"eventId": 7, "subscriptionId": 8, "portalId": 3, "appId": 4, "occurredAt": 1700000000000,
+         "subscriptionType": "deal.creation", "attemptNumber": 0, "objectId": 789}
+    ]"#;
+    let timestamp = "1700000000000";
+    let sent = Duration::from_millis(1_700_000_000_000);
+    let signature = verifier.sign("POST", uri, body, timestamp);
+
+    // The URI is signed with reserved characters decoded.
+    assert_eq!(
+        signature,
+        verifier.sign(
+            "POST",
+            "https://example.com/webhooks?portal:id=1",
+            body,
+            timestamp
+        )
+    );
+    verifier
+        .verify_at(
+            "POST",
+            uri,
+            body,
+            timestamp,
+            &signature,
+            sent + Duration::from_secs(60),
+        )
+        .unwrap();
+    assert!(matches!(
+        verifier.verify_at("POST", uri, b"[]", timestamp, &signature, sent),
+        Err(WebhookError::InvalidSignature)
+    ));
+    assert!(matches!(
+        verifier.verify_at(
+            "POST",
+            uri,
+            body,
+            timestamp,
+            &signature,
+            sent + Duration::from_secs(301)
+        ),
+        Err(WebhookError::Expired { .. })
+    ));
+
+    let events = crate::webhooks::parse_events(body).unwrap();
+    assert!(
+        matches!(&events[0], WebhookEvent::ContactPropertyChange(e) if e.property_name == "email")
+    );
+    assert!(
+        matches!(&events[1], WebhookEvent::TicketDeletion(e) if e.metadata.attempt_number == 1)
+    );
+    assert_eq!(events[2].subscription_type(), "deal.creation");
+    assert_eq!(
+        events
+            .iter()
+            .map(|e| e.object_id().unwrap())
+            .collect::<Vec<_>>(),
+        vec!["123", "456", "789"]
+    );
+}
+
+#[test]
+fn test_import_request_and_error_report() {
+    use crate::imports::{
+        error_report_csv, ColumnMapping, IdColumnType, ImportError, ImportOperation, ImportRequest,
+    };
+
+    let request = ImportRequest::csv(
+        "Quarterly list",
+        "contacts.csv",
+        ImportOperation::Upsert,
+        vec![
+            ColumnMapping::id("Email", "email", IdColumnType::HubspotAlternateId),
+            ColumnMapping::skip("Notes"),
+        ],
+    );
+    assert_eq!(
+        serde_json::to_value(&request).unwrap(),
+        serde_json::json!({
+            "name": "Quarterly list",
+            "importOperations": {"0-1": "UPSERT"},
+            "files": [{
+                "fileName": "contacts.csv",
+                "fileFormat": "CSV",
+                "fileImportPage": {
+                    "hasHeader": true,
+                    "columnMappings": [
+                        {"columnObjectTypeId": "0-1", "columnName": "Email",
+                         "propertyName": "email", "idColumnType": "HUBSPOT_ALTERNATE_ID"},
+                        {"columnObjectTypeId": "0-1", "columnName": "Notes"}
+                    ]
+                }
+            }]
+        })
+    );
+
+    let errors: Vec<ImportError> = serde_json::from_str(
+        r#"[{"id": "1", "errorType": "INVALID_EMAIL", "errorMessage": "Invalid email",
+             "invalidValue": "bob@", "createdAt": "2024-01-01T00:00:00Z",
+             "sourceData": {"lineNumber": 3, "rowData": ["bob@", "said \"hi\""]}}]"#,
+    )
+    .unwrap();
+    // The row is a line of CSV of its own, quoted as one field.
+    assert_eq!(
+        error_report_csv(&errors),
+        "line,error_type,error_message,invalid_value,row\n\
+         3,INVALID_EMAIL,Invalid email,bob@,\"bob@,\"\"said \"\"\"\"hi\"\"\"\"\"\"\"\n"
+    );
+}
+
+#[tokio::test]
+async fn test_objects_requests() {
+    use crate::objects::{GetPageParams, ObjectType};
+    use hubspot_common::test_util::StandIn;
+
+    let deal = r#"{"id":"7","properties":{"dealname":"Big deal"},"createdAt":"2024-01-01T00:00:00Z","updatedAt":"2024-01-02T00:00:00Z"}"#;
+    let stand_in = StandIn::new([
+        (200, "", &*format!(r#"{{"results":[{}]}}"#, deal)),
+        (200, "", &*format!(r#"{{"total":1,"results":[{}]}}"#, deal)),
+        (200, "", deal),
+        (204, "", ""),
+        (404, "", r#"{"message":"not found"}"#),
+    ]);
+    let mut client = crate::Client::new("token");
+    client.set_base_url(stand_in.serve().await);
+    let deals = client.objects(ObjectType::Deals);
+
+    let page = deals
+        .get_page(GetPageParams {
+            limit: Some(10),
+            properties: Some(vec!["dealname".to_string(), "amount".to_string()]),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(page.results[0].id, "7");
+
+    let found = deals
+        .search(&crate::types::PublicObjectSearchRequest {
+            query: Some("Big".to_string()),
+            limit: None,
+            after: None,
+            sorts: None,
+            properties: None,
+            filter_groups: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(found.total, 1);
+
+    deals
+        .update(
+            "big-deal",
+            Some("dealname".to_string()),
+            &crate::types::SimplePublicObjectInput {
+                properties: std::collections::HashMap::from([(
+                    "amount".to_string(),
+                    "100".to_string(),
+                )]),
+            },
+        )
+        .await
+        .unwrap();
+    deals.archive("7").await.unwrap();
+    let err = deals.archive("8").await.unwrap_err();
+    assert_eq!(err.status(), Some(reqwest::StatusCode::NOT_FOUND));
+
+    assert_eq!(
+        stand_in.paths(),
+        vec![
+            (
+                "GET".to_string(),
+                "/crm/v3/objects/deals?limit=10&properties=dealname%2Camount".to_string()
+            ),
+            (
+                "POST".to_string(),
+                "/crm/v3/objects/deals/search".to_string()
+            ),
+            (
+                "PATCH".to_string(),
+                "/crm/v3/objects/deals/big-deal?idProperty=dealname".to_string()
+            ),
+            ("DELETE".to_string(), "/crm/v3/objects/deals/7".to_string()),
+            ("DELETE".to_string(), "/crm/v3/objects/deals/8".to_string()),
+        ]
+    );
+    let requests = stand_in.requests();
+    assert!(requests[0].head.contains("authorization: bearer token"));
+    assert_eq!(requests[1].json(), serde_json::json!({"query": "Big"}));
+    assert_eq!(
+        requests[2].json(),
+        serde_json::json!({"properties": {"amount": "100"}})
+    );
+}
+
+#[tokio::test]
+async fn test_associations_requests() {
+    use crate::{
+        associations::{AssociationInput, AssociationSpec, BatchInput, PublicObjectId},
+        objects::ObjectType,
+    };
+    use hubspot_common::test_util::StandIn;
+
+    let stand_in = StandIn::new([
+        (
+            200,
+            "",
+            r#"{"fromObjectTypeId":"0-5","fromObjectId":1,"toObjectTypeId":"0-1","toObjectId":2,"labels":[]}"#,
+        ),
+        (
+            200,
+            "",
+            r#"{"status":"COMPLETE","results":[],"startedAt":"2024-01-01T00:00:00Z","completedAt":"2024-01-01T00:00:01Z"}"#,
+        ),
+        (204, "", ""),
+    ]);
+    let mut client = crate::Client::new("token");
+    client.set_base_url(stand_in.serve().await);
+    let associations = client.associations();
+
+    associations
+        .create(
+            ObjectType::Tickets,
+            "1",
+            ObjectType::Contacts,
+            "2",
+            &[AssociationSpec::TICKET_TO_CONTACT],
+        )
+        .await
+        .unwrap();
+    associations
+        .batch_create(
+            ObjectType::Contacts,
+            ObjectType::Companies,
+            &BatchInput {
+                inputs: vec![AssociationInput {
+                    from: PublicObjectId {
+                        id: "2".to_string(),
+                    },
+                    to: PublicObjectId {
+                        id: "3".to_string(),
+                    },
+                    types: vec![AssociationSpec::CONTACT_TO_COMPANY_PRIMARY],
+                }],
+            },
+        )
+        .await
+        .unwrap();
+    associations
+        .delete_label(ObjectType::Contacts, ObjectType::Companies, 12)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        stand_in.paths(),
+        vec![
+            (
+                "PUT".to_string(),
+                "/crm/v4/objects/tickets/1/associations/contacts/2".to_string()
+            ),
+            (
+                "POST".to_string(),
+                "/crm/v4/associations/contacts/companies/batch/create".to_string()
+            ),
+            (
+                "DELETE".to_string(),
+                "/crm/v4/associations/contacts/companies/labels/12".to_string()
+            ),
+        ]
+    );
+    assert_eq!(
+        stand_in.requests()[0].json(),
+        serde_json::json!([{"associationCategory": "HUBSPOT_DEFINED", "associationTypeId": 16}])
+    );
+
+    // The constants also fit the generated models.
+    let spec: crate::types::AssociationSpec = AssociationSpec::TICKET_TO_CONTACT.into();
+    assert_eq!(
+        spec.association_category,
+        crate::types::AssociationCategory::HubspotDefined
+    );
+}
+
+#[tokio::test]
+async fn test_property_cache_validates_search_requests() {
+    use crate::{
+        objects::ObjectType,
+        properties::{PropertyCache, PropertyError, ValidationError},
+        types::{Filter, FilterGroup, Operator, PublicObjectSearchRequest},
+    };
+    use hubspot_common::test_util::StandIn;
+
+    let stand_in = StandIn::new([(
+        200,
+        "",
+        r#"{"results": [
+            {"name": "email", "label": "Email", "type": "string", "fieldType": "text"},
+            {"name": "createdate", "label": "Created", "type": "datetime", "fieldType": "date"},
+            {"name": "lifecyclestage", "label": "Lifecycle stage", "type": "enumeration",
+             "fieldType": "radio", "options": [{"label": "Lead", "value": "lead"}]}
+        ]}"#,
+    )]);
+    let mut client = crate::Client::new("token");
+    client.set_base_url(stand_in.serve().await);
+    let cache = PropertyCache::new(client.properties());
+
+    let filter = |operator, value: &str| Filter {
+        high_value: None,
+        property_name: "lifecyclestage".to_string(),
+        values: None,
+        value: Some(value.to_string()),
+        operator,
+    };
+    let mut request = PublicObjectSearchRequest {
+        query: None,
+        limit: None,
+        after: None,
+        sorts: Some(vec!["-createdate".to_string()]),
+        properties: Some(vec!["email".to_string()]),
+        filter_groups: Some(vec![FilterGroup {
+            filters: vec![
+                filter(Operator::Eq, "lead"),
+                // Token filters match partial values.
+                filter(Operator::ContainsToken, "le"),
+            ],
+        }]),
+    };
+    cache
+        .validate_search(&ObjectType::Contacts, &request)
+        .await
+        .unwrap();
+
+    request.sorts = Some(vec!["-createdat".to_string()]);
+    request.filter_groups.as_mut().unwrap()[0]
+        .filters
+        .push(filter(Operator::In, "customer"));
+    let err = cache
+        .validate_search(&ObjectType::Contacts, &request)
+        .await
+        .unwrap_err();
+    let ValidationError::InvalidProperties(errors) = err else {
+        panic!("expected invalid properties, got {:?}", err);
+    };
+    assert_eq!(
+        errors,
+        vec![
+            PropertyError::InvalidOption {
+                property: "lifecyclestage".to_string(),
+                value: "customer".to_string(),
+                allowed: vec!["lead".to_string()],
+            },
+            PropertyError::UnknownProperty {
+                property: "createdat".to_string(),
+                suggestion: Some("createdate".to_string()),
+            },
+        ]
+    );
+    // The definitions are fetched once.
+    assert_eq!(
+        stand_in.paths(),
+        vec![("GET".to_string(), "/crm/v3/properties/contacts".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn test_import_streams_file_and_wait_times_out() {
+    use std::time::Duration;
+
+    use crate::{
+        imports::{
+            ColumnMapping, CsvFile, ImportOperation, ImportProblem, ImportRequest, ImportState,
+            WaitError,
+        },
+        objects::ObjectType,
+        properties::{Property, PropertySchema},
+    };
+    use hubspot_common::test_util::StandIn;
+
+    let path = std::env::temp_dir().join(format!("hubspot-import-{}.csv", std::process::id()));
+    std::fs::write(
+        &path,
+        "\u{feff}Email,\"First, Name\"\r\nbob@example.com,Bob\r\n",
+    )
+    .unwrap();
+    let file = CsvFile::open(&path).await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(file.header(), ["Email", "First, Name"]);
+
+    let request = ImportRequest::csv(
+        "List",
+        "list.csv",
+        ImportOperation::Create,
+        vec![
+            ColumnMapping::property("Email", "email"),
+            ColumnMapping::property("Last Name", "lastname"),
+        ],
+    );
+    let properties: Vec<Property> = serde_json::from_str(
+        r#"[{"name": "email", "label": "Email", "type": "string", "fieldType": "text"},
+            {"name": "lastname", "label": "Last name", "type": "string", "fieldType": "text"}]"#,
+    )
+    .unwrap();
+    let schema = PropertySchema::new(ObjectType::Contacts, properties);
+    let client = crate::Client::new("token");
+    assert_eq!(
+        client.imports().validate(&request, &schema, &file),
+        Err(vec![ImportProblem::MissingColumn {
+            column: "Last Name".to_string(),
+            file_name: "list.csv".to_string(),
+        }])
+    );
+
+    let processing = r#"{"id":"9","state":"PROCESSING","createdAt":"2024-01-01T00:00:00Z","updatedAt":"2024-01-01T00:00:00Z"}"#;
+    let stand_in = StandIn::new([
+        (200, "", processing),
+        (200, "", processing),
+        (200, "", processing),
+    ]);
+    let mut client = client;
+    client.set_base_url(stand_in.serve().await);
+    let imports = client.imports();
+
+    let import = imports.start(&request, file).await.unwrap();
+    let upload = &stand_in.requests()[0];
+    assert!(upload.head.contains("content-type: multipart/form-data"));
+    let body = String::from_utf8_lossy(&upload.body);
+    assert!(body.contains("filename=\"list.csv\""));
+    assert!(body.contains("Email,\"First, Name\"\r\nbob@example.com,Bob\r\n"));
+
+    let err = imports
+        .wait(
+            &import.id,
+            Duration::from_millis(10),
+            Duration::from_millis(15),
+        )
+        .await
+        .unwrap_err();
+    let WaitError::TimedOut(import) = err else {
+        panic!("expected a timeout, got {:?}", err);
+    };
+    assert_eq!(import.state, ImportState::Processing);
+    // The upload, then at least one poll.
+    assert!(stand_in.requests().len() >= 2);
+}
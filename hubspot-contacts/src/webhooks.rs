@@ -0,0 +1,45 @@
+//! Verification and parsing of webhook requests.
+//!
+//! HubSpot signs webhook requests with the `X-HubSpot-Signature-v3` header: a
+//! base64 HMAC-SHA256, keyed with the app's client secret, over the request
+//! method, URI, body and the `X-HubSpot-Request-Timestamp` header. Requests
+//! older than five minutes are rejected to prevent replays.
+//!
+//! The verifier reads the client secret from `HUBSPOT_CLIENT_SECRET`, like
+//! [`OAuthApp::new_from_env`](crate::oauth::OAuthApp::new_from_env).
+//!
+//! ```rust,no_run
+//! use hubspot_contacts::webhooks::{WebhookEvent, WebhookVerifier};
+//!
+//! async fn example_webhooks(
+//!     uri: &str,
+//!     headers: &http::HeaderMap,
+//!     body: &[u8],
+//! ) -> anyhow::Result<()> {
+//!     let client = hubspot_contacts::Client::new_from_env();
+//!     let verifier = WebhookVerifier::new_from_env();
+//!
+//!     for event in verifier.verify_and_parse("POST", uri, headers, body)? {
+//!         if let WebhookEvent::ContactCreation(created) = event {
+//!             let contact = client
+//!                 .basic()
+//!                 .get_crm_v_3_objects_contacts_contact_id_get_by_id(
+//!                     None,
+//!                     None,
+//!                     &created.object_id.to_string(),
+//!                     None,
+//!                     None,
+//!                 )
+//!                 .await?;
+//!             println!("{:?}", contact);
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+pub use hubspot_common::webhooks::{
+    parse_events, AssociationChangeEvent, EventMetadata, MergeEvent, ObjectEvent,
+    PropertyChangeEvent, WebhookError, WebhookEvent, WebhookVerifier, MAX_REQUEST_AGE,
+    SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
@@ -0,0 +1,75 @@
+//! Associations (v4) between contacts, tickets and other CRM objects.
+//!
+//! Reading associations is possible through the `associations` parameter of
+//! the basic operations; this module creates, lists and removes them, one at a
+//! time or in batches, and manages custom association labels.
+//!
+//! ```rust,no_run
+//! use hubspot_tickets::{associations::AssociationSpec, objects::ObjectType};
+//!
+//! async fn example_associations() -> anyhow::Result<()> {
+//!     let client = hubspot_tickets::Client::new_from_env();
+//!     client
+//!         .associations()
+//!         .create(
+//!             ObjectType::Tickets,
+//!             "1234",
+//!             ObjectType::Contacts,
+//!             "5678",
+//!             &[AssociationSpec::TICKET_TO_CONTACT],
+//!         )
+//!         .await?;
+//!     Ok(())
+//! }
+//! ```
+
+pub use hubspot_common::associations::{
+    ArchiveAssociationInput, AssociationCategory, AssociationInput, AssociationLabel,
+    AssociationLabelCreate, AssociationLabelUpdate, AssociationSpec, AssociationSpecWithLabel,
+    BatchInput, BatchResponse, CollectionResponseAssociationLabel,
+    CollectionResponseMultiAssociatedObjectWithLabel, DefaultAssociationInput,
+    FetchAssociationsInput, LabelsBetweenObjectPair, MultiAssociatedObjectWithLabel,
+    MultiAssociatedObjects, NextPage, Paging, PublicDefaultAssociation, PublicObjectId,
+    StandardError, Status,
+};
+
+/// Associations between CRM objects.
+pub type Associations = hubspot_common::associations::Associations<crate::types::error::Error>;
+
+impl From<AssociationCategory> for crate::types::AssociationCategory {
+    fn from(category: AssociationCategory) -> Self {
+        match category {
+            AssociationCategory::HubspotDefined => Self::HubspotDefined,
+            AssociationCategory::UserDefined => Self::UserDefined,
+            AssociationCategory::IntegratorDefined => Self::IntegratorDefined,
+        }
+    }
+}
+
+impl From<crate::types::AssociationCategory> for AssociationCategory {
+    fn from(category: crate::types::AssociationCategory) -> Self {
+        match category {
+            crate::types::AssociationCategory::HubspotDefined => Self::HubspotDefined,
+            crate::types::AssociationCategory::UserDefined => Self::UserDefined,
+            crate::types::AssociationCategory::IntegratorDefined => Self::IntegratorDefined,
+        }
+    }
+}
+
+impl From<AssociationSpec> for crate::types::AssociationSpec {
+    fn from(spec: AssociationSpec) -> Self {
+        crate::types::AssociationSpec {
+            association_category: spec.association_category.into(),
+            association_type_id: spec.association_type_id,
+        }
+    }
+}
+
+impl From<crate::types::AssociationSpec> for AssociationSpec {
+    fn from(spec: crate::types::AssociationSpec) -> Self {
+        AssociationSpec {
+            association_category: spec.association_category.into(),
+            association_type_id: spec.association_type_id,
+        }
+    }
+}
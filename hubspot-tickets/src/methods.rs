@@ -1 +1,113 @@
+//! Helpers shared by the hand-written modules.
 
+#[cfg(feature = "requests")]
+pub(crate) use hubspot_common::transport::{check_response, parse_response};
+
+#[cfg(feature = "requests")]
+impl hubspot_common::transport::ClientError for crate::types::error::Error {
+    fn communication(e: reqwest_middleware::Error) -> Self {
+        #[cfg(feature = "retry")]
+        return crate::types::error::Error::CommunicationError(e);
+        #[cfg(not(feature = "retry"))]
+        match e {
+            reqwest_middleware::Error::Reqwest(e) => crate::types::error::Error::RequestError(e),
+            reqwest_middleware::Error::Middleware(e) => {
+                crate::types::error::Error::InvalidRequest(e.to_string())
+            }
+        }
+    }
+
+    fn server(body: String, status: reqwest::StatusCode) -> Self {
+        crate::types::error::Error::Server { body, status }
+    }
+
+    fn deserialize(body: String, e: serde_json::Error, status: reqwest::StatusCode) -> Self {
+        crate::types::error::Error::from_serde_error(
+            format_serde_error::SerdeError::new(body, e),
+            status,
+        )
+    }
+}
+
+#[cfg(feature = "requests")]
+impl crate::Client {
+    /// The base URL, token and middleware stack, for the modules shared
+    /// through `hubspot-common`.
+    pub(crate) fn transport(&self) -> hubspot_common::transport::Transport {
+        hubspot_common::transport::Transport::new(&self.base_url, &self.token, self.client.clone())
+    }
+
+    /// Return a reference to an interface that provides access to Associations operations.
+    pub fn associations(&self) -> crate::associations::Associations {
+        crate::associations::Associations::new(self.transport())
+    }
+
+    /// Return a reference to an interface that provides access to Basic, Batch and
+    /// Search operations on any CRM object type.
+    pub fn objects(&self, object_type: crate::objects::ObjectType) -> crate::objects::Objects {
+        crate::objects::Objects::new(self.transport(), object_type)
+    }
+
+    /// Return a reference to an interface that provides access to ticket Pipelines operations.
+    pub fn pipelines(&self) -> crate::pipelines::Pipelines {
+        crate::pipelines::Pipelines::new(self.clone())
+    }
+
+    /// Return a reference to an interface that provides access to Properties operations.
+    pub fn properties(&self) -> crate::properties::Properties {
+        crate::properties::Properties::new(self.transport())
+    }
+}
+
+#[cfg(feature = "requests")]
+#[cfg(feature = "retry")]
+#[cfg(not(target_arch = "wasm32"))]
+impl crate::Client {
+    /// Append a middleware to the stacks of the client, after the tracing and
+    /// retries installed by the generated constructors.
+    fn with_middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: reqwest_middleware::Middleware + Clone,
+    {
+        self.client_http1_only = hubspot_common::transport::append_middleware(
+            self.client_http1_only,
+            middleware.clone(),
+        );
+        self.client = hubspot_common::transport::append_middleware(self.client, middleware);
+        self
+    }
+
+    /// Schedule every request of the client with a rate limiter.
+    ///
+    /// The limiter runs inside the retries, so they never see a `429`. The
+    /// clones of the returned client share it, including the handles returned
+    /// by its module accessors, and the same limiter can be set on several
+    /// clients that use the same app.
+    ///
+    /// ```rust,no_run
+    /// use hubspot_tickets::rate_limit::RateLimiter;
+    ///
+    /// let client = hubspot_tickets::Client::new_from_env().with_rate_limiter(RateLimiter::default());
+    /// ```
+    pub fn with_rate_limiter(self, limiter: crate::rate_limit::RateLimiter) -> Self {
+        self.with_middleware(limiter)
+    }
+
+    /// Create a client that sends the access token of an OAuth session,
+    /// refreshed once it expires. The clones of the client share the session.
+    ///
+    /// ```rust,no_run
+    /// use hubspot_tickets::oauth::{AccessToken, OAuthApp, Session};
+    ///
+    /// fn example_session(stored: &AccessToken) {
+    ///     let session = Session::new(OAuthApp::new_from_env(), stored);
+    ///     let client = hubspot_tickets::Client::new_from_session(&session);
+    ///     // HubSpot may rotate the refresh token, so persist it again later on.
+    ///     let _refresh_token = session.refresh_token();
+    /// }
+    /// ```
+    #[tracing::instrument(skip_all)]
+    pub fn new_from_session(session: &crate::oauth::Session) -> Self {
+        Self::new(session.access_token().unwrap_or_default()).with_middleware(session.clone())
+    }
+}
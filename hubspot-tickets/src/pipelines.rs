@@ -0,0 +1,308 @@
+//! Ticket pipelines and stage transitions.
+//!
+//! The `hs_pipeline` and `hs_pipeline_stage` properties of a ticket hold
+//! opaque IDs. A [`PipelineCache`] fetches the ticket pipelines once, resolves
+//! pipeline and stage labels to those IDs, and moves tickets between stages of
+//! their pipeline.
+//!
+//! ```rust,no_run
+//! use hubspot_tickets::pipelines::PipelineCache;
+//!
+//! async fn example_pipelines() -> anyhow::Result<()> {
+//!     let client = hubspot_tickets::Client::new_from_env();
+//!     let pipelines = PipelineCache::new(client);
+//!
+//!     let ticket = pipelines
+//!         .move_to_stage("1234", "Waiting on customer")
+//!         .await?;
+//!     println!("{:?}", ticket.properties.get("hs_pipeline_stage"));
+//!     Ok(())
+//! }
+//! ```
+
+use std::sync::Arc;
+
+use crate::{methods::parse_response, Client};
+
+/// A stage of a pipeline.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct PipelineStage {
+    pub id: String,
+    pub label: String,
+    #[serde(rename = "displayOrder")]
+    pub display_order: i32,
+    #[serde(default)]
+    pub archived: bool,
+    /// Stage metadata. For tickets `ticketState` is `OPEN` or `CLOSED`.
+    #[serde(default)]
+    pub metadata: std::collections::HashMap<String, String>,
+    #[serde(rename = "createdAt", default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(rename = "updatedAt", default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl PipelineStage {
+    /// Whether tickets in this stage are closed.
+    pub fn is_closed(&self) -> bool {
+        self.metadata
+            .get("ticketState")
+            .map(|s| s == "CLOSED")
+            .unwrap_or(false)
+    }
+
+    fn matches(&self, id_or_label: &str) -> bool {
+        self.id == id_or_label || self.label.eq_ignore_ascii_case(id_or_label)
+    }
+}
+
+/// A pipeline and its stages.
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct Pipeline {
+    pub id: String,
+    pub label: String,
+    #[serde(rename = "displayOrder")]
+    pub display_order: i32,
+    #[serde(default)]
+    pub archived: bool,
+    pub stages: Vec<PipelineStage>,
+    #[serde(rename = "createdAt", default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(rename = "updatedAt", default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Pipeline {
+    /// Find a stage of this pipeline by ID or by label, ignoring case.
+    pub fn stage(&self, id_or_label: &str) -> Option<&PipelineStage> {
+        self.stages
+            .iter()
+            .find(|s| s.id == id_or_label)
+            .or_else(|| self.stages.iter().find(|s| s.matches(id_or_label)))
+    }
+
+    fn matches(&self, id_or_label: &str) -> bool {
+        self.id == id_or_label || self.label.eq_ignore_ascii_case(id_or_label)
+    }
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct CollectionResponsePipeline {
+    pub results: Vec<Pipeline>,
+}
+
+#[derive(
+    serde :: Serialize, serde :: Deserialize, PartialEq, Debug, Clone, schemars :: JsonSchema,
+)]
+pub struct CollectionResponsePipelineStage {
+    pub results: Vec<PipelineStage>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Pipelines {
+    pub client: Client,
+}
+
+impl Pipelines {
+    #[doc(hidden)]
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    /// Read all ticket pipelines with their stages.
+    #[tracing::instrument]
+    pub async fn list(&self) -> Result<CollectionResponsePipeline, crate::types::error::Error> {
+        let mut req = self.client.client.request(
+            http::Method::GET,
+            format!("{}/crm/v3/pipelines/tickets", self.client.base_url),
+        );
+        req = req.bearer_auth(&self.client.token);
+        parse_response(req.send().await?).await
+    }
+
+    /// Read a ticket pipeline by ID.
+    #[tracing::instrument]
+    pub async fn get(&self, pipeline_id: &str) -> Result<Pipeline, crate::types::error::Error> {
+        let mut req = self.client.client.request(
+            http::Method::GET,
+            format!(
+                "{}/crm/v3/pipelines/tickets/{}",
+                self.client.base_url, pipeline_id
+            ),
+        );
+        req = req.bearer_auth(&self.client.token);
+        parse_response(req.send().await?).await
+    }
+
+    /// Read the stages of a ticket pipeline.
+    #[tracing::instrument]
+    pub async fn list_stages(
+        &self,
+        pipeline_id: &str,
+    ) -> Result<CollectionResponsePipelineStage, crate::types::error::Error> {
+        let mut req = self.client.client.request(
+            http::Method::GET,
+            format!(
+                "{}/crm/v3/pipelines/tickets/{}/stages",
+                self.client.base_url, pipeline_id
+            ),
+        );
+        req = req.bearer_auth(&self.client.token);
+        parse_response(req.send().await?).await
+    }
+}
+
+/// Ticket pipelines fetched once and shared by every clone of the cache.
+#[derive(Clone, Debug)]
+pub struct PipelineCache {
+    pipelines: Pipelines,
+    cached: Arc<tokio::sync::RwLock<Option<Arc<Vec<Pipeline>>>>>,
+}
+
+impl PipelineCache {
+    /// Create an empty cache that fetches pipelines through the client.
+    pub fn new(client: Client) -> Self {
+        PipelineCache {
+            pipelines: Pipelines::new(client),
+            cached: Default::default(),
+        }
+    }
+
+    /// All ticket pipelines, fetched on first use.
+    pub async fn pipelines(&self) -> Result<Arc<Vec<Pipeline>>, crate::types::error::Error> {
+        if let Some(pipelines) = self.cached.read().await.as_ref() {
+            return Ok(pipelines.clone());
+        }
+
+        let pipelines = Arc::new(self.pipelines.list().await?.results);
+        *self.cached.write().await = Some(pipelines.clone());
+        Ok(pipelines)
+    }
+
+    /// Drop the cached pipelines, e.g. after stages were added or renamed.
+    pub async fn invalidate(&self) {
+        *self.cached.write().await = None;
+    }
+
+    /// Find a pipeline by ID or by label, ignoring case.
+    pub async fn pipeline(
+        &self,
+        id_or_label: &str,
+    ) -> Result<Pipeline, crate::types::error::Error> {
+        let pipelines = self.pipelines().await?;
+        pipelines
+            .iter()
+            .find(|p| p.id == id_or_label)
+            .or_else(|| pipelines.iter().find(|p| p.matches(id_or_label)))
+            .cloned()
+            .ok_or_else(|| {
+                crate::types::error::Error::InvalidRequest(format!(
+                    "unknown ticket pipeline `{}`, expected one of: {}",
+                    id_or_label,
+                    itertools::join(pipelines.iter().map(|p| &p.label), ", ")
+                ))
+            })
+    }
+
+    /// Resolve a stage ID or label to the stage of a pipeline.
+    ///
+    /// The pipelines are fetched again once if the stage is not cached, so
+    /// stages created since the cache was filled are found.
+    pub async fn stage(
+        &self,
+        pipeline_id: &str,
+        stage: &str,
+    ) -> Result<PipelineStage, crate::types::error::Error> {
+        if let Some(found) = self.pipeline(pipeline_id).await?.stage(stage) {
+            return Ok(found.clone());
+        }
+
+        self.invalidate().await;
+        let pipeline = self.pipeline(pipeline_id).await?;
+        if let Some(found) = pipeline.stage(stage) {
+            return Ok(found.clone());
+        }
+
+        let mut message = format!(
+            "stage `{}` does not belong to ticket pipeline `{}`, expected one of: {}",
+            stage,
+            pipeline.label,
+            itertools::join(pipeline.stages.iter().map(|s| &s.label), ", ")
+        );
+        let elsewhere = self
+            .pipelines()
+            .await?
+            .iter()
+            .filter(|p| p.stage(stage).is_some())
+            .map(|p| p.label.clone())
+            .collect::<Vec<_>>();
+        if !elsewhere.is_empty() {
+            message.push_str(&format!(
+                " (found in pipeline {})",
+                itertools::join(elsewhere, ", ")
+            ));
+        }
+        Err(crate::types::error::Error::InvalidRequest(message))
+    }
+
+    /// Move a ticket to another stage of its pipeline.
+    ///
+    /// The stage is given by ID or by label. The ticket is not updated when the
+    /// stage does not belong to the pipeline the ticket is in.
+    #[tracing::instrument]
+    pub async fn move_to_stage(
+        &self,
+        ticket_id: &str,
+        stage: &str,
+    ) -> Result<crate::types::SimplePublicObject, crate::types::error::Error> {
+        let ticket = self
+            .pipelines
+            .client
+            .basic()
+            .get_crm_v_3_objects_tickets_ticket_id_get_by_id(
+                crate::basic::GetCrmV3ObjectsTicketsTicketIdGetByIdParams {
+                    archived: None,
+                    associations: None,
+                    id_property: None,
+                    properties: Some(vec!["hs_pipeline".to_string()]),
+                    properties_with_history: None,
+                    ticket_id,
+                },
+            )
+            .await?;
+        let pipeline_id = ticket
+            .properties
+            .get("hs_pipeline")
+            .cloned()
+            .flatten()
+            .ok_or_else(|| {
+                crate::types::error::Error::InvalidRequest(format!(
+                    "ticket `{}` is not in a pipeline",
+                    ticket_id
+                ))
+            })?;
+
+        let stage = self.stage(&pipeline_id, stage).await?;
+        self.pipelines
+            .client
+            .basic()
+            .patch_crm_v_3_objects_tickets_ticket_id_update(
+                None,
+                ticket_id,
+                &crate::types::SimplePublicObjectInput {
+                    properties: std::collections::HashMap::from([(
+                        "hs_pipeline_stage".to_string(),
+                        stage.id,
+                    )]),
+                    object_write_trace_id: None,
+                },
+            )
+            .await
+    }
+}
@@ -1 +1,258 @@
+#[test]
+fn test_pipeline_stage_by_id_or_label() {
+    let pipeline: crate::pipelines::Pipeline = serde_json::from_str(
+        r#"{
+            "id": "0",
+            "label": "Support Pipeline",
+            "displayOrder": 0,
+            "archived": false,
+            "stages": [
+                {"id": "1", "label": "New", "displayOrder": 0, "metadata": {"ticketState": "OPEN"}},
+                {"id": "2", "label": "Waiting on contact", "displayOrder": 1, "metadata": {"ticketState": "OPEN"}},
+                {"id": "4", "label": "Closed", "displayOrder": 3, "metadata": {"ticketState": "CLOSED"}}
+            ]
+        }"#,
+    )
+    .unwrap();
 
+    assert_eq!(pipeline.stage("waiting on contact").unwrap().id, "2");
+    assert_eq!(pipeline.stage("4").unwrap().label, "Closed");
+    assert!(pipeline.stage("4").unwrap().is_closed());
+    assert!(pipeline.stage("Waiting on customer").is_none());
+}
+
+#[tokio::test]
+async fn test_objects_requests() {
+    use crate::objects::{GetByIdParams, ObjectType};
+    use hubspot_common::test_util::StandIn;
+
+    let pet = r#"{"id":"42","properties":{"name":"Rex"},"createdAt":"2024-01-01T00:00:00Z","updatedAt":"2024-01-02T00:00:00Z"}"#;
+    let stand_in = StandIn::new([
+        (200, "", pet),
+        (
+            201,
+            "",
+            &*format!(
+                r#"{{"status":"COMPLETE","results":[{}],"startedAt":"2024-01-01T00:00:00Z","completedAt":"2024-01-01T00:00:01Z"}}"#,
+                pet
+            ),
+        ),
+        (204, "", ""),
+    ]);
+    let mut client = crate::Client::new("token");
+    client.set_base_url(stand_in.serve().await);
+    let pets = client.objects("2-1234567".parse().unwrap());
+    assert_eq!(
+        pets.object_type,
+        ObjectType::Custom("2-1234567".to_string())
+    );
+
+    let found = pets
+        .get_by_id(
+            "Rex",
+            GetByIdParams {
+                id_property: Some("name".to_string()),
+                associations: Some(vec!["tickets".to_string()]),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(found.id, "42");
+
+    let created = pets
+        .batch_create(&crate::types::BatchInputSimplePublicObjectInputForCreate {
+            inputs: vec![crate::types::SimplePublicObjectInputForCreate {
+                associations: vec![],
+                object_write_trace_id: None,
+                properties: std::collections::HashMap::from([(
+                    "name".to_string(),
+                    "Rex".to_string(),
+                )]),
+            }],
+        })
+        .await
+        .unwrap();
+    assert_eq!(created.results.len(), 1);
+
+    pets.archive("42").await.unwrap();
+    // The stand-in answers `404` once its responses run out.
+    let err = pets.get_by_id("42", Default::default()).await.unwrap_err();
+    assert_eq!(err.status(), Some(reqwest::StatusCode::NOT_FOUND));
+
+    assert_eq!(
+        stand_in.paths(),
+        vec![
+            (
+                "GET".to_string(),
+                "/crm/v3/objects/2-1234567/Rex?associations=tickets&idProperty=name".to_string()
+            ),
+            (
+                "POST".to_string(),
+                "/crm/v3/objects/2-1234567/batch/create".to_string()
+            ),
+            (
+                "DELETE".to_string(),
+                "/crm/v3/objects/2-1234567/42".to_string()
+            ),
+            (
+                "GET".to_string(),
+                "/crm/v3/objects/2-1234567/42".to_string()
+            ),
+        ]
+    );
+    assert_eq!(
+        stand_in.requests()[1].json(),
+        serde_json::json!({"inputs": [{"associations": [], "properties": {"name": "Rex"}}]})
+    );
+}
+
+#[test]
+fn test_association_spec_converts_to_generated_model() {
+    use crate::associations::AssociationSpec;
+
+    let spec: crate::types::AssociationSpec = AssociationSpec::user_defined(12).into();
+    assert_eq!(
+        spec,
+        crate::types::AssociationSpec {
+            association_category: crate::types::AssociationCategory::UserDefined,
+            association_type_id: 12,
+        }
+    );
+    assert_eq!(
+        AssociationSpec::from(spec),
+        AssociationSpec::user_defined(12)
+    );
+}
+
+#[tokio::test]
+async fn test_property_cache_validates_inputs() {
+    use crate::{
+        objects::ObjectType,
+        properties::{PropertyCache, PropertyError, ValidationError},
+        types::{BatchInputSimplePublicObjectBatchInput, SimplePublicObjectBatchInput},
+    };
+    use hubspot_common::test_util::StandIn;
+
+    let stand_in = StandIn::new([(
+        200,
+        "",
+        r#"{"results": [
+            {"name": "subject", "label": "Ticket name", "type": "string", "fieldType": "text"},
+            {"name": "hs_ticket_priority", "label": "Priority", "type": "enumeration",
+             "fieldType": "select", "options": [{"label": "High", "value": "HIGH"}]},
+            {"name": "time_to_close", "label": "Time to close", "type": "number",
+             "fieldType": "calculation_equation", "calculated": true}
+        ]}"#,
+    )]);
+    let mut client = crate::Client::new("token");
+    client.set_base_url(stand_in.serve().await);
+    let cache = PropertyCache::new(client.properties());
+
+    let input = |properties: &[(&str, &str)]| SimplePublicObjectBatchInput {
+        id_property: None,
+        object_write_trace_id: None,
+        id: "1".to_string(),
+        properties: properties
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+    };
+    let err = cache
+        .validate(
+            &ObjectType::Tickets,
+            &BatchInputSimplePublicObjectBatchInput {
+                inputs: vec![
+                    input(&[
+                        ("subject", "Printer on fire"),
+                        ("hs_ticket_priority", "LOW"),
+                    ]),
+                    input(&[("hs_ticket_priority", "LOW"), ("time_to_close", "1")]),
+                ],
+            },
+        )
+        .await
+        .unwrap_err();
+    // Each problem is listed once.
+    assert_eq!(
+        err.invalid_properties().unwrap(),
+        [
+            PropertyError::InvalidOption {
+                property: "hs_ticket_priority".to_string(),
+                value: "LOW".to_string(),
+                allowed: vec!["HIGH".to_string()],
+            },
+            PropertyError::ReadOnly {
+                property: "time_to_close".to_string(),
+            },
+        ]
+    );
+
+    // A failed fetch is not reported as invalid properties.
+    cache.invalidate(&ObjectType::Tickets).await;
+    let err = cache
+        .validate(
+            &ObjectType::Tickets,
+            &BatchInputSimplePublicObjectBatchInput { inputs: vec![] },
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ValidationError::Request(_)));
+}
+
+#[test]
+fn test_webhook_verify_and_parse_ticket_events() {
+    use crate::webhooks::{
+        WebhookError, WebhookEvent, WebhookVerifier, SIGNATURE_HEADER, TIMESTAMP_HEADER,
+    };
+
+    std::env::set_var("HUBSPOT_CLIENT_SECRET", "app-secret");
+    let verifier = WebhookVerifier::new_from_env();
+    let uri = "https://example.com/hubspot/tickets";
+    let body = br#"[
+        {"eventId": 1, "subscriptionId": 2, "portalId": 3, "appId": 4, "occurredAt": 1700000000000,
+         "subscriptionType": "ticket.propertyChange", "attemptNumber": 0, "objectId": 55,
+         "propertyName": "hs_pipeline_stage", "propertyValue": "4"},
+        {"eventId": 5, "subscriptionId": 6, "portalId": 3, "appId": 4, "occurredAt": 1700000000000,
+         "subscriptionType": "ticket.associationChange", "attemptNumber": 0,
+         "associationType": "TICKET_TO_CONTACT", "fromObjectId": 55, "toObjectId": 77,
+         "associationRemoved": false}
+    ]"#;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis()
+        .to_string();
+    let mut headers = http::HeaderMap::new();
+    headers.insert(TIMESTAMP_HEADER, timestamp.parse().unwrap());
+    headers.insert(
+        SIGNATURE_HEADER,
+        WebhookVerifier::new("app-secret")
+            .sign("POST", uri, body, &timestamp)
+            .parse()
+            .unwrap(),
+    );
+
+    let events = verifier
+        .verify_and_parse("POST", uri, &headers, body)
+        .unwrap();
+    assert_eq!(events.len(), 2);
+    let WebhookEvent::TicketPropertyChange(change) = &events[0] else {
+        panic!("expected a property change, got {:?}", events[0]);
+    };
+    assert_eq!(change.property_name, "hs_pipeline_stage");
+    assert_eq!(change.property_value, "4");
+    assert_eq!(events[1].subscription_type(), "ticket.associationChange");
+    assert_eq!(events[1].object_id().as_deref(), Some("55"));
+
+    // Another app's secret does not match.
+    assert!(matches!(
+        WebhookVerifier::new("other-secret").verify_and_parse("POST", uri, &headers, body),
+        Err(WebhookError::InvalidSignature)
+    ));
+    headers.remove(SIGNATURE_HEADER);
+    assert!(matches!(
+        verifier.verify_and_parse("POST", uri, &headers, body),
+        Err(WebhookError::MissingHeader(SIGNATURE_HEADER))
+    ));
+}
@@ -0,0 +1,49 @@
+//! Verification and parsing of webhook requests.
+//!
+//! HubSpot signs webhook requests with the `X-HubSpot-Signature-v3` header: a
+//! base64 HMAC-SHA256, keyed with the app's client secret, over the request
+//! method, URI, body and the `X-HubSpot-Request-Timestamp` header. Requests
+//! older than five minutes are rejected to prevent replays.
+//!
+//! The verifier reads the client secret from `HUBSPOT_CLIENT_SECRET`, like
+//! [`OAuthApp::new_from_env`](crate::oauth::OAuthApp::new_from_env).
+//!
+//! ```rust,no_run
+//! use hubspot_tickets::webhooks::{WebhookEvent, WebhookVerifier};
+//!
+//! async fn example_webhooks(
+//!     uri: &str,
+//!     headers: &http::HeaderMap,
+//!     body: &[u8],
+//! ) -> anyhow::Result<()> {
+//!     let client = hubspot_tickets::Client::new_from_env();
+//!     let verifier = WebhookVerifier::new_from_env();
+//!
+//!     for event in verifier.verify_and_parse("POST", uri, headers, body)? {
+//!         if let WebhookEvent::TicketCreation(created) = event {
+//!             let ticket_id = created.object_id.to_string();
+//!             let ticket = client
+//!                 .basic()
+//!                 .get_crm_v_3_objects_tickets_ticket_id_get_by_id(
+//!                     hubspot_tickets::basic::GetCrmV3ObjectsTicketsTicketIdGetByIdParams {
+//!                         archived: None,
+//!                         associations: None,
+//!                         id_property: None,
+//!                         properties: None,
+//!                         properties_with_history: None,
+//!                         ticket_id: &ticket_id,
+//!                     },
+//!                 )
+//!                 .await?;
+//!             println!("{:?}", ticket);
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+pub use hubspot_common::webhooks::{
+    parse_events, AssociationChangeEvent, EventMetadata, MergeEvent, ObjectEvent,
+    PropertyChangeEvent, WebhookError, WebhookEvent, WebhookVerifier, MAX_REQUEST_AGE,
+    SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
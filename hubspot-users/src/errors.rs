@@ -0,0 +1,38 @@
+//! Errors the generated [`Error`] has no variant for.
+//!
+//! A request that stays rate limited fails with a [`RateLimitExceeded`]
+//! inside the generated error. [`ErrorExt::rate_limited`] finds it, whether
+//! it came from a generated or a hand-written method:
+//!
+//! ```rust,no_run
+//! use hubspot_users::errors::ErrorExt;
+//!
+//! async fn example_rate_limited() -> anyhow::Result<()> {
+//!     let client = hubspot_users::Client::new_from_env();
+//!     let page = client.roles().get_settings_v_3_users_get_all().await;
+//!     match page {
+//!         Err(err) if err.rate_limited().is_some() => {
+//!             println!("try again in {:?}", err.retry_after());
+//!         }
+//!         result => println!("{:?}", result?),
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+pub use hubspot_common::errors::{ErrorExt, RateLimitErrorBody, RateLimitExceeded};
+
+use crate::types::error::Error;
+
+impl ErrorExt for Error {
+    fn rate_limited(&self) -> Option<RateLimitExceeded> {
+        match self {
+            #[cfg(feature = "retry")]
+            Error::CommunicationError(e) => RateLimitExceeded::from_middleware_error(e),
+            Error::Server { body, status } if *status == reqwest::StatusCode::TOO_MANY_REQUESTS => {
+                Some(RateLimitExceeded::from_body(None, body))
+            }
+            _ => None,
+        }
+    }
+}
@@ -57,8 +57,18 @@
 #![allow(clippy::too_many_arguments)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+#[cfg(feature = "requests")]
+pub mod errors;
 mod methods;
 #[cfg(feature = "requests")]
+pub mod oauth;
+#[cfg(feature = "requests")]
+#[cfg(feature = "retry")]
+#[cfg(not(target_arch = "wasm32"))]
+pub use hubspot_common::rate_limit;
+#[cfg(feature = "requests")]
+pub mod reconcile;
+#[cfg(feature = "requests")]
 pub mod roles;
 #[cfg(feature = "requests")]
 pub mod teams;
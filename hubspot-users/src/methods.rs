@@ -1 +1,63 @@
+//! Methods of the client for the hand-written modules.
 
+#[cfg(feature = "requests")]
+impl crate::Client {
+    /// Return a reference to an interface that reconciles the users of the portal
+    /// with a desired list.
+    pub fn reconcile(&self) -> crate::reconcile::Reconcile {
+        crate::reconcile::Reconcile::new(self.clone())
+    }
+}
+
+#[cfg(feature = "requests")]
+#[cfg(feature = "retry")]
+#[cfg(not(target_arch = "wasm32"))]
+impl crate::Client {
+    /// Append a middleware to the stacks of the client, after the tracing and
+    /// retries installed by the generated constructors.
+    fn with_middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: reqwest_middleware::Middleware + Clone,
+    {
+        self.client_http1_only = hubspot_common::transport::append_middleware(
+            self.client_http1_only,
+            middleware.clone(),
+        );
+        self.client = hubspot_common::transport::append_middleware(self.client, middleware);
+        self
+    }
+
+    /// Schedule every request of the client with a rate limiter.
+    ///
+    /// The limiter runs inside the retries, so they never see a `429`. The
+    /// clones of the returned client share it, including the handles returned
+    /// by its module accessors, and the same limiter can be set on several
+    /// clients that use the same app.
+    ///
+    /// ```rust,no_run
+    /// use hubspot_users::rate_limit::RateLimiter;
+    ///
+    /// let client = hubspot_users::Client::new_from_env().with_rate_limiter(RateLimiter::default());
+    /// ```
+    pub fn with_rate_limiter(self, limiter: crate::rate_limit::RateLimiter) -> Self {
+        self.with_middleware(limiter)
+    }
+
+    /// Create a client that sends the access token of an OAuth session,
+    /// refreshed once it expires. The clones of the client share the session.
+    ///
+    /// ```rust,no_run
+    /// use hubspot_users::oauth::{AccessToken, OAuthApp, Session};
+    ///
+    /// fn example_session(stored: &AccessToken) {
+    ///     let session = Session::new(OAuthApp::new_from_env(), stored);
+    ///     let client = hubspot_users::Client::new_from_session(&session);
+    ///     // HubSpot may rotate the refresh token, so persist it again later on.
+    ///     let _refresh_token = session.refresh_token();
+    /// }
+    /// ```
+    #[tracing::instrument(skip_all)]
+    pub fn new_from_session(session: &crate::oauth::Session) -> Self {
+        Self::new(session.access_token().unwrap_or_default()).with_middleware(session.clone())
+    }
+}
@@ -0,0 +1,550 @@
+//! Declarative reconciliation of the users of a portal.
+//!
+//! Describe the users a portal should have, with their role and teams given by
+//! name, and [`Reconcile::plan`] compares them with the live portal. The
+//! resulting [`Plan`] prints as a dry-run diff and is carried out by
+//! [`Reconcile::apply`].
+//!
+//! ```rust,no_run
+//! use hubspot_users::reconcile::{DesiredUser, ReconcileOptions};
+//!
+//! async fn example_reconcile() -> anyhow::Result<()> {
+//!     let client = hubspot_users::Client::new_from_env();
+//!     let desired = vec![DesiredUser {
+//!         email: "alice@example.com".to_string(),
+//!         role: Some("Sales Rep".to_string()),
+//!         primary_team: Some("EMEA".to_string()),
+//!         secondary_teams: Some(vec!["Partners".to_string()]),
+//!         ..Default::default()
+//!     }];
+//!
+//!     let plan = client
+//!         .reconcile()
+//!         .plan(&desired, &ReconcileOptions::default())
+//!         .await?;
+//!     println!("{}", plan);
+//!
+//!     for outcome in client.reconcile().apply(&plan).await {
+//!         if let Err(err) = outcome.result {
+//!             eprintln!("{}: {}", outcome.change.email(), err);
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    types::{PublicPermissionSet, PublicTeam, PublicUser, PublicUserUpdate, UserProvisionRequest},
+    Client,
+};
+
+/// A user as the portal should have them.
+///
+/// Fields left as `None` are not managed: they are not set on new users and
+/// not changed on existing ones.
+#[derive(
+    serde :: Serialize,
+    serde :: Deserialize,
+    PartialEq,
+    Debug,
+    Clone,
+    Default,
+    schemars :: JsonSchema,
+)]
+pub struct DesiredUser {
+    pub email: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_name: Option<String>,
+    /// The name or ID of the role.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    /// The name or ID of the primary team.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary_team: Option<String>,
+    /// The names or IDs of the secondary teams.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secondary_teams: Option<Vec<String>>,
+}
+
+/// Options for computing a [`Plan`].
+#[derive(Debug, Clone, Default)]
+pub struct ReconcileOptions {
+    /// Remove live users that are not in the desired list. Super admins are
+    /// never removed.
+    pub remove_unlisted: bool,
+    /// Emails of users that are never removed.
+    pub keep: Vec<String>,
+    /// Send new users a welcome email.
+    pub send_welcome_email: bool,
+}
+
+/// The roles and teams of a portal, used to resolve names to IDs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Directory {
+    pub roles: Vec<PublicPermissionSet>,
+    pub teams: Vec<PublicTeam>,
+}
+
+impl Directory {
+    /// Resolve a role name, ignoring case, or ID to a role ID.
+    pub fn role_id(&self, name: &str) -> Result<String, crate::types::error::Error> {
+        self.roles
+            .iter()
+            .find(|r| r.id == name)
+            .or_else(|| {
+                self.roles
+                    .iter()
+                    .find(|r| r.name.eq_ignore_ascii_case(name))
+            })
+            .map(|r| r.id.clone())
+            .ok_or_else(|| {
+                crate::types::error::Error::InvalidRequest(format!(
+                    "unknown role `{}`, expected one of: {}",
+                    name,
+                    itertools::join(self.roles.iter().map(|r| &r.name), ", ")
+                ))
+            })
+    }
+
+    /// Resolve a team name, ignoring case, or ID to a team ID.
+    pub fn team_id(&self, name: &str) -> Result<String, crate::types::error::Error> {
+        self.teams
+            .iter()
+            .find(|t| t.id == name)
+            .or_else(|| {
+                self.teams
+                    .iter()
+                    .find(|t| t.name.eq_ignore_ascii_case(name))
+            })
+            .map(|t| t.id.clone())
+            .ok_or_else(|| {
+                crate::types::error::Error::InvalidRequest(format!(
+                    "unknown team `{}`, expected one of: {}",
+                    name,
+                    itertools::join(self.teams.iter().map(|t| &t.name), ", ")
+                ))
+            })
+    }
+
+    /// The name of a role, or its ID when the role is unknown.
+    pub fn role_name(&self, id: &str) -> String {
+        self.roles
+            .iter()
+            .find(|r| r.id == id)
+            .map(|r| r.name.clone())
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    /// The name of a team, or its ID when the team is unknown.
+    pub fn team_name(&self, id: &str) -> String {
+        self.teams
+            .iter()
+            .find(|t| t.id == id)
+            .map(|t| t.name.clone())
+            .unwrap_or_else(|| id.to_string())
+    }
+}
+
+/// A change of one field of a user, by display value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub from: String,
+    pub to: String,
+}
+
+/// One step of a [`Plan`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Create {
+        email: String,
+        request: UserProvisionRequest,
+        fields: Vec<FieldChange>,
+    },
+    Update {
+        user_id: String,
+        email: String,
+        request: PublicUserUpdate,
+        fields: Vec<FieldChange>,
+    },
+    Remove {
+        user_id: String,
+        email: String,
+    },
+}
+
+impl Change {
+    /// The email of the user the change applies to.
+    pub fn email(&self) -> &str {
+        match self {
+            Change::Create { email, .. }
+            | Change::Update { email, .. }
+            | Change::Remove { email, .. } => email,
+        }
+    }
+}
+
+/// The changes that bring the live portal in line with the desired users.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Plan {
+    pub changes: Vec<Change>,
+    /// Emails of desired users that already match.
+    pub unchanged: Vec<String>,
+    /// Emails of unlisted users that are kept, e.g. super admins.
+    pub kept: Vec<String>,
+}
+
+impl Plan {
+    /// Compare the desired users with the live users of a portal.
+    pub fn compute(
+        desired: &[DesiredUser],
+        live: &[PublicUser],
+        directory: &Directory,
+        options: &ReconcileOptions,
+    ) -> Result<Self, crate::types::error::Error> {
+        let mut plan = Plan::default();
+        let live_by_email: HashMap<String, &PublicUser> =
+            live.iter().map(|u| (u.email.to_lowercase(), u)).collect();
+
+        let mut seen = BTreeSet::new();
+        for user in desired {
+            let email = user.email.to_lowercase();
+            if !seen.insert(email.clone()) {
+                return Err(crate::types::error::Error::InvalidRequest(format!(
+                    "user `{}` is listed more than once",
+                    user.email
+                )));
+            }
+
+            let role_id = user
+                .role
+                .as_deref()
+                .map(|r| directory.role_id(r))
+                .transpose()?;
+            let primary_team_id = user
+                .primary_team
+                .as_deref()
+                .map(|t| directory.team_id(t))
+                .transpose()?;
+            let secondary_team_ids = user
+                .secondary_teams
+                .as_ref()
+                .map(|teams| {
+                    teams
+                        .iter()
+                        .map(|t| directory.team_id(t))
+                        .collect::<Result<BTreeSet<_>, _>>()
+                })
+                .transpose()?;
+
+            match live_by_email.get(&email) {
+                None => {
+                    let mut fields = vec![];
+                    diff_field(&mut fields, "first name", None, user.first_name.as_deref());
+                    diff_field(&mut fields, "last name", None, user.last_name.as_deref());
+                    if let Some(id) = &role_id {
+                        diff_field(&mut fields, "role", None, Some(&directory.role_name(id)));
+                    }
+                    if let Some(id) = &primary_team_id {
+                        diff_field(
+                            &mut fields,
+                            "primary team",
+                            None,
+                            Some(&directory.team_name(id)),
+                        );
+                    }
+                    if let Some(ids) = &secondary_team_ids {
+                        diff_field(
+                            &mut fields,
+                            "secondary teams",
+                            None,
+                            Some(&team_names(directory, ids.iter())),
+                        );
+                    }
+                    plan.changes.push(Change::Create {
+                        email: user.email.clone(),
+                        request: UserProvisionRequest {
+                            first_name: user.first_name.clone(),
+                            last_name: user.last_name.clone(),
+                            primary_team_id,
+                            send_welcome_email: Some(options.send_welcome_email),
+                            role_id,
+                            secondary_team_ids: secondary_team_ids
+                                .map(|ids| ids.into_iter().collect()),
+                            email: user.email.clone(),
+                        },
+                        fields,
+                    });
+                }
+                Some(live) => {
+                    let mut fields = vec![];
+                    let mut request = PublicUserUpdate {
+                        first_name: None,
+                        last_name: None,
+                        primary_team_id: None,
+                        role_id: None,
+                        secondary_team_ids: None,
+                    };
+                    if let Some(name) = &user.first_name {
+                        if diff_field(
+                            &mut fields,
+                            "first name",
+                            live.first_name.as_deref(),
+                            Some(name),
+                        ) {
+                            request.first_name = Some(name.clone());
+                        }
+                    }
+                    if let Some(name) = &user.last_name {
+                        if diff_field(
+                            &mut fields,
+                            "last name",
+                            live.last_name.as_deref(),
+                            Some(name),
+                        ) {
+                            request.last_name = Some(name.clone());
+                        }
+                    }
+                    if let Some(id) = role_id {
+                        if live.role_id.as_deref() != Some(&id) {
+                            diff_field(
+                                &mut fields,
+                                "role",
+                                live.role_id
+                                    .as_deref()
+                                    .map(|r| directory.role_name(r))
+                                    .as_deref(),
+                                Some(&directory.role_name(&id)),
+                            );
+                            request.role_id = Some(id);
+                        }
+                    }
+                    if let Some(id) = primary_team_id {
+                        if live.primary_team_id.as_deref() != Some(&id) {
+                            diff_field(
+                                &mut fields,
+                                "primary team",
+                                live.primary_team_id
+                                    .as_deref()
+                                    .map(|t| directory.team_name(t))
+                                    .as_deref(),
+                                Some(&directory.team_name(&id)),
+                            );
+                            request.primary_team_id = Some(id);
+                        }
+                    }
+                    if let Some(ids) = secondary_team_ids {
+                        let current: BTreeSet<String> =
+                            live.secondary_team_ids.iter().flatten().cloned().collect();
+                        if current != ids {
+                            diff_field(
+                                &mut fields,
+                                "secondary teams",
+                                Some(&team_names(directory, current.iter())),
+                                Some(&team_names(directory, ids.iter())),
+                            );
+                            request.secondary_team_ids = Some(ids.into_iter().collect());
+                        }
+                    }
+
+                    if fields.is_empty() {
+                        plan.unchanged.push(live.email.clone());
+                    } else {
+                        plan.changes.push(Change::Update {
+                            user_id: live.id.clone(),
+                            email: live.email.clone(),
+                            request,
+                            fields,
+                        });
+                    }
+                }
+            }
+        }
+
+        if options.remove_unlisted {
+            let keep: BTreeSet<String> = options.keep.iter().map(|e| e.to_lowercase()).collect();
+            for user in live {
+                let email = user.email.to_lowercase();
+                if seen.contains(&email) {
+                    continue;
+                }
+                if user.super_admin.unwrap_or(false) || keep.contains(&email) {
+                    plan.kept.push(user.email.clone());
+                } else {
+                    plan.changes.push(Change::Remove {
+                        user_id: user.id.clone(),
+                        email: user.email.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// Whether the portal already matches.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl std::fmt::Display for Plan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for change in &self.changes {
+            let (sign, fields) = match change {
+                Change::Create { fields, .. } => ("+", fields.as_slice()),
+                Change::Update { fields, .. } => ("~", fields.as_slice()),
+                Change::Remove { .. } => ("-", [].as_slice()),
+            };
+            writeln!(f, "{} {}", sign, change.email())?;
+            for field in fields {
+                if field.from.is_empty() {
+                    writeln!(f, "    {}: {}", field.field, field.to)?;
+                } else {
+                    writeln!(f, "    {}: {} -> {}", field.field, field.from, field.to)?;
+                }
+            }
+        }
+        write!(
+            f,
+            "{} to create, {} to update, {} to remove, {} unchanged",
+            self.changes
+                .iter()
+                .filter(|c| matches!(c, Change::Create { .. }))
+                .count(),
+            self.changes
+                .iter()
+                .filter(|c| matches!(c, Change::Update { .. }))
+                .count(),
+            self.changes
+                .iter()
+                .filter(|c| matches!(c, Change::Remove { .. }))
+                .count(),
+            self.unchanged.len()
+        )
+    }
+}
+
+/// Record a field change when the values differ. Returns whether they differ.
+fn diff_field(
+    fields: &mut Vec<FieldChange>,
+    field: &'static str,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> bool {
+    if from == to {
+        return false;
+    }
+    fields.push(FieldChange {
+        field,
+        from: from.unwrap_or_default().to_string(),
+        to: to.unwrap_or_default().to_string(),
+    });
+    true
+}
+
+fn team_names<'a>(directory: &Directory, ids: impl Iterator<Item = &'a String>) -> String {
+    itertools::join(ids.map(|id| directory.team_name(id)), ", ")
+}
+
+/// The result of applying one change of a [`Plan`].
+#[derive(Debug)]
+pub struct ApplyOutcome {
+    pub change: Change,
+    /// The created or updated user, `None` for removals.
+    pub result: Result<Option<PublicUser>, crate::types::error::Error>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Reconcile {
+    pub client: Client,
+}
+
+impl Reconcile {
+    #[doc(hidden)]
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    /// Read the roles and teams of the portal.
+    pub async fn directory(&self) -> Result<Directory, crate::types::error::Error> {
+        Ok(Directory {
+            roles: self
+                .client
+                .roles()
+                .get_settings_v_3_users_get_all()
+                .await?
+                .results,
+            teams: self
+                .client
+                .teams()
+                .get_settings_v_3_users_get_all()
+                .await?
+                .results,
+        })
+    }
+
+    /// Read all users of the portal.
+    pub async fn live_users(&self) -> Result<Vec<PublicUser>, crate::types::error::Error> {
+        let mut users = vec![];
+        let mut after = None;
+        loop {
+            let page = self
+                .client
+                .users()
+                .get_settings_v_3_get_page(after, Some(100))
+                .await?;
+            users.extend(page.results);
+            after = page.paging.and_then(|p| p.next).map(|n| n.after);
+            if after.is_none() {
+                return Ok(users);
+            }
+        }
+    }
+
+    /// Compare the desired users with the live portal without changing it.
+    pub async fn plan(
+        &self,
+        desired: &[DesiredUser],
+        options: &ReconcileOptions,
+    ) -> Result<Plan, crate::types::error::Error> {
+        let directory = self.directory().await?;
+        let live = self.live_users().await?;
+        Plan::compute(desired, &live, &directory, options)
+    }
+
+    /// Carry out the changes of a plan in order.
+    ///
+    /// A failed change does not stop the remaining ones; every change has an
+    /// outcome.
+    pub async fn apply(&self, plan: &Plan) -> Vec<ApplyOutcome> {
+        let mut outcomes = vec![];
+        for change in &plan.changes {
+            let users = self.client.users();
+            let result = match change {
+                Change::Create { request, .. } => {
+                    users.post_settings_v_3_create(request).await.map(Some)
+                }
+                Change::Update {
+                    user_id, request, ..
+                } => users
+                    .put_settings_v_3_id_replace(None, user_id, request)
+                    .await
+                    .map(Some),
+                Change::Remove { user_id, .. } => users
+                    .delete_settings_v_3_id_archive(None, user_id)
+                    .await
+                    .map(|()| None),
+            };
+            outcomes.push(ApplyOutcome {
+                change: change.clone(),
+                result,
+            });
+        }
+        outcomes
+    }
+}
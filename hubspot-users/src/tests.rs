@@ -1 +1,66 @@
+use pretty_assertions::assert_eq;
 
+#[test]
+fn test_reconcile_plan() {
+    use crate::reconcile::{Change, DesiredUser, Directory, Plan, ReconcileOptions};
+
+    let directory: Directory = Directory {
+        roles: serde_json::from_str(
+            r#"[{"id": "10", "name": "Sales Rep", "requiresBillingWrite": false},
+                {"id": "11", "name": "Support", "requiresBillingWrite": false}]"#,
+        )
+        .unwrap(),
+        teams: serde_json::from_str(
+            r#"[{"id": "20", "name": "EMEA", "userIds": [], "secondaryUserIds": []},
+                {"id": "21", "name": "APAC", "userIds": [], "secondaryUserIds": []}]"#,
+        )
+        .unwrap(),
+    };
+    let live: Vec<crate::types::PublicUser> = serde_json::from_str(
+        r#"[{"id": "1", "email": "Bob@example.com", "roleId": "10", "primaryTeamId": "20"},
+            {"id": "2", "email": "carol@example.com", "roleId": "11"},
+            {"id": "3", "email": "admin@example.com", "superAdmin": true}]"#,
+    )
+    .unwrap();
+    let desired = vec![
+        DesiredUser {
+            email: "alice@example.com".to_string(),
+            role: Some("sales rep".to_string()),
+            ..Default::default()
+        },
+        DesiredUser {
+            email: "bob@example.com".to_string(),
+            role: Some("Support".to_string()),
+            primary_team: Some("EMEA".to_string()),
+            ..Default::default()
+        },
+    ];
+    let options = ReconcileOptions {
+        remove_unlisted: true,
+        ..Default::default()
+    };
+
+    let plan = Plan::compute(&desired, &live, &directory, &options).unwrap();
+    assert_eq!(
+        plan.changes.iter().map(|c| c.email()).collect::<Vec<_>>(),
+        vec!["alice@example.com", "Bob@example.com", "carol@example.com"]
+    );
+    assert!(matches!(&plan.changes[2], Change::Remove { user_id, .. } if user_id == "2"));
+    assert_eq!(plan.kept, vec!["admin@example.com".to_string()]);
+    assert_eq!(
+        plan.to_string(),
+        "+ alice@example.com
+    role: Sales Rep
+~ Bob@example.com
+    role: Sales Rep -> Support
+- carol@example.com
+1 to create, 1 to update, 1 to remove, 0 unchanged"
+    );
+
+    let unknown = vec![DesiredUser {
+        email: "dave@example.com".to_string(),
+        primary_team: Some("Americas".to_string()),
+        ..Default::default()
+    }];
+    assert!(Plan::compute(&unknown, &live, &directory, &options).is_err());
+}